@@ -0,0 +1,175 @@
+#[cfg(not(feature = "library"))]
+use cosmwasm_std::entry_point;
+
+use cosmwasm_bignumber::Decimal256;
+use cosmwasm_std::{
+    attr, to_binary, Binary, Deps, DepsMut, Env, MessageInfo, Response, StdError, StdResult,
+};
+use moneymarket::distribution_model::{
+    AncEmissionRateResponse, ConfigResponse, ExecuteMsg, InstantiateMsg, QueryMsg,
+};
+
+use crate::state::{read_config, store_config, Config};
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn instantiate(
+    deps: DepsMut,
+    _env: Env,
+    _info: MessageInfo,
+    msg: InstantiateMsg,
+) -> StdResult<Response> {
+    if msg.emission_floor > msg.emission_cap {
+        return Err(StdError::generic_err(
+            "emission_floor can not exceed emission_cap",
+        ));
+    }
+
+    store_config(
+        deps.storage,
+        &Config {
+            owner: deps.api.addr_canonicalize(&msg.owner)?,
+            emission_cap: msg.emission_cap,
+            emission_floor: msg.emission_floor,
+            increment_multiplier: msg.increment_multiplier,
+            decrement_multiplier: msg.decrement_multiplier,
+        },
+    )?;
+
+    Ok(Response::default())
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn execute(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    msg: ExecuteMsg,
+) -> StdResult<Response> {
+    match msg {
+        ExecuteMsg::UpdateConfig {
+            owner,
+            emission_cap,
+            emission_floor,
+            increment_multiplier,
+            decrement_multiplier,
+        } => update_config(
+            deps,
+            info,
+            owner,
+            emission_cap,
+            emission_floor,
+            increment_multiplier,
+            decrement_multiplier,
+        ),
+    }
+}
+
+pub fn update_config(
+    deps: DepsMut,
+    info: MessageInfo,
+    owner: Option<String>,
+    emission_cap: Option<Decimal256>,
+    emission_floor: Option<Decimal256>,
+    increment_multiplier: Option<Decimal256>,
+    decrement_multiplier: Option<Decimal256>,
+) -> StdResult<Response> {
+    let mut config: Config = read_config(deps.storage)?;
+    if deps.api.addr_canonicalize(info.sender.as_str())? != config.owner {
+        return Err(StdError::generic_err("unauthorized"));
+    }
+
+    if let Some(owner) = owner {
+        config.owner = deps.api.addr_canonicalize(&owner)?;
+    }
+
+    if let Some(emission_cap) = emission_cap {
+        config.emission_cap = emission_cap;
+    }
+
+    if let Some(emission_floor) = emission_floor {
+        config.emission_floor = emission_floor;
+    }
+
+    if let Some(increment_multiplier) = increment_multiplier {
+        config.increment_multiplier = increment_multiplier;
+    }
+
+    if let Some(decrement_multiplier) = decrement_multiplier {
+        config.decrement_multiplier = decrement_multiplier;
+    }
+
+    if config.emission_floor > config.emission_cap {
+        return Err(StdError::generic_err(
+            "emission_floor can not exceed emission_cap",
+        ));
+    }
+
+    store_config(deps.storage, &config)?;
+    Ok(Response::new().add_attributes(vec![attr("action", "update_config")]))
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
+    match msg {
+        QueryMsg::Config {} => to_binary(&query_config(deps)?),
+        QueryMsg::AncEmissionRate {
+            deposit_rate,
+            target_deposit_rate,
+            threshold_deposit_rate,
+            current_emission_rate,
+        } => to_binary(&query_anc_emission_rate(
+            deps,
+            deposit_rate,
+            target_deposit_rate,
+            threshold_deposit_rate,
+            current_emission_rate,
+        )?),
+    }
+}
+
+pub fn query_config(deps: Deps) -> StdResult<ConfigResponse> {
+    let config: Config = read_config(deps.storage)?;
+    Ok(ConfigResponse {
+        owner: deps.api.addr_humanize(&config.owner)?.to_string(),
+        emission_cap: config.emission_cap,
+        emission_floor: config.emission_floor,
+        increment_multiplier: config.increment_multiplier,
+        decrement_multiplier: config.decrement_multiplier,
+    })
+}
+
+/// Raises the emission rate while the deposit rate sits in the lower half
+/// between threshold and target, lowers it in the upper half,
+/// clamped between the floor and the cap
+pub fn query_anc_emission_rate(
+    deps: Deps,
+    deposit_rate: Decimal256,
+    target_deposit_rate: Decimal256,
+    threshold_deposit_rate: Decimal256,
+    current_emission_rate: Decimal256,
+) -> StdResult<AncEmissionRateResponse> {
+    let config: Config = read_config(deps.storage)?;
+
+    let two = Decimal256::one() + Decimal256::one();
+    let mid_rate = (threshold_deposit_rate + target_deposit_rate) / two;
+    let high_trigger = (mid_rate + target_deposit_rate) / two;
+    let low_trigger = (mid_rate + threshold_deposit_rate) / two;
+
+    let emission_rate = if deposit_rate < low_trigger {
+        current_emission_rate * config.increment_multiplier
+    } else if deposit_rate > high_trigger {
+        current_emission_rate * config.decrement_multiplier
+    } else {
+        current_emission_rate
+    };
+
+    let emission_rate = if emission_rate > config.emission_cap {
+        config.emission_cap
+    } else if emission_rate < config.emission_floor {
+        config.emission_floor
+    } else {
+        emission_rate
+    };
+
+    Ok(AncEmissionRateResponse { emission_rate })
+}
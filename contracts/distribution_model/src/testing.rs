@@ -0,0 +1,76 @@
+use crate::contract::{instantiate, query_anc_emission_rate};
+
+use cosmwasm_bignumber::Decimal256;
+use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info};
+use moneymarket::distribution_model::InstantiateMsg;
+use std::str::FromStr;
+
+#[test]
+fn emission_rate_follows_deposit_rate() {
+    let mut deps = mock_dependencies(&[]);
+    let msg = InstantiateMsg {
+        owner: "owner0000".to_string(),
+        emission_cap: Decimal256::from_uint256(100u64),
+        emission_floor: Decimal256::from_uint256(10u64),
+        increment_multiplier: Decimal256::from_str("1.1").unwrap(),
+        decrement_multiplier: Decimal256::from_str("0.9").unwrap(),
+    };
+    instantiate(deps.as_mut(), mock_env(), mock_info("addr0000", &[]), msg).unwrap();
+
+    let target = Decimal256::from_str("0.02").unwrap();
+    let threshold = Decimal256::from_str("0.01").unwrap();
+    let current = Decimal256::from_uint256(50u64);
+
+    // below the low trigger
+    let res = query_anc_emission_rate(
+        deps.as_ref(),
+        Decimal256::from_str("0.011").unwrap(),
+        target,
+        threshold,
+        current,
+    )
+    .unwrap();
+    assert_eq!(res.emission_rate, Decimal256::from_uint256(55u64));
+
+    // above the high trigger
+    let res = query_anc_emission_rate(
+        deps.as_ref(),
+        Decimal256::from_str("0.019").unwrap(),
+        target,
+        threshold,
+        current,
+    )
+    .unwrap();
+    assert_eq!(res.emission_rate, Decimal256::from_uint256(45u64));
+
+    // between the triggers
+    let res = query_anc_emission_rate(
+        deps.as_ref(),
+        Decimal256::from_str("0.015").unwrap(),
+        target,
+        threshold,
+        current,
+    )
+    .unwrap();
+    assert_eq!(res.emission_rate, current);
+
+    // clamped by the cap and the floor
+    let res = query_anc_emission_rate(
+        deps.as_ref(),
+        Decimal256::zero(),
+        target,
+        threshold,
+        Decimal256::from_uint256(95u64),
+    )
+    .unwrap();
+    assert_eq!(res.emission_rate, Decimal256::from_uint256(100u64));
+    let res = query_anc_emission_rate(
+        deps.as_ref(),
+        Decimal256::one(),
+        target,
+        threshold,
+        Decimal256::from_uint256(10u64),
+    )
+    .unwrap();
+    assert_eq!(res.emission_rate, Decimal256::from_uint256(10u64));
+}
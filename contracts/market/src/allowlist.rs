@@ -0,0 +1,68 @@
+use cosmwasm_std::{attr, Addr, Deps, DepsMut, MessageInfo, Response};
+
+use crate::error::ContractError;
+use crate::querier::query_is_member;
+use crate::state::{is_allowlisted, read_config, remove_allowlisted, store_allowlisted, Config};
+
+pub fn add_to_allowlist(
+    deps: DepsMut,
+    info: MessageInfo,
+    addresses: Vec<String>,
+) -> Result<Response, ContractError> {
+    let config: Config = read_config(deps.storage)?;
+    if deps.api.addr_canonicalize(info.sender.as_str())? != config.owner_addr {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    for address in addresses.iter() {
+        let ident_raw = deps.api.addr_canonicalize(address)?;
+        store_allowlisted(deps.storage, &ident_raw)?;
+    }
+
+    Ok(Response::new().add_attributes(vec![
+        attr("action", "add_to_allowlist"),
+        attr("addresses", addresses.join(",")),
+    ]))
+}
+
+pub fn remove_from_allowlist(
+    deps: DepsMut,
+    info: MessageInfo,
+    addresses: Vec<String>,
+) -> Result<Response, ContractError> {
+    let config: Config = read_config(deps.storage)?;
+    if deps.api.addr_canonicalize(info.sender.as_str())? != config.owner_addr {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    for address in addresses.iter() {
+        let ident_raw = deps.api.addr_canonicalize(address)?;
+        remove_allowlisted(deps.storage, &ident_raw);
+    }
+
+    Ok(Response::new().add_attributes(vec![
+        attr("action", "remove_from_allowlist"),
+        attr("addresses", addresses.join(",")),
+    ]))
+}
+
+/// Permissioned markets only accept depositors on the allowlist or
+/// approved by the membership contract
+pub fn assert_allowlisted(deps: Deps, config: &Config, ident: &Addr) -> Result<(), ContractError> {
+    if !config.permissioned {
+        return Ok(());
+    }
+
+    if is_allowlisted(deps.storage, &deps.api.addr_canonicalize(ident.as_str())?) {
+        return Ok(());
+    }
+
+    if let Some(membership_contract) = &config.membership_contract {
+        let membership_contract = deps.api.addr_humanize(membership_contract)?;
+        if query_is_member(deps, membership_contract, ident)? {
+            return Ok(());
+        }
+    }
+
+    Err(ContractError::NotAllowlisted(ident.to_string()))
+}
@@ -0,0 +1,232 @@
+use std::cmp::{max, min};
+
+use cosmwasm_std::{
+    attr, to_binary, Addr, CanonicalAddr, Decimal, Deps, DepsMut, Env, MessageInfo, Response,
+    StdResult, Storage, Uint128, WasmMsg,
+};
+use cw20::Cw20ExecuteMsg;
+
+use crate::error::ContractError;
+use crate::rewards::accrue_rewards;
+use crate::state::{
+    read_config, read_deposit_info, read_lock, read_state, remove_lock, store_deposit_info,
+    store_lock, store_state, BoostInfoResponse, Config, DepositInfo, LockInfo, State,
+};
+
+/// Voting power of `lock` at `time`, decaying linearly to zero at its end
+pub fn voting_power(config: &Config, lock: &LockInfo, time: u64) -> Uint128 {
+    if lock.end <= time || config.max_lock_duration == 0 {
+        return Uint128::zero();
+    }
+    let remaining = min(lock.end - time, config.max_lock_duration);
+    lock.amount
+        .multiply_ratio(remaining, config.max_lock_duration)
+}
+
+/// `balance` raised by up to `max_boost` in proportion to the voting power
+/// over the `balance * boost_ratio` needed for the full boost
+pub fn working_balance(config: &Config, lock: &LockInfo, balance: Uint128, time: u64) -> Uint128 {
+    let power = voting_power(config, lock, time);
+    let max_extra = balance * (config.max_boost - Decimal::one());
+    if power.is_zero() || max_extra.is_zero() {
+        return balance;
+    }
+
+    let required = balance * config.boost_ratio;
+    let extra = if power >= required {
+        max_extra
+    } else {
+        max_extra.multiply_ratio(power, required)
+    };
+    balance + extra
+}
+
+/// Recalculates the working balance of `deposit` and the total. Rewards
+/// must be accrued first and the deposit stored afterwards.
+pub fn update_working_balance(
+    storage: &mut dyn Storage,
+    config: &Config,
+    ident: &CanonicalAddr,
+    deposit: &mut DepositInfo,
+    time: u64,
+) -> StdResult<()> {
+    let lock = read_lock(storage, ident);
+    let working = working_balance(config, &lock, deposit.last_balance, time);
+
+    let mut state: State = read_state(storage)?;
+    state.total_working_balance = state.total_working_balance + working - deposit.working_balance;
+    store_state(storage, &state)?;
+
+    deposit.working_balance = working;
+    Ok(())
+}
+
+/// Accrues rewards at the current working balance of `ident` before
+/// recalculating it after a lock change
+fn refresh_boost(
+    storage: &mut dyn Storage,
+    config: &Config,
+    ident: &CanonicalAddr,
+    time: u64,
+) -> StdResult<Uint128> {
+    let mut deposit = read_deposit_info(storage, ident)?;
+    if deposit.initial_interaction == 0 {
+        return Ok(Uint128::zero());
+    }
+
+    accrue_rewards(storage, config, ident, &mut deposit, time)?;
+    update_working_balance(storage, config, ident, &mut deposit, time)?;
+    store_deposit_info(storage, ident, &deposit)?;
+    Ok(deposit.working_balance)
+}
+
+/// Locks `amount` governance tokens sent by `sender` until `duration`
+/// seconds from now, or the existing lock end if later
+pub fn lock(
+    deps: DepsMut,
+    env: Env,
+    sender: Addr,
+    amount: Uint128,
+    duration: u64,
+) -> Result<Response, ContractError> {
+    let config: Config = read_config(deps.storage)?;
+    if duration == 0 || duration > config.max_lock_duration {
+        return Err(ContractError::InvalidLockDuration(config.max_lock_duration));
+    }
+
+    let time = env.block.time.seconds();
+    let sender_raw = deps.api.addr_canonicalize(sender.as_str())?;
+    let mut lock = read_lock(deps.storage, &sender_raw);
+    lock.amount += amount;
+    lock.end = max(lock.end, time + duration);
+    store_lock(deps.storage, &sender_raw, &lock)?;
+
+    let mut state: State = read_state(deps.storage)?;
+    state.total_locked += amount;
+    store_state(deps.storage, &state)?;
+
+    let working_balance = refresh_boost(deps.storage, &config, &sender_raw, time)?;
+
+    Ok(Response::new().add_attributes(vec![
+        attr("action", "lock"),
+        attr("locker", sender),
+        attr("amount", amount),
+        attr("lock_end", lock.end.to_string()),
+        attr("working_balance", working_balance),
+    ]))
+}
+
+pub fn extend_lock(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    duration: u64,
+) -> Result<Response, ContractError> {
+    let config: Config = read_config(deps.storage)?;
+    let time = env.block.time.seconds();
+    let sender_raw = deps.api.addr_canonicalize(info.sender.as_str())?;
+    let mut lock = read_lock(deps.storage, &sender_raw);
+    if lock.amount.is_zero() {
+        return Err(ContractError::NoLock {});
+    }
+    if duration > config.max_lock_duration || time + duration <= lock.end {
+        return Err(ContractError::InvalidLockDuration(config.max_lock_duration));
+    }
+
+    lock.end = time + duration;
+    store_lock(deps.storage, &sender_raw, &lock)?;
+    let working_balance = refresh_boost(deps.storage, &config, &sender_raw, time)?;
+
+    Ok(Response::new().add_attributes(vec![
+        attr("action", "extend_lock"),
+        attr("locker", info.sender),
+        attr("lock_end", lock.end.to_string()),
+        attr("working_balance", working_balance),
+    ]))
+}
+
+pub fn unlock(deps: DepsMut, env: Env, info: MessageInfo) -> Result<Response, ContractError> {
+    let config: Config = read_config(deps.storage)?;
+    let governance_token = match &config.governance_token {
+        Some(governance_token) => deps.api.addr_humanize(governance_token)?,
+        None => return Err(ContractError::BoostDisabled {}),
+    };
+
+    let time = env.block.time.seconds();
+    let sender_raw = deps.api.addr_canonicalize(info.sender.as_str())?;
+    let lock = read_lock(deps.storage, &sender_raw);
+    if lock.amount.is_zero() {
+        return Err(ContractError::NoLock {});
+    }
+    if time < lock.end {
+        return Err(ContractError::LockNotExpired(lock.end));
+    }
+
+    remove_lock(deps.storage, &sender_raw);
+    let mut state: State = read_state(deps.storage)?;
+    state.total_locked -= lock.amount;
+    store_state(deps.storage, &state)?;
+    let working_balance = refresh_boost(deps.storage, &config, &sender_raw, time)?;
+
+    Ok(Response::new()
+        .add_message(WasmMsg::Execute {
+            contract_addr: governance_token.to_string(),
+            funds: vec![],
+            msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                recipient: info.sender.to_string(),
+                amount: lock.amount,
+            })?,
+        })
+        .add_attributes(vec![
+            attr("action", "unlock"),
+            attr("locker", info.sender),
+            attr("amount", lock.amount),
+            attr("working_balance", working_balance),
+        ]))
+}
+
+/// Updates the working balance of `address` to its decayed boost
+pub fn kick(deps: DepsMut, env: Env, address: String) -> Result<Response, ContractError> {
+    let config: Config = read_config(deps.storage)?;
+    let address = deps.api.addr_validate(&address)?;
+    let address_raw = deps.api.addr_canonicalize(address.as_str())?;
+    let working_balance = refresh_boost(
+        deps.storage,
+        &config,
+        &address_raw,
+        env.block.time.seconds(),
+    )?;
+
+    Ok(Response::new().add_attributes(vec![
+        attr("action", "kick"),
+        attr("address", address),
+        attr("working_balance", working_balance),
+    ]))
+}
+
+pub fn query_boost_info(deps: Deps, env: Env, address: String) -> StdResult<BoostInfoResponse> {
+    let config: Config = read_config(deps.storage)?;
+    let state: State = read_state(deps.storage)?;
+    let time = env.block.time.seconds();
+    let address_raw = deps.api.addr_canonicalize(&address)?;
+    let lock = read_lock(deps.storage, &address_raw);
+    let deposit = read_deposit_info(deps.storage, &address_raw)?;
+
+    let boost = if deposit.last_balance.is_zero() {
+        Decimal::one()
+    } else {
+        Decimal::from_ratio(
+            working_balance(&config, &lock, deposit.last_balance, time),
+            deposit.last_balance,
+        )
+    };
+
+    Ok(BoostInfoResponse {
+        locked_amount: lock.amount,
+        lock_end: lock.end,
+        voting_power: voting_power(&config, &lock, time),
+        boost,
+        working_balance: deposit.working_balance,
+        total_working_balance: state.total_working_balance,
+    })
+}
@@ -1,24 +1,64 @@
 #[cfg(not(feature = "library"))]
 use cosmwasm_std::entry_point;
 
-use crate::deposit::{deposit_stable, redeem_all_stable, redeem_n_stable};
+use crate::allowlist::{add_to_allowlist, remove_from_allowlist};
+use crate::boost::{extend_lock, kick, lock, query_boost_info, unlock};
+use crate::deposit::{
+    deposit_stable, redeem_all_stable, redeem_n_stable, set_interest_payout, settle, settle_range,
+};
+use crate::epoch::execute_epoch_operations;
 use crate::error::ContractError;
+use crate::harvest::{harvest, query_harvest};
 use crate::helpers::{calculate_accrued_interest, get_decimals};
+use crate::prize::{
+    award_prizes, claim_prize, commit_randomness, deregister_operator, draw_prizes, query_draw,
+    query_operators, query_prize, register_operator, reveal_randomness, MAX_PRIZE_WINNERS,
+};
+use crate::queue::{claim_queued, fund_reserve, query_withdrawal_queue};
+use crate::redirect::{
+    claim_redirected_interest, query_incoming_redirects, query_interest_redirect,
+    set_interest_redirect,
+};
+use crate::referral::{claim_referral_rewards, query_referral};
 use crate::response::MsgInstantiateContractResponse;
+use crate::rewards::{claim_rewards, query_pending_rewards, update_reward_index};
+use crate::slashing::reconcile;
+use crate::split::{
+    add_maturity, cancel_recombine, query_maturities, query_maturity, recombine, redeem_split,
+    register_maturity_token, split,
+};
+use crate::staking::{
+    query_validators, rebalance, register_validator, remove_validator, update_validator_weight,
+};
 use crate::state::{
-    read_config, read_deposit_info, read_state, read_tvl_indice, read_tvl_indices, store_config,
-    store_state, store_tvl_indice, Config, ConfigResponse, DepositInfo, InstantiateMsg, MigrateMsg,
-    QueryMsg, State, Tvl,
+    read_allowlist, read_config, read_deposit_info, read_state, read_tvl_indice, read_tvl_indices,
+    store_config, store_state, store_tvl_indice, AllowlistResponse, Config, ConfigResponse,
+    DepositInfo, InstantiateMsg, MigrateMsg, QueryMsg, State, Tvl, Twab,
+    DEFAULT_CHAIN_UNBONDING_PERIOD, DEFAULT_MAX_LOCK_DURATION,
+};
+use crate::strategy::{
+    add_strategy, allocate, query_asset_breakdown, query_strategies, remove_strategy, skim_yield,
+    update_strategy_weight, withdraw_strategy,
+};
+use crate::streams::{
+    add_sponsors, claim_stream_rewards, close_stream, create_native_stream, create_stream,
+    query_pending_stream_rewards, query_streams, remove_sponsors,
 };
+use crate::swap::{query_swap_routes, remove_swap_route, set_swap_route, sweep};
+use crate::tranche::{
+    deposit_junior, enable_junior_tranche, query_tranches, redeem_junior, register_junior_token,
+};
+use crate::unbonding::{claim_unbonded, query_unbonding};
 
 use cosmwasm_std::{
-    attr, from_binary, to_binary, Addr, Binary, CanonicalAddr, CosmosMsg, Deps, DepsMut, Env,
-    MessageInfo, Reply, Response, StdError, StdResult, SubMsg, Uint128, WasmMsg,
+    attr, from_binary, to_binary, Addr, Binary, CanonicalAddr, CosmosMsg, Decimal, Deps, DepsMut,
+    Env, MessageInfo, Reply, Response, StdError, StdResult, SubMsg, Uint128, WasmMsg,
 };
 use cw20::{Cw20Coin, Cw20ReceiveMsg, MinterResponse};
 
 use crate::state::{Cw20HookMsg, ExecuteMsg};
 use protobuf::Message;
+use terraswap::asset::{Asset, AssetInfo};
 use terraswap::token::InstantiateMsg as TokenInstantiateMsg;
 
 // TODO: CHANGE TO 24 HOURS
@@ -26,8 +66,32 @@ pub const DURATION: u64 = 30;
 pub const INITIAL_DEPOSIT_AMOUNT: u128 = 1000000;
 
 #[cfg_attr(not(feature = "library"), entry_point)]
-pub fn migrate(_deps: DepsMut, _env: Env, _msg: MigrateMsg) -> StdResult<Response> {
-    Ok(Response::default())
+pub fn migrate(deps: DepsMut, env: Env, msg: MigrateMsg) -> StdResult<Response> {
+    // fields missing from older versions decode to their defaults,
+    // rewrite them with the values needing the upgrade time or an owner
+    let time = env.block.time.seconds();
+    let mut config: Config = read_config(deps.storage)?;
+    if let Some(owner_addr) = msg.owner_addr {
+        config.owner_addr = deps.api.addr_canonicalize(&owner_addr)?;
+    }
+    if config.owner_addr == CanonicalAddr::from(vec![]) {
+        return Err(StdError::generic_err("owner_addr is required to migrate"));
+    }
+    if config.draw_start == 0 {
+        config.draw_start = time;
+    }
+    store_config(deps.storage, &config)?;
+
+    let mut state: State = read_state(deps.storage)?;
+    if state.last_reward_updated == 0 {
+        state.last_reward_updated = time;
+    }
+    if state.total_twab.updated == 0 {
+        state.total_twab.updated = time;
+    }
+    store_state(deps.storage, &state)?;
+
+    Ok(Response::new().add_attributes(vec![attr("action", "migrate")]))
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
@@ -40,7 +104,7 @@ pub fn instantiate(
     let initial_deposit = info
         .funds
         .iter()
-        .find(|c| c.denom == "uusd".to_string())
+        .find(|c| c.denom == "uusd")
         .map(|c| c.amount)
         .unwrap_or_else(Uint128::zero);
 
@@ -51,13 +115,113 @@ pub fn instantiate(
         ));
     }
 
+    let liquidity_buffer = msg.liquidity_buffer.unwrap_or_else(Decimal::zero);
+    if liquidity_buffer > Decimal::one() {
+        return Err(ContractError::InvalidLiquidityBuffer {});
+    }
+
+    let harvest_bounty = msg.harvest_bounty.unwrap_or_else(Decimal::zero);
+    if harvest_bounty > Decimal::one() {
+        return Err(ContractError::InvalidHarvestBounty {});
+    }
+
+    // a bounty paid on every call would drain the reserve
+    let epoch_period = msg.epoch_period.unwrap_or(0);
+    let epoch_bounty = msg.epoch_bounty.unwrap_or_else(Uint128::zero);
+    if !epoch_bounty.is_zero() && epoch_period == 0 {
+        return Err(ContractError::InvalidEpochPeriod {});
+    }
+
+    let max_boost = msg.max_boost.unwrap_or_else(Decimal::one);
+    if max_boost < Decimal::one() {
+        return Err(ContractError::InvalidMaxBoost {});
+    }
+
+    let referral_share = msg.referral_share.unwrap_or_else(Decimal::zero);
+    if referral_share > Decimal::one() {
+        return Err(ContractError::InvalidReferralShare {});
+    }
+
+    let prize_mode = msg.prize_mode.unwrap_or(false);
+    let draw_period = msg.draw_period.unwrap_or(0);
+    let reveal_period = msg.reveal_period.unwrap_or(0);
+    let prize_winners = msg.prize_winners.unwrap_or(1);
+    if prize_mode
+        && (draw_period <= reveal_period || prize_winners == 0 || prize_winners > MAX_PRIZE_WINNERS)
+    {
+        return Err(ContractError::InvalidDrawSchedule {});
+    }
+
+    let native_staking = msg.native_staking.unwrap_or(false);
+    let unbonding_period = msg.unbonding_period.unwrap_or(0);
+    let chain_unbonding_period = msg
+        .chain_unbonding_period
+        .unwrap_or(DEFAULT_CHAIN_UNBONDING_PERIOD);
+    if native_staking {
+        let bonded_denom = deps.querier.query_bonded_denom()?;
+        if bonded_denom != msg.stable_denom || unbonding_period == 0 {
+            return Err(ContractError::InvalidNativeStaking(bonded_denom));
+        }
+        // redemptions can not be paid before the undelegations complete,
+        // which start at the next epoch
+        if unbonding_period < chain_unbonding_period + epoch_period {
+            return Err(ContractError::UnbondingPeriodTooShort(
+                chain_unbonding_period + epoch_period,
+            ));
+        }
+    }
+
     store_config(
         deps.storage,
         &Config {
             contract_addr: deps.api.addr_canonicalize(env.contract.address.as_str())?,
+            owner_addr: deps.api.addr_canonicalize(&msg.owner_addr)?,
             aterra_contract: CanonicalAddr::from(vec![]),
             stable_denom: msg.stable_denom.clone(),
             interest_rate: get_decimals(msg.interest)?,
+            tvl_cap: msg.tvl_cap.unwrap_or_else(Uint128::zero),
+            max_balance: msg.max_balance.unwrap_or_else(Uint128::zero),
+            min_deposit: msg.min_deposit.unwrap_or_else(Uint128::zero),
+            permissioned: msg.permissioned.unwrap_or(false),
+            membership_contract: match msg.membership_contract {
+                Some(membership_contract) => {
+                    Some(deps.api.addr_canonicalize(&membership_contract)?)
+                }
+                None => None,
+            },
+            unbonding_period,
+            native_staking,
+            chain_unbonding_period,
+            harvest_bounty,
+            max_rebalance: msg.max_rebalance.unwrap_or_else(Uint128::zero),
+            liquidity_buffer,
+            epoch_period,
+            epoch_bounty,
+            reward_token: match msg.reward_token {
+                Some(reward_token) => Some(deps.api.addr_canonicalize(&reward_token)?),
+                None => None,
+            },
+            emission_rate: msg.emission_rate.unwrap_or_else(Decimal::zero),
+            distribution_model: match msg.distribution_model {
+                Some(distribution_model) => Some(deps.api.addr_canonicalize(&distribution_model)?),
+                None => None,
+            },
+            target_deposit_rate: msg.target_deposit_rate.unwrap_or_else(Decimal::zero),
+            threshold_deposit_rate: msg.threshold_deposit_rate.unwrap_or_else(Decimal::zero),
+            governance_token: match msg.governance_token {
+                Some(governance_token) => Some(deps.api.addr_canonicalize(&governance_token)?),
+                None => None,
+            },
+            max_lock_duration: msg.max_lock_duration.unwrap_or(DEFAULT_MAX_LOCK_DURATION),
+            max_boost,
+            boost_ratio: msg.boost_ratio.unwrap_or_else(Decimal::one),
+            referral_share,
+            prize_mode,
+            draw_start: env.block.time.seconds(),
+            draw_period,
+            reveal_period,
+            prize_winners,
+            junior_token: None,
         },
     )?;
 
@@ -67,10 +231,46 @@ pub fn instantiate(
             tvl: Uint128::zero(),
             tvl_indices: 0,
             accrued_interest_payments: Uint128::zero(),
+            exchange_rate: Decimal::one(),
+            last_harvest_time: 0,
+            last_harvest_amount: Uint128::zero(),
+            total_harvested: Uint128::zero(),
+            reserve: Uint128::zero(),
+            queue_head: 0,
+            queue_tail: 0,
+            total_queued: Uint128::zero(),
+            total_claimable: Uint128::zero(),
+            total_unbonding: Uint128::zero(),
+            total_delegated: Uint128::zero(),
+            pending_undelegation: Uint128::zero(),
+            withdrawn_rewards: Uint128::zero(),
+            total_slashed: Uint128::zero(),
+            global_reward_index: Decimal::zero(),
+            last_reward_updated: env.block.time.seconds(),
+            total_working_balance: Uint128::zero(),
+            total_locked: Uint128::zero(),
+            total_referral_pending: Uint128::zero(),
+            total_redirect_claimable: Uint128::zero(),
+            total_unpaid_interest: Uint128::zero(),
+            total_twab: Twab {
+                updated: env.block.time.seconds(),
+                ..Twab::default()
+            },
+            drawn_twab: Uint128::zero(),
+            prize_rollover: Uint128::zero(),
+            total_prizes_claimable: Uint128::zero(),
+            next_stream_id: 0,
+            last_epoch_time: 0,
+            last_epoch_exchange_rate: Decimal::one(),
+            yield_checkpoint_time: env.block.time.seconds(),
+            total_enqueued: Uint128::zero(),
+            total_filled: Uint128::zero(),
+            junior_supply: Uint128::zero(),
+            junior_exchange_rate: Decimal::one(),
         },
     )?;
 
-    let _tvl_store = store_tvl_indice(
+    store_tvl_indice(
         deps.storage,
         &mut Tvl {
             tvl: Uint128::zero(),
@@ -118,33 +318,186 @@ pub fn execute(
 ) -> Result<Response, ContractError> {
     match msg {
         ExecuteMsg::Receive(msg) => receive_cw20(deps, env, info, msg),
-        ExecuteMsg::DepositStable {} => deposit_stable(deps, env, info),
-        ExecuteMsg::ClaimRewards { to: _ } => Ok(Response::default()),
+        ExecuteMsg::DepositStable {
+            recipient,
+            referrer,
+        } => deposit_stable(deps, env, info, recipient, referrer),
+        ExecuteMsg::ClaimRewards { to } => claim_rewards(deps, env, info, to),
+        ExecuteMsg::UpdateConfig {
+            owner_addr,
+            tvl_cap,
+            max_balance,
+            min_deposit,
+        } => update_config(deps, info, owner_addr, tvl_cap, max_balance, min_deposit),
+        ExecuteMsg::UpdatePermissionConfig {
+            permissioned,
+            membership_contract,
+        } => update_permission_config(deps, info, permissioned, membership_contract),
+        ExecuteMsg::UpdateStakingConfig {
+            unbonding_period,
+            chain_unbonding_period,
+            harvest_bounty,
+            max_rebalance,
+        } => update_staking_config(
+            deps,
+            info,
+            unbonding_period,
+            chain_unbonding_period,
+            harvest_bounty,
+            max_rebalance,
+        ),
+        ExecuteMsg::UpdateEpochConfig {
+            liquidity_buffer,
+            epoch_period,
+            epoch_bounty,
+        } => update_epoch_config(deps, info, liquidity_buffer, epoch_period, epoch_bounty),
+        ExecuteMsg::UpdateRewardConfig {
+            reward_token,
+            emission_rate,
+        } => update_reward_config(deps, env, info, reward_token, emission_rate),
+        ExecuteMsg::UpdateDistributionConfig {
+            distribution_model,
+            target_deposit_rate,
+            threshold_deposit_rate,
+        } => update_distribution_config(
+            deps,
+            info,
+            distribution_model,
+            target_deposit_rate,
+            threshold_deposit_rate,
+        ),
+        ExecuteMsg::UpdateBoostConfig {
+            governance_token,
+            max_lock_duration,
+            max_boost,
+            boost_ratio,
+        } => update_boost_config(
+            deps,
+            info,
+            governance_token,
+            max_lock_duration,
+            max_boost,
+            boost_ratio,
+        ),
+        ExecuteMsg::UpdateReferralConfig { referral_share } => {
+            update_referral_config(deps, info, referral_share)
+        }
+        ExecuteMsg::AddToAllowlist { addresses } => add_to_allowlist(deps, info, addresses),
+        ExecuteMsg::AddSponsors { addresses } => add_sponsors(deps, info, addresses),
+        ExecuteMsg::RemoveSponsors { addresses } => remove_sponsors(deps, info, addresses),
+        ExecuteMsg::RemoveFromAllowlist { addresses } => {
+            remove_from_allowlist(deps, info, addresses)
+        }
+        ExecuteMsg::FundReserve {} => fund_reserve(deps, env, info),
+        ExecuteMsg::ClaimQueued {} => claim_queued(deps, env, info),
+        ExecuteMsg::ClaimUnbonded {} => claim_unbonded(deps, env, info),
+        ExecuteMsg::RegisterValidator { validator, weight } => {
+            register_validator(deps, info, validator, weight)
+        }
+        ExecuteMsg::RemoveValidator { validator } => remove_validator(deps, env, info, validator),
+        ExecuteMsg::UpdateValidatorWeight { validator, weight } => {
+            update_validator_weight(deps, info, validator, weight)
+        }
+        ExecuteMsg::Rebalance {} => rebalance(deps, env),
+        ExecuteMsg::Harvest {} => harvest(deps, env, info),
+        ExecuteMsg::Reconcile {} => reconcile(deps, env),
+        ExecuteMsg::SetSwapRoute {
+            denom,
+            pair_addr,
+            max_spread,
+            oracle_addr,
+        } => set_swap_route(deps, info, denom, pair_addr, max_spread, oracle_addr),
+        ExecuteMsg::RemoveSwapRoute { denom } => remove_swap_route(deps, info, denom),
+        ExecuteMsg::Sweep { denom } => sweep(deps, env, denom),
+        ExecuteMsg::AddStrategy {
+            contract_addr,
+            kind,
+            weight,
+        } => add_strategy(deps, info, contract_addr, kind, weight),
+        ExecuteMsg::RemoveStrategy { contract_addr } => {
+            remove_strategy(deps, env, info, contract_addr)
+        }
+        ExecuteMsg::UpdateStrategyWeight {
+            contract_addr,
+            weight,
+        } => update_strategy_weight(deps, info, contract_addr, weight),
+        ExecuteMsg::WithdrawStrategy {
+            contract_addr,
+            amount,
+        } => withdraw_strategy(deps, env, info, contract_addr, amount),
+        ExecuteMsg::SkimYield {} => skim_yield(deps, env),
+        ExecuteMsg::Allocate {} => allocate(deps, env),
+        ExecuteMsg::ExecuteEpochOperations {} => execute_epoch_operations(deps, env, info),
+        ExecuteMsg::Settle { addresses } => settle(deps, env, addresses),
+        ExecuteMsg::SettleRange { start_after, limit } => {
+            settle_range(deps, env, start_after, limit)
+        }
+        ExecuteMsg::CreateStream {
+            start_time,
+            end_time,
+        } => create_native_stream(deps, env, info, start_time, end_time),
+        ExecuteMsg::CloseStream { id } => close_stream(deps, env, id),
+        ExecuteMsg::ClaimStreamRewards { ids, to } => {
+            claim_stream_rewards(deps, env, info, ids, to)
+        }
+        ExecuteMsg::Kick { address } => kick(deps, env, address),
+        ExecuteMsg::ExtendLock { duration } => extend_lock(deps, env, info, duration),
+        ExecuteMsg::Unlock {} => unlock(deps, env, info),
+        ExecuteMsg::ClaimReferralRewards {} => claim_referral_rewards(deps, env, info),
+        ExecuteMsg::SetInterestPayout { to } => set_interest_payout(deps, env, info, to),
+        ExecuteMsg::SetInterestRedirect { beneficiary, share } => {
+            set_interest_redirect(deps, env, info, beneficiary, share)
+        }
+        ExecuteMsg::ClaimRedirectedInterest {} => claim_redirected_interest(deps, env, info),
+        ExecuteMsg::ClaimPrize {} => claim_prize(deps, env, info),
+        ExecuteMsg::RegisterOperator { operator } => register_operator(deps, info, operator),
+        ExecuteMsg::RemoveOperator { operator } => deregister_operator(deps, info, operator),
+        ExecuteMsg::CommitRandomness {
+            draw_id,
+            commitment,
+        } => commit_randomness(deps, env, info, draw_id, commitment),
+        ExecuteMsg::RevealRandomness { draw_id, secret } => {
+            reveal_randomness(deps, env, info, draw_id, secret)
+        }
+        ExecuteMsg::DrawPrizes { draw_id } => draw_prizes(deps, env, draw_id),
+        ExecuteMsg::AwardPrizes { draw_id, limit } => award_prizes(deps, env, draw_id, limit),
+        ExecuteMsg::AddMaturity {
+            maturity,
+            token_code_id,
+        } => add_maturity(deps, env, info, maturity, token_code_id),
+        ExecuteMsg::CancelRecombine { maturity } => cancel_recombine(deps, info, maturity),
+        ExecuteMsg::EnableJuniorTranche { token_code_id } => {
+            enable_junior_tranche(deps, env, info, token_code_id)
+        }
+        ExecuteMsg::DepositJunior {} => deposit_junior(deps, env, info),
     }
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn reply(deps: DepsMut, _env: Env, msg: Reply) -> Result<Response, ContractError> {
     match msg.id {
-        1 => {
-            // get new token's contract address
-            let res: MsgInstantiateContractResponse = Message::parse_from_bytes(
-                msg.result.unwrap().data.unwrap().as_slice(),
-            )
-            .map_err(|_| {
-                ContractError::Std(StdError::parse_err(
-                    "MsgInstantiateContractResponse",
-                    "failed to parse data",
-                ))
-            })?;
-            let token_addr = Addr::unchecked(res.get_contract_address());
-
-            register_aterra(deps, token_addr)
-        }
+        1 => register_aterra(deps, instantiated_token(msg)?),
+        // principal and yield tokens of a new maturity
+        2 => register_maturity_token(deps, instantiated_token(msg)?, true),
+        3 => register_maturity_token(deps, instantiated_token(msg)?, false),
+        4 => register_junior_token(deps, instantiated_token(msg)?),
         _ => Err(ContractError::InvalidReplyId {}),
     }
 }
 
+/// Address of the token contract instantiated by the replied message
+fn instantiated_token(msg: Reply) -> Result<Addr, ContractError> {
+    // get new token's contract address
+    let res: MsgInstantiateContractResponse =
+        Message::parse_from_bytes(msg.result.unwrap().data.unwrap().as_slice()).map_err(|_| {
+            ContractError::Std(StdError::parse_err(
+                "MsgInstantiateContractResponse",
+                "failed to parse data",
+            ))
+        })?;
+    Ok(Addr::unchecked(res.get_contract_address()))
+}
+
 pub fn receive_cw20(
     deps: DepsMut,
     env: Env,
@@ -153,23 +506,113 @@ pub fn receive_cw20(
 ) -> Result<Response, ContractError> {
     let contract_addr = info.sender;
     match from_binary(&cw20_msg.msg) {
-        Ok(Cw20HookMsg::RedeemNStable {}) => {
+        Ok(Cw20HookMsg::RedeemNStable { to, msg }) => {
+            let config: Config = read_config(deps.storage)?;
+            if deps.api.addr_canonicalize(contract_addr.as_str())? != config.aterra_contract {
+                return Err(ContractError::Unauthorized {});
+            }
+
+            let cw20_sender_addr = deps.api.addr_validate(&cw20_msg.sender)?;
+            let recipient = match to {
+                Some(to) => deps.api.addr_validate(&to)?,
+                None => cw20_sender_addr.clone(),
+            };
+            redeem_n_stable(deps, env, cw20_sender_addr, cw20_msg.amount, recipient, msg)
+        }
+        Ok(Cw20HookMsg::RedeemAllStable { to, msg }) => {
             let config: Config = read_config(deps.storage)?;
             if deps.api.addr_canonicalize(contract_addr.as_str())? != config.aterra_contract {
                 return Err(ContractError::Unauthorized {});
             }
 
             let cw20_sender_addr = deps.api.addr_validate(&cw20_msg.sender)?;
-            redeem_n_stable(deps, env, cw20_sender_addr, cw20_msg.amount)
+            let recipient = match to {
+                Some(to) => deps.api.addr_validate(&to)?,
+                None => cw20_sender_addr.clone(),
+            };
+            redeem_all_stable(deps, env, cw20_sender_addr, cw20_msg.amount, recipient, msg)
+        }
+        Ok(Cw20HookMsg::CreateStream {
+            start_time,
+            end_time,
+        }) => {
+            let sponsor = deps.api.addr_validate(&cw20_msg.sender)?;
+            let asset = Asset {
+                info: AssetInfo::Token {
+                    contract_addr: contract_addr.to_string(),
+                },
+                amount: cw20_msg.amount,
+            };
+            create_stream(deps, env, sponsor, asset, start_time, end_time)
         }
-        Ok(Cw20HookMsg::RedeemAllStable {}) => {
+        Ok(Cw20HookMsg::Lock { duration }) => {
+            let config: Config = read_config(deps.storage)?;
+            match config.governance_token {
+                Some(governance_token) => {
+                    if deps.api.addr_canonicalize(contract_addr.as_str())? != governance_token {
+                        return Err(ContractError::Unauthorized {});
+                    }
+                }
+                None => return Err(ContractError::BoostDisabled {}),
+            }
+
+            let locker = deps.api.addr_validate(&cw20_msg.sender)?;
+            lock(deps, env, locker, cw20_msg.amount, duration)
+        }
+        Ok(Cw20HookMsg::Split { maturity }) => {
             let config: Config = read_config(deps.storage)?;
             if deps.api.addr_canonicalize(contract_addr.as_str())? != config.aterra_contract {
                 return Err(ContractError::Unauthorized {});
             }
 
+            let splitter = deps.api.addr_validate(&cw20_msg.sender)?;
+            split(deps, env, splitter, cw20_msg.amount, maturity)
+        }
+        Ok(Cw20HookMsg::Recombine { maturity }) => {
+            let owner = deps.api.addr_validate(&cw20_msg.sender)?;
+            recombine(deps, env, contract_addr, owner, cw20_msg.amount, maturity)
+        }
+        Ok(Cw20HookMsg::RedeemPrincipal { maturity }) => {
+            let redeemer = deps.api.addr_validate(&cw20_msg.sender)?;
+            redeem_split(
+                deps,
+                env,
+                contract_addr,
+                redeemer,
+                cw20_msg.amount,
+                maturity,
+                true,
+            )
+        }
+        Ok(Cw20HookMsg::RedeemJunior { to }) => {
+            let config: Config = read_config(deps.storage)?;
+            match config.junior_token {
+                Some(junior_token) => {
+                    if deps.api.addr_canonicalize(contract_addr.as_str())? != junior_token {
+                        return Err(ContractError::Unauthorized {});
+                    }
+                }
+                None => return Err(ContractError::JuniorTrancheDisabled {}),
+            }
+
             let cw20_sender_addr = deps.api.addr_validate(&cw20_msg.sender)?;
-            redeem_all_stable(deps, env, cw20_sender_addr, cw20_msg.amount)
+            let recipient = match to {
+                Some(to) => deps.api.addr_validate(&to)?,
+                None => cw20_sender_addr.clone(),
+            };
+            redeem_junior(deps, env, cw20_sender_addr, cw20_msg.amount, recipient)
+        }
+        Ok(Cw20HookMsg::RedeemYield { maturity }) => {
+            let redeemer = deps.api.addr_validate(&cw20_msg.sender)?;
+            redeem_split(
+                deps,
+                env,
+                contract_addr,
+                redeemer,
+                cw20_msg.amount,
+                maturity,
+                false,
+            )
         }
         _ => Err(ContractError::MissingRedeemStableHook {}),
     }
@@ -187,22 +630,382 @@ pub fn register_aterra(deps: DepsMut, token_addr: Addr) -> Result<Response, Cont
     Ok(Response::new().add_attributes(vec![attr("aterra", token_addr)]))
 }
 
+pub fn update_config(
+    deps: DepsMut,
+    info: MessageInfo,
+    owner_addr: Option<String>,
+    tvl_cap: Option<Uint128>,
+    max_balance: Option<Uint128>,
+    min_deposit: Option<Uint128>,
+) -> Result<Response, ContractError> {
+    let mut config: Config = read_config(deps.storage)?;
+    if deps.api.addr_canonicalize(info.sender.as_str())? != config.owner_addr {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    if let Some(owner_addr) = owner_addr {
+        config.owner_addr = deps.api.addr_canonicalize(&owner_addr)?;
+    }
+
+    if let Some(tvl_cap) = tvl_cap {
+        config.tvl_cap = tvl_cap;
+    }
+
+    if let Some(max_balance) = max_balance {
+        config.max_balance = max_balance;
+    }
+
+    if let Some(min_deposit) = min_deposit {
+        config.min_deposit = min_deposit;
+    }
+
+    store_config(deps.storage, &config)?;
+    Ok(Response::new().add_attributes(vec![attr("action", "update_config")]))
+}
+
+pub fn update_permission_config(
+    deps: DepsMut,
+    info: MessageInfo,
+    permissioned: Option<bool>,
+    membership_contract: Option<String>,
+) -> Result<Response, ContractError> {
+    let mut config: Config = read_config(deps.storage)?;
+    if deps.api.addr_canonicalize(info.sender.as_str())? != config.owner_addr {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    if let Some(permissioned) = permissioned {
+        config.permissioned = permissioned;
+    }
+
+    if let Some(membership_contract) = membership_contract {
+        config.membership_contract = if membership_contract.is_empty() {
+            None
+        } else {
+            Some(deps.api.addr_canonicalize(&membership_contract)?)
+        };
+    }
+
+    store_config(deps.storage, &config)?;
+    Ok(Response::new().add_attributes(vec![attr("action", "update_permission_config")]))
+}
+
+pub fn update_staking_config(
+    deps: DepsMut,
+    info: MessageInfo,
+    unbonding_period: Option<u64>,
+    chain_unbonding_period: Option<u64>,
+    harvest_bounty: Option<Decimal>,
+    max_rebalance: Option<Uint128>,
+) -> Result<Response, ContractError> {
+    let mut config: Config = read_config(deps.storage)?;
+    if deps.api.addr_canonicalize(info.sender.as_str())? != config.owner_addr {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    if let Some(unbonding_period) = unbonding_period {
+        config.unbonding_period = unbonding_period;
+    }
+
+    if let Some(chain_unbonding_period) = chain_unbonding_period {
+        config.chain_unbonding_period = chain_unbonding_period;
+    }
+
+    let min_unbonding_period = config.chain_unbonding_period + config.epoch_period;
+    if config.native_staking && config.unbonding_period < min_unbonding_period {
+        return Err(ContractError::UnbondingPeriodTooShort(min_unbonding_period));
+    }
+
+    if let Some(harvest_bounty) = harvest_bounty {
+        if harvest_bounty > Decimal::one() {
+            return Err(ContractError::InvalidHarvestBounty {});
+        }
+        config.harvest_bounty = harvest_bounty;
+    }
+
+    if let Some(max_rebalance) = max_rebalance {
+        config.max_rebalance = max_rebalance;
+    }
+
+    store_config(deps.storage, &config)?;
+    Ok(Response::new().add_attributes(vec![attr("action", "update_staking_config")]))
+}
+
+pub fn update_epoch_config(
+    deps: DepsMut,
+    info: MessageInfo,
+    liquidity_buffer: Option<Decimal>,
+    epoch_period: Option<u64>,
+    epoch_bounty: Option<Uint128>,
+) -> Result<Response, ContractError> {
+    let mut config: Config = read_config(deps.storage)?;
+    if deps.api.addr_canonicalize(info.sender.as_str())? != config.owner_addr {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    if let Some(liquidity_buffer) = liquidity_buffer {
+        if liquidity_buffer > Decimal::one() {
+            return Err(ContractError::InvalidLiquidityBuffer {});
+        }
+        config.liquidity_buffer = liquidity_buffer;
+    }
+
+    if let Some(epoch_period) = epoch_period {
+        config.epoch_period = epoch_period;
+    }
+
+    if let Some(epoch_bounty) = epoch_bounty {
+        config.epoch_bounty = epoch_bounty;
+    }
+
+    if !config.epoch_bounty.is_zero() && config.epoch_period == 0 {
+        return Err(ContractError::InvalidEpochPeriod {});
+    }
+
+    // batched undelegations must complete within the unbonding period
+    let min_unbonding_period = config.chain_unbonding_period + config.epoch_period;
+    if config.native_staking && config.unbonding_period < min_unbonding_period {
+        return Err(ContractError::UnbondingPeriodTooShort(min_unbonding_period));
+    }
+
+    store_config(deps.storage, &config)?;
+    Ok(Response::new().add_attributes(vec![attr("action", "update_epoch_config")]))
+}
+
+pub fn update_reward_config(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    reward_token: Option<String>,
+    emission_rate: Option<Decimal>,
+) -> Result<Response, ContractError> {
+    let mut config: Config = read_config(deps.storage)?;
+    if deps.api.addr_canonicalize(info.sender.as_str())? != config.owner_addr {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    if let Some(reward_token) = reward_token {
+        config.reward_token = if reward_token.is_empty() {
+            None
+        } else {
+            Some(deps.api.addr_canonicalize(&reward_token)?)
+        };
+    }
+
+    // rewards emitted so far are indexed at the previous rate
+    if let Some(emission_rate) = emission_rate {
+        let mut state: State = read_state(deps.storage)?;
+        update_reward_index(&mut state, &config, env.block.time.seconds());
+        store_state(deps.storage, &state)?;
+        config.emission_rate = emission_rate;
+    }
+
+    store_config(deps.storage, &config)?;
+    Ok(Response::new().add_attributes(vec![attr("action", "update_reward_config")]))
+}
+
+pub fn update_distribution_config(
+    deps: DepsMut,
+    info: MessageInfo,
+    distribution_model: Option<String>,
+    target_deposit_rate: Option<Decimal>,
+    threshold_deposit_rate: Option<Decimal>,
+) -> Result<Response, ContractError> {
+    let mut config: Config = read_config(deps.storage)?;
+    if deps.api.addr_canonicalize(info.sender.as_str())? != config.owner_addr {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    if let Some(distribution_model) = distribution_model {
+        config.distribution_model = if distribution_model.is_empty() {
+            None
+        } else {
+            Some(deps.api.addr_canonicalize(&distribution_model)?)
+        };
+    }
+
+    if let Some(target_deposit_rate) = target_deposit_rate {
+        config.target_deposit_rate = target_deposit_rate;
+    }
+
+    if let Some(threshold_deposit_rate) = threshold_deposit_rate {
+        config.threshold_deposit_rate = threshold_deposit_rate;
+    }
+
+    store_config(deps.storage, &config)?;
+    Ok(Response::new().add_attributes(vec![attr("action", "update_distribution_config")]))
+}
+
+/// Working balances are not recalculated here: each depositor's boost
+/// follows the new values at their next interaction or `Kick`
+pub fn update_boost_config(
+    deps: DepsMut,
+    info: MessageInfo,
+    governance_token: Option<String>,
+    max_lock_duration: Option<u64>,
+    max_boost: Option<Decimal>,
+    boost_ratio: Option<Decimal>,
+) -> Result<Response, ContractError> {
+    let mut config: Config = read_config(deps.storage)?;
+    if deps.api.addr_canonicalize(info.sender.as_str())? != config.owner_addr {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    // locked tokens are returned in the token they were locked in
+    if let Some(governance_token) = governance_token {
+        if !read_state(deps.storage)?.total_locked.is_zero() {
+            return Err(ContractError::LocksOutstanding {});
+        }
+        config.governance_token = if governance_token.is_empty() {
+            None
+        } else {
+            Some(deps.api.addr_canonicalize(&governance_token)?)
+        };
+    }
+
+    if let Some(max_lock_duration) = max_lock_duration {
+        config.max_lock_duration = max_lock_duration;
+    }
+
+    if let Some(max_boost) = max_boost {
+        if max_boost < Decimal::one() {
+            return Err(ContractError::InvalidMaxBoost {});
+        }
+        config.max_boost = max_boost;
+    }
+
+    if let Some(boost_ratio) = boost_ratio {
+        config.boost_ratio = boost_ratio;
+    }
+
+    store_config(deps.storage, &config)?;
+    Ok(Response::new().add_attributes(vec![attr("action", "update_boost_config")]))
+}
+
+pub fn update_referral_config(
+    deps: DepsMut,
+    info: MessageInfo,
+    referral_share: Decimal,
+) -> Result<Response, ContractError> {
+    let mut config: Config = read_config(deps.storage)?;
+    if deps.api.addr_canonicalize(info.sender.as_str())? != config.owner_addr {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    if referral_share > Decimal::one() {
+        return Err(ContractError::InvalidReferralShare {});
+    }
+    config.referral_share = referral_share;
+
+    store_config(deps.storage, &config)?;
+    Ok(Response::new().add_attributes(vec![attr("action", "update_referral_config")]))
+}
+
 #[cfg_attr(not(feature = "library"), entry_point)]
-pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
+pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
     match msg {
         QueryMsg::Config {} => to_binary(&query_config(deps)?),
         QueryMsg::State {} => to_binary(&query_state(deps)?),
         QueryMsg::Ident { address, epoch } => to_binary(&query_ident(deps, address, epoch)?),
         QueryMsg::Tvl { indice } => to_binary(&query_tvl(deps, indice)?),
+        QueryMsg::Allowlist { start_after, limit } => {
+            to_binary(&query_allowlist(deps, start_after, limit)?)
+        }
+        QueryMsg::WithdrawalQueue { address } => to_binary(&query_withdrawal_queue(deps, address)?),
+        QueryMsg::Unbonding { address } => to_binary(&query_unbonding(deps, env, address)?),
+        QueryMsg::Validators {} => to_binary(&query_validators(deps, env)?),
+        QueryMsg::Harvest {} => to_binary(&query_harvest(deps)?),
+        QueryMsg::SwapRoutes {} => to_binary(&query_swap_routes(deps)?),
+        QueryMsg::Strategies {} => to_binary(&query_strategies(deps, env)?),
+        QueryMsg::AssetBreakdown {} => to_binary(&query_asset_breakdown(deps, env)?),
+        QueryMsg::PendingRewards { address } => {
+            to_binary(&query_pending_rewards(deps, env, address)?)
+        }
+        QueryMsg::Streams {} => to_binary(&query_streams(deps)?),
+        QueryMsg::PendingStreamRewards { address } => {
+            to_binary(&query_pending_stream_rewards(deps, env, address)?)
+        }
+        QueryMsg::BoostInfo { address } => to_binary(&query_boost_info(deps, env, address)?),
+        QueryMsg::Referral { referrer } => to_binary(&query_referral(deps, referrer)?),
+        QueryMsg::Draw { draw_id } => to_binary(&query_draw(deps, env, draw_id)?),
+        QueryMsg::Prize { address } => to_binary(&query_prize(deps, env, address)?),
+        QueryMsg::Operators {} => to_binary(&query_operators(deps)?),
+        QueryMsg::InterestRedirect { address } => {
+            to_binary(&query_interest_redirect(deps, address)?)
+        }
+        QueryMsg::IncomingRedirects {
+            beneficiary,
+            start_after,
+            limit,
+        } => to_binary(&query_incoming_redirects(
+            deps,
+            beneficiary,
+            start_after,
+            limit,
+        )?),
+        QueryMsg::Maturity { maturity } => to_binary(&query_maturity(deps, maturity)?),
+        QueryMsg::Maturities { start_after, limit } => {
+            to_binary(&query_maturities(deps, start_after, limit)?)
+        }
+        QueryMsg::Tranches {} => to_binary(&query_tranches(deps)?),
     }
 }
 
 pub fn query_config(deps: Deps) -> StdResult<ConfigResponse> {
     let config: Config = read_config(deps.storage)?;
     Ok(ConfigResponse {
+        owner_addr: deps.api.addr_humanize(&config.owner_addr)?.to_string(),
         aterra_contract: deps.api.addr_humanize(&config.aterra_contract)?.to_string(),
         stable_denom: config.stable_denom,
         interest_rate: config.interest_rate,
+        tvl_cap: config.tvl_cap,
+        max_balance: config.max_balance,
+        min_deposit: config.min_deposit,
+        permissioned: config.permissioned,
+        membership_contract: match config.membership_contract {
+            Some(membership_contract) => {
+                Some(deps.api.addr_humanize(&membership_contract)?.to_string())
+            }
+            None => None,
+        },
+        unbonding_period: config.unbonding_period,
+        native_staking: config.native_staking,
+        chain_unbonding_period: config.chain_unbonding_period,
+        harvest_bounty: config.harvest_bounty,
+        max_rebalance: config.max_rebalance,
+        liquidity_buffer: config.liquidity_buffer,
+        epoch_period: config.epoch_period,
+        epoch_bounty: config.epoch_bounty,
+        reward_token: match config.reward_token {
+            Some(reward_token) => Some(deps.api.addr_humanize(&reward_token)?.to_string()),
+            None => None,
+        },
+        emission_rate: config.emission_rate,
+        distribution_model: match config.distribution_model {
+            Some(distribution_model) => {
+                Some(deps.api.addr_humanize(&distribution_model)?.to_string())
+            }
+            None => None,
+        },
+        target_deposit_rate: config.target_deposit_rate,
+        threshold_deposit_rate: config.threshold_deposit_rate,
+        governance_token: match config.governance_token {
+            Some(governance_token) => Some(deps.api.addr_humanize(&governance_token)?.to_string()),
+            None => None,
+        },
+        max_lock_duration: config.max_lock_duration,
+        max_boost: config.max_boost,
+        boost_ratio: config.boost_ratio,
+        referral_share: config.referral_share,
+        prize_mode: config.prize_mode,
+        draw_start: config.draw_start,
+        draw_period: config.draw_period,
+        reveal_period: config.reveal_period,
+        prize_winners: config.prize_winners,
+        junior_token: match config.junior_token {
+            Some(junior_token) => Some(deps.api.addr_humanize(&junior_token)?.to_string()),
+            None => None,
+        },
     })
 }
 
@@ -213,9 +1016,9 @@ pub fn query_state(deps: Deps) -> StdResult<State> {
 
 pub fn query_ident(deps: Deps, ident: String, epoch: u64) -> StdResult<DepositInfo> {
     let config: Config = read_config(deps.storage)?;
-    let mut depositor = read_deposit_info(deps.storage, &deps.api.addr_canonicalize(&ident)?);
+    let mut depositor = read_deposit_info(deps.storage, &deps.api.addr_canonicalize(&ident)?)?;
     if depositor.initial_interaction != 0 && epoch > depositor.last_interaction {
-        let duration = epoch - depositor.last_interaction.clone();
+        let duration = epoch - depositor.last_interaction;
         let days = duration / (DURATION);
         let accrued_interest = calculate_accrued_interest(&depositor, config.interest_rate, days)?;
         depositor.accrued_interest = accrued_interest;
@@ -224,12 +1027,29 @@ pub fn query_ident(deps: Deps, ident: String, epoch: u64) -> StdResult<DepositIn
 }
 
 pub fn query_tvl(deps: Deps, indice: i64) -> StdResult<Vec<Tvl>> {
-    let tvls: Vec<Tvl>;
-    if indice == -1 {
+    let tvls: Vec<Tvl> = if indice == -1 {
         let state = read_state(deps.storage)?;
-        tvls = read_tvl_indices(deps.storage, state.tvl_indices)?;
+        read_tvl_indices(deps.storage, state.tvl_indices)?
     } else {
-        tvls = vec![read_tvl_indice(deps.storage, indice)];
-    }
+        vec![read_tvl_indice(deps.storage, indice)]
+    };
     Ok(tvls.clone())
 }
+
+pub fn query_allowlist(
+    deps: Deps,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<AllowlistResponse> {
+    let start_after = match start_after {
+        Some(start_after) => Some(deps.api.addr_canonicalize(&start_after)?),
+        None => None,
+    };
+
+    let addresses = read_allowlist(deps.storage, start_after, limit)?
+        .iter()
+        .map(|ident| Ok(deps.api.addr_humanize(ident)?.to_string()))
+        .collect::<StdResult<Vec<String>>>()?;
+
+    Ok(AllowlistResponse { addresses })
+}
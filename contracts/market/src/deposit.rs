@@ -1,27 +1,44 @@
 use cosmwasm_std::{
-    attr, to_binary, Addr, BankMsg, Coin, CosmosMsg, DepsMut, Env, MessageInfo, Response, Uint128,
-    WasmMsg,
+    attr, to_binary, Addr, BankMsg, Binary, CanonicalAddr, Coin, CosmosMsg, DepsMut, Env,
+    MessageInfo, Response, StdResult, Storage, Uint128, WasmMsg,
 };
 
+use crate::allowlist::assert_allowlisted;
+use crate::boost::update_working_balance;
 use crate::contract::DURATION;
 use crate::error::ContractError;
 use crate::helpers::*;
+use crate::prize::{update_total_twab, update_twab};
+use crate::queue::{fill_queue, pay_or_enqueue, query_liquidity};
+use crate::redirect::redirect_interest;
+use crate::referral::{accrue_referral_reward, record_referrer};
+use crate::rewards::accrue_rewards;
+use crate::staking::delegate_msgs;
 use crate::state::{
-    read_config, read_deposit_info, read_state, store_deposit_info, store_state, store_tvl_indice,
-    Config, State, Tvl,
+    read_config, read_deposit_addresses, read_deposit_info, read_state, store_deposit_info,
+    store_state, store_tvl_indice, Config, DepositInfo, State, Tvl,
 };
+use crate::strategy::allocate_msgs;
+use crate::unbonding::start_unbonding;
 
 use cw20::Cw20ExecuteMsg;
 
 pub fn deposit_stable(
-    deps: DepsMut,
+    mut deps: DepsMut,
     env: Env,
     info: MessageInfo,
+    recipient: Option<String>,
+    referrer: Option<String>,
 ) -> Result<Response, ContractError> {
     let time = env.block.time.seconds();
     let config: Config = read_config(deps.storage)?;
-    let ident = info.sender;
+    let funder = info.sender;
+    let ident = match recipient {
+        Some(recipient) => deps.api.addr_validate(&recipient)?,
+        None => funder.clone(),
+    };
     let ident_raw = deps.api.addr_canonicalize(ident.as_str())?;
+    assert_allowlisted(deps.as_ref(), &config, &ident)?;
 
     let deposit_amount: Uint128 = info
         .funds
@@ -30,56 +47,106 @@ pub fn deposit_stable(
         .map(|c| c.amount)
         .unwrap_or_else(Uint128::zero);
 
-    let mut deposit = read_deposit_info(deps.storage, &ident_raw);
+    if deposit_amount.is_zero() {
+        return Err(ContractError::ZeroDeposit(config.stable_denom));
+    }
+
+    if deposit_amount < config.min_deposit {
+        return Err(ContractError::DepositTooSmall(
+            config.min_deposit.u128(),
+            config.stable_denom,
+        ));
+    }
+
+    let state: State = read_state(deps.storage)?;
+    // receipt tokens wiped out by slashing are worth nothing
+    if state.exchange_rate.is_zero() {
+        return Err(ContractError::Insolvent {});
+    }
+    let receipt_amount = stable_to_receipt(deposit_amount, state.exchange_rate);
+    // the caps are in stable coins, the balances in receipt tokens
+    if !config.tvl_cap.is_zero()
+        && receipt_to_stable(state.tvl + receipt_amount, state.exchange_rate) > config.tvl_cap
+    {
+        return Err(ContractError::TvlCapExceeded(config.tvl_cap.u128()));
+    }
+
+    let mut deposit = read_deposit_info(deps.storage, &ident_raw)?;
     if deposit.initial_interaction == 0 {
         deposit.initial_interaction = time;
     }
     if deposit.last_interaction == 0 {
         deposit.last_interaction = time;
     }
-    let last_interaction = deposit.last_interaction;
-    let duration = time - last_interaction;
-    let days = duration / (DURATION);
-    deposit.last_interaction = time;
+    let accrued_interest = accrue_interest(deps.storage, &config, &ident_raw, &mut deposit, time)?;
+    // only depositors name their own referrer, a deposit made for a
+    // recipient must not bind the recipient to the funder's choice
+    if let Some(referrer) = referrer.filter(|_| funder == ident) {
+        record_referrer(deps.branch(), &ident, &mut deposit, referrer)?;
+    }
 
-    let mut accrued_interest = Uint128::zero();
-    if days > 0 {
-        let mut state: State = read_state(deps.storage)?;
-        accrued_interest = calculate_accrued_interest(&deposit, config.interest_rate, days)?;
-        state.accrued_interest_payments += accrued_interest;
-        store_state(deps.storage, &state)?;
+    if !config.max_balance.is_zero()
+        && receipt_to_stable(deposit.last_balance + receipt_amount, state.exchange_rate)
+            > config.max_balance
+    {
+        return Err(ContractError::MaxBalanceExceeded(config.max_balance.u128()));
     }
-    let _tvl_put = store_tvl_indice(
+    update_total_twab(deps.storage, &config, time)?;
+    store_tvl_indice(
         deps.storage,
         &mut Tvl {
             epoch: time,
-            tvl: deposit_amount + accrued_interest,
+            tvl: receipt_amount + accrued_interest,
         },
         1,
     )
     .unwrap();
-    deposit.accrued_interest += accrued_interest;
-    deposit.last_balance += deposit_amount + accrued_interest;
+    deposit.last_balance += receipt_amount;
     deposit.sum_deposits += deposit_amount;
+    update_working_balance(deps.storage, &config, &ident_raw, &mut deposit, time)?;
 
     store_deposit_info(deps.storage, &ident_raw, &deposit)?;
+    let (withdraw_msgs, queue_filled) = fill_queue(deps.branch(), &env, &config)?;
+
+    // liquid funds serve queued withdrawals before being put to work
+    let yield_msgs: Vec<CosmosMsg> = if config.native_staking {
+        delegate_msgs(
+            deps.branch(),
+            &env,
+            &config,
+            deposit_amount.saturating_sub(queue_filled),
+        )?
+    } else {
+        allocate_msgs(deps.branch(), &env, &config, Uint128::zero())?.0
+    };
+
     Ok(Response::new()
+        .add_messages(withdraw_msgs)
+        .add_messages(yield_msgs)
         .add_message(CosmosMsg::Wasm(WasmMsg::Execute {
             contract_addr: deps.api.addr_humanize(&config.aterra_contract)?.to_string(),
             funds: vec![],
             msg: to_binary(&Cw20ExecuteMsg::Mint {
                 recipient: ident.to_string(),
-                amount: deposit_amount + accrued_interest,
+                amount: receipt_amount + accrued_interest,
             })?,
         }))
-        .add_attributes(vec![attr("action", "deposit_stable")]))
+        .add_attributes(vec![
+            attr("action", "deposit_stable"),
+            attr("funder", funder),
+            attr("beneficiary", ident),
+            attr("deposit_amount", deposit_amount),
+            attr("queue_filled", queue_filled),
+        ]))
 }
 
 pub fn redeem_n_stable(
-    deps: DepsMut,
+    mut deps: DepsMut,
     env: Env,
     sender: Addr,
     withdraw_amount: Uint128,
+    recipient: Addr,
+    msg: Option<Binary>,
 ) -> Result<Response, ContractError> {
     let time = env.block.time.seconds();
     let config: Config = read_config(deps.storage)?;
@@ -87,20 +154,17 @@ pub fn redeem_n_stable(
     let ident_raw = deps.api.addr_canonicalize(ident.as_str())?;
     let mut response_ixs: Vec<CosmosMsg> = Vec::new();
 
-    let mut deposit = read_deposit_info(deps.storage, &ident_raw);
-    let last_interaction = deposit.last_interaction;
-    let duration = time - last_interaction;
-    let days = duration / (DURATION);
-
-    deposit.last_interaction = time;
-    let mut accrued_interest = Uint128::zero();
-    if days > 0 {
-        accrued_interest = calculate_accrued_interest(&deposit, config.interest_rate, days)?;
-        let mut state: State = read_state(deps.storage)?;
-        state.accrued_interest_payments += accrued_interest;
-        store_state(deps.storage, &state)?;
-        deposit.last_balance += accrued_interest;
-
+    let mut deposit = read_deposit_info(deps.storage, &ident_raw)?;
+    let accrued_interest = accrue_interest(deps.storage, &config, &ident_raw, &mut deposit, time)?;
+    if !accrued_interest.is_zero() {
+        store_tvl_indice(
+            deps.storage,
+            &mut Tvl {
+                epoch: time,
+                tvl: accrued_interest,
+            },
+            1,
+        )?;
         response_ixs.push(CosmosMsg::Wasm(WasmMsg::Execute {
             contract_addr: deps.api.addr_humanize(&config.aterra_contract)?.to_string(),
             funds: vec![],
@@ -110,7 +174,8 @@ pub fn redeem_n_stable(
             })?,
         }))
     }
-    let _tvl_put = store_tvl_indice(
+    update_total_twab(deps.storage, &config, time)?;
+    store_tvl_indice(
         deps.storage,
         &mut Tvl {
             epoch: time,
@@ -119,8 +184,8 @@ pub fn redeem_n_stable(
         0,
     )
     .unwrap();
-    deposit.accrued_interest += accrued_interest;
     deposit.last_balance -= withdraw_amount;
+    update_working_balance(deps.storage, &config, &ident_raw, &mut deposit, time)?;
     response_ixs.push(CosmosMsg::Wasm(WasmMsg::Execute {
         contract_addr: deps.api.addr_humanize(&config.aterra_contract)?.to_string(),
         funds: vec![],
@@ -128,41 +193,50 @@ pub fn redeem_n_stable(
             amount: withdraw_amount,
         })?,
     }));
-    response_ixs.push(CosmosMsg::Bank(BankMsg::Send {
-        to_address: ident.to_string(),
-        amount: vec![Coin {
-            denom: config.stable_denom,
-            amount: withdraw_amount,
-        }],
-    }));
 
     store_deposit_info(deps.storage, &ident_raw, &deposit)?;
+    let exchange_rate = read_state(deps.storage)?.exchange_rate;
+    let (payout, payout_status) = withdraw_stable(
+        deps.branch(),
+        &env,
+        &config,
+        &ident,
+        &recipient,
+        receipt_to_stable(withdraw_amount, exchange_rate),
+        msg,
+    )?;
+    response_ixs.extend(payout);
+
     Ok(Response::new()
         .add_messages(response_ixs)
-        .add_attributes(vec![attr("action", "redeem_n_stable")]))
+        .add_attributes(vec![
+            attr("action", "redeem_n_stable"),
+            attr("redeemer", ident),
+            attr("recipient", recipient),
+            attr("payout", payout_status),
+        ]))
 }
 
 pub fn redeem_all_stable(
-    deps: DepsMut,
+    mut deps: DepsMut,
     env: Env,
     sender: Addr,
     _withdrawal: Uint128,
+    recipient: Addr,
+    msg: Option<Binary>,
 ) -> Result<Response, ContractError> {
     let time = env.block.time.seconds();
     let config: Config = read_config(deps.storage)?;
     let ident = sender;
     let ident_raw = deps.api.addr_canonicalize(ident.as_str())?;
 
-    let mut deposit = read_deposit_info(deps.storage, &ident_raw);
-    let last_interaction = deposit.last_interaction;
-    let duration = time - last_interaction;
-    let days = duration / (DURATION);
-    deposit.last_interaction = time;
-
-    let burn_amount = deposit.last_balance.clone();
-    let mut withdraw_amount = deposit.last_balance.clone();
-    withdraw_amount += calculate_accrued_interest(&deposit, config.interest_rate, days)?;
-    let _tvl_put = store_tvl_indice(
+    let mut deposit = read_deposit_info(deps.storage, &ident_raw)?;
+    // interest accrued since the last interaction is paid out without minting
+    let burn_amount = deposit.last_balance;
+    accrue_interest(deps.storage, &config, &ident_raw, &mut deposit, time)?;
+    let withdraw_amount = deposit.last_balance;
+    update_total_twab(deps.storage, &config, time)?;
+    store_tvl_indice(
         deps.storage,
         &mut Tvl {
             epoch: time,
@@ -176,24 +250,320 @@ pub fn redeem_all_stable(
     deposit.last_balance = Uint128::zero();
     deposit.last_interaction = 0;
     deposit.sum_deposits = Uint128::zero();
+    update_working_balance(deps.storage, &config, &ident_raw, &mut deposit, time)?;
 
     store_deposit_info(deps.storage, &ident_raw, &deposit)?;
+    let mut response_ixs: Vec<CosmosMsg> = vec![CosmosMsg::Wasm(WasmMsg::Execute {
+        contract_addr: deps.api.addr_humanize(&config.aterra_contract)?.to_string(),
+        funds: vec![],
+        msg: to_binary(&Cw20ExecuteMsg::Burn {
+            amount: burn_amount,
+        })?,
+    })];
+    let exchange_rate = read_state(deps.storage)?.exchange_rate;
+    let (payout, payout_status) = withdraw_stable(
+        deps.branch(),
+        &env,
+        &config,
+        &ident,
+        &recipient,
+        receipt_to_stable(withdraw_amount, exchange_rate),
+        msg,
+    )?;
+    response_ixs.extend(payout);
+
     Ok(Response::new()
-        .add_messages(vec![
-            CosmosMsg::Wasm(WasmMsg::Execute {
-                contract_addr: deps.api.addr_humanize(&config.aterra_contract)?.to_string(),
+        .add_messages(response_ixs)
+        .add_attributes(vec![
+            attr("action", "redeem_all_stable"),
+            attr("redeemer", ident),
+            attr("recipient", recipient),
+            attr("payout", payout_status),
+        ]))
+}
+
+/// Accrues interest on `deposit` up to `time`, adding it to the balance and
+/// `State.accrued_interest_payments`, after accruing its reward tokens.
+/// The referrer's share is set aside from the reserve and the redirected
+/// share credited to the beneficiary.
+/// Returns the compounded interest, which the caller mints or pays out.
+/// Interest of depositors with `payout_to` goes to `unpaid_interest` instead.
+pub fn accrue_interest(
+    storage: &mut dyn Storage,
+    config: &Config,
+    ident: &CanonicalAddr,
+    deposit: &mut DepositInfo,
+    time: u64,
+) -> StdResult<Uint128> {
+    accrue_rewards(storage, config, ident, deposit, time)?;
+
+    // prize mode pools the interest of all deposits by time weighted balance
+    if config.prize_mode {
+        update_twab(config, &mut deposit.twab, deposit.last_balance, time);
+        deposit.last_interaction = time;
+        return Ok(Uint128::zero());
+    }
+
+    let days = (time - deposit.last_interaction) / DURATION;
+    deposit.last_interaction = time;
+
+    let accrued_interest = calculate_accrued_interest(deposit, config.interest_rate, days)?;
+    if accrued_interest.is_zero() {
+        return Ok(accrued_interest);
+    }
+
+    let mut state: State = read_state(storage)?;
+    state.accrued_interest_payments += accrued_interest;
+    store_state(storage, &state)?;
+    accrue_referral_reward(storage, config, deposit, accrued_interest)?;
+
+    let redirected = redirect_interest(storage, ident, deposit, accrued_interest)?;
+    let accrued_interest = accrued_interest - redirected;
+    deposit.accrued_interest += accrued_interest;
+
+    // interest of depositors taking payouts waits for the next settlement
+    if deposit.payout_to.is_some() {
+        let unpaid = receipt_to_stable(accrued_interest, state.exchange_rate);
+        deposit.unpaid_interest += unpaid;
+        let mut state: State = read_state(storage)?;
+        state.total_unpaid_interest += unpaid;
+        store_state(storage, &state)?;
+        return Ok(Uint128::zero());
+    }
+    deposit.last_balance += accrued_interest;
+    Ok(accrued_interest)
+}
+
+/// Accrues interest for `addresses` and mints it as receipt tokens,
+/// or pays it out to depositors with `payout_to` while liquidity allows
+pub fn settle(deps: DepsMut, env: Env, addresses: Vec<String>) -> Result<Response, ContractError> {
+    let accounts = addresses
+        .iter()
+        .map(|address| {
+            let address = deps.api.addr_validate(address)?;
+            let address_raw = deps.api.addr_canonicalize(address.as_str())?;
+            Ok((address_raw, address))
+        })
+        .collect::<StdResult<Vec<(CanonicalAddr, Addr)>>>()?;
+
+    settle_accounts(deps, env, accounts, "settle")
+}
+
+/// Settles a page of depositors in address order
+pub fn settle_range(
+    deps: DepsMut,
+    env: Env,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> Result<Response, ContractError> {
+    let start_after = match start_after {
+        Some(start_after) => Some(deps.api.addr_canonicalize(&start_after)?),
+        None => None,
+    };
+    let accounts = read_deposit_addresses(deps.storage, start_after, limit)?
+        .into_iter()
+        .map(|address_raw| {
+            let address = deps.api.addr_humanize(&address_raw)?;
+            Ok((address_raw, address))
+        })
+        .collect::<StdResult<Vec<(CanonicalAddr, Addr)>>>()?;
+    let last = accounts.last().map(|(_, address)| address.to_string());
+
+    let response = settle_accounts(deps, env, accounts, "settle_range")?;
+    Ok(match last {
+        Some(last) => response.add_attribute("last", last),
+        None => response,
+    })
+}
+
+pub fn settle_accounts(
+    deps: DepsMut,
+    env: Env,
+    accounts: Vec<(CanonicalAddr, Addr)>,
+    action: &str,
+) -> Result<Response, ContractError> {
+    let time = env.block.time.seconds();
+    let config: Config = read_config(deps.storage)?;
+    let aterra_contract = deps.api.addr_humanize(&config.aterra_contract)?;
+
+    // interest payouts are made from liquid funds nobody is queued for,
+    // including the funds set aside for them
+    let state: State = read_state(deps.storage)?;
+    let mut available = if state.total_queued.is_zero() {
+        query_liquidity(deps.as_ref(), &env, &config, &state)? + state.total_unpaid_interest
+    } else {
+        Uint128::zero()
+    };
+
+    let mut msgs: Vec<CosmosMsg> = vec![];
+    let mut settled: u32 = 0;
+    let mut total_interest = Uint128::zero();
+    let mut total_paid = Uint128::zero();
+    for (address_raw, address) in accounts.into_iter() {
+        let mut deposit = read_deposit_info(deps.storage, &address_raw)?;
+        // settling within a day would restart the day without accruing
+        let accrued_interest =
+            if deposit.initial_interaction == 0 || time - deposit.last_interaction < DURATION {
+                Uint128::zero()
+            } else {
+                let accrued_interest =
+                    accrue_interest(deps.storage, &config, &address_raw, &mut deposit, time)?;
+                update_working_balance(deps.storage, &config, &address_raw, &mut deposit, time)?;
+                accrued_interest
+            };
+
+        let mut paid = Uint128::zero();
+        if let Some(payout_to) = &deposit.payout_to {
+            if !deposit.unpaid_interest.is_zero() && deposit.unpaid_interest <= available {
+                paid = deposit.unpaid_interest;
+                available -= paid;
+                deposit.unpaid_interest = Uint128::zero();
+                msgs.push(CosmosMsg::Bank(BankMsg::Send {
+                    to_address: deps.api.addr_humanize(payout_to)?.to_string(),
+                    amount: vec![Coin {
+                        denom: config.stable_denom.clone(),
+                        amount: paid,
+                    }],
+                }));
+            }
+        }
+        store_deposit_info(deps.storage, &address_raw, &deposit)?;
+        if accrued_interest.is_zero() && paid.is_zero() {
+            continue;
+        }
+
+        if !accrued_interest.is_zero() {
+            msgs.push(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: aterra_contract.to_string(),
                 funds: vec![],
-                msg: to_binary(&Cw20ExecuteMsg::Burn {
-                    amount: burn_amount,
+                msg: to_binary(&Cw20ExecuteMsg::Mint {
+                    recipient: address.to_string(),
+                    amount: accrued_interest,
                 })?,
-            }),
-            CosmosMsg::Bank(BankMsg::Send {
-                to_address: ident.to_string(),
-                amount: vec![Coin {
-                    denom: config.stable_denom,
-                    amount: withdraw_amount,
-                }],
-            }),
-        ])
-        .add_attributes(vec![attr("action", "redeem_all_stable")]))
+            }));
+        }
+        settled += 1;
+        total_interest += accrued_interest;
+        total_paid += paid;
+    }
+
+    if !total_paid.is_zero() {
+        let mut state: State = read_state(deps.storage)?;
+        state.total_unpaid_interest -= total_paid;
+        store_state(deps.storage, &state)?;
+    }
+    if !total_interest.is_zero() {
+        store_tvl_indice(
+            deps.storage,
+            &mut Tvl {
+                epoch: time,
+                tvl: total_interest,
+            },
+            1,
+        )?;
+    }
+
+    Ok(Response::new().add_messages(msgs).add_attributes(vec![
+        attr("action", action),
+        attr("settled", settled.to_string()),
+        attr("interest", total_interest),
+        attr("interest_paid", total_paid),
+    ]))
+}
+
+/// Pays future interest to `to` in stable coins instead of compounding it,
+/// compounding again if unset. Settles the sender first.
+pub fn set_interest_payout(
+    mut deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    to: Option<String>,
+) -> Result<Response, ContractError> {
+    let sender_raw = deps.api.addr_canonicalize(info.sender.as_str())?;
+    let response = settle_accounts(
+        deps.branch(),
+        env,
+        vec![(sender_raw.clone(), info.sender)],
+        "set_interest_payout",
+    )?;
+
+    let mut deposit = read_deposit_info(deps.storage, &sender_raw)?;
+    deposit.payout_to = match &to {
+        Some(to) => Some(
+            deps.api
+                .addr_canonicalize(deps.api.addr_validate(to)?.as_str())?,
+        ),
+        None => {
+            if !deposit.unpaid_interest.is_zero() {
+                return Err(ContractError::InterestUnpaid(
+                    deposit.unpaid_interest.u128(),
+                ));
+            }
+            None
+        }
+    };
+    store_deposit_info(deps.storage, &sender_raw, &deposit)?;
+
+    Ok(response.add_attribute("payout_to", to.unwrap_or_default()))
+}
+
+/// Releases redeemed stable coins to `recipient`, either by starting an
+/// unbonding claimed by `redeemer`, paying out immediately or queueing
+/// the withdrawal.
+/// In native staking mode the amount is undelegated at the next epoch.
+pub fn withdraw_stable(
+    deps: DepsMut,
+    env: &Env,
+    config: &Config,
+    redeemer: &Addr,
+    recipient: &Addr,
+    amount: Uint128,
+    msg: Option<Binary>,
+) -> Result<(Vec<CosmosMsg>, &'static str), ContractError> {
+    if config.unbonding_period > 0 {
+        if msg.is_some() {
+            return Err(ContractError::UnbondingCallback {});
+        }
+
+        // undelegations are batched to stay within the chain's unbonding
+        // entries, interest beyond the delegated principal is paid from
+        // liquid funds
+        if config.native_staking {
+            let mut state: State = read_state(deps.storage)?;
+            let undelegatable = state
+                .total_delegated
+                .saturating_sub(state.pending_undelegation);
+            state.pending_undelegation += std::cmp::min(amount, undelegatable);
+            store_state(deps.storage, &state)?;
+        }
+
+        start_unbonding(deps, env, config, redeemer, recipient, amount)?;
+        return Ok((vec![], "unbonding"));
+    }
+
+    let (payout, queued) = pay_or_enqueue(deps, env, config, recipient, amount, msg)?;
+    let payout_status = if queued.is_zero() {
+        "paid"
+    } else if queued == amount {
+        "queued"
+    } else {
+        "partially_queued"
+    };
+    Ok((payout, payout_status))
+}
+
+/// Pays `funds` to `recipient`, executing it with `msg` when set
+/// so redemptions can be composed with other contracts
+pub fn payout_msg(recipient: &Addr, funds: Vec<Coin>, msg: Option<Binary>) -> CosmosMsg {
+    match msg {
+        Some(msg) => CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: recipient.to_string(),
+            funds,
+            msg,
+        }),
+        None => CosmosMsg::Bank(BankMsg::Send {
+            to_address: recipient.to_string(),
+            amount: funds,
+        }),
+    }
 }
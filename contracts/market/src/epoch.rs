@@ -0,0 +1,99 @@
+use std::cmp::min;
+
+use cosmwasm_std::{attr, BankMsg, Coin, DepsMut, Env, Event, MessageInfo, Response, Uint128};
+
+use crate::error::ContractError;
+use crate::harvest::harvest_rewards;
+use crate::queue::query_liquidity;
+use crate::rewards::adjust_emission_rate;
+use crate::slashing::reconcile;
+use crate::staking::undelegate_pending;
+use crate::state::{read_config, read_state, store_state, store_tvl_indice, Config, State, Tvl};
+use crate::strategy::{allocate_msgs, skim_yield};
+
+/// Runs the periodic housekeeping at most once per `epoch_period`, lets the
+/// distribution model adjust the emission rate and pays the caller
+/// `epoch_bounty` from the reserve instead of the harvest bounty
+pub fn execute_epoch_operations(
+    mut deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    let config: Config = read_config(deps.storage)?;
+    let time = env.block.time.seconds();
+    let state: State = read_state(deps.storage)?;
+    if time < state.last_epoch_time + config.epoch_period {
+        return Err(ContractError::EpochNotPassed(
+            state.last_epoch_time + config.epoch_period,
+        ));
+    }
+
+    store_tvl_indice(
+        deps.storage,
+        &mut Tvl {
+            epoch: time,
+            tvl: Uint128::zero(),
+        },
+        1,
+    )?;
+
+    let mut operations: Vec<(&str, Response)> = vec![];
+    if config.native_staking {
+        operations.push(("reconcile", reconcile(deps.branch(), env.clone())?));
+        match harvest_rewards(deps.branch(), env.clone(), None) {
+            Ok(response) => operations.push(("harvest", response)),
+            Err(ContractError::NothingToHarvest {}) => {}
+            Err(err) => return Err(err),
+        }
+        operations.push(("undelegate", undelegate_pending(deps.branch(), &env)?));
+    } else {
+        operations.push(("skim_yield", skim_yield(deps.branch(), env.clone())?));
+    }
+
+    // the bounty is paid from liquid reserve funds only,
+    // held back from the allocation
+    let mut state: State = read_state(deps.storage)?;
+    let liquidity = query_liquidity(deps.as_ref(), &env, &config, &state)?;
+    let bounty = min(min(config.epoch_bounty, state.reserve), liquidity);
+    state.reserve -= bounty;
+    state.last_epoch_time = time;
+    state.last_epoch_exchange_rate = state.exchange_rate;
+    store_state(deps.storage, &state)?;
+
+    if !config.native_staking {
+        let (msgs, allocated) = allocate_msgs(deps.branch(), &env, &config, bounty)?;
+        operations.push((
+            "allocate",
+            Response::new()
+                .add_messages(msgs)
+                .add_attributes(vec![attr("allocated", allocated)]),
+        ));
+    }
+    operations.push((
+        "adjust_emission_rate",
+        adjust_emission_rate(deps.branch(), env.clone())?,
+    ));
+
+    let mut response = Response::new();
+    for (operation, res) in operations.into_iter() {
+        response = response
+            .add_submessages(res.messages)
+            .add_events(res.events)
+            .add_event(Event::new(operation).add_attributes(res.attributes));
+    }
+    if !bounty.is_zero() {
+        response = response.add_message(BankMsg::Send {
+            to_address: info.sender.to_string(),
+            amount: vec![Coin {
+                denom: config.stable_denom,
+                amount: bounty,
+            }],
+        });
+    }
+
+    Ok(response.add_attributes(vec![
+        attr("action", "execute_epoch_operations"),
+        attr("tvl", state.tvl),
+        attr("bounty", bounty),
+    ]))
+}
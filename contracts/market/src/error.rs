@@ -36,6 +36,183 @@ pub enum ContractError {
     #[error("Deposit amount must be greater than 0 {0}")]
     ZeroDeposit(String),
 
+    #[error("Deposit amount must be at least {0}{1}")]
+    DepositTooSmall(u128, String),
+
+    #[error("Deposit exceeds the market TVL cap of {0}")]
+    TvlCapExceeded(u128),
+
+    #[error("Deposit exceeds the maximum depositor balance of {0}")]
+    MaxBalanceExceeded(u128),
+
+    #[error("Depositor is not allowlisted: {0}")]
+    NotAllowlisted(String),
+
+    #[error("Not enough {0} liquidity to pay out immediately")]
+    InsufficientLiquidity(String),
+
+    #[error("Nothing to claim")]
+    NothingToClaim {},
+
+    #[error("Redeem callbacks are unavailable while redemptions unbond")]
+    UnbondingCallback {},
+
+    #[error("Too many concurrent unbondings; claim matured unbondings first")]
+    TooManyUnbondings {},
+
+    #[error("Market is insolvent: losses exceeded all deposits")]
+    Insolvent {},
+
+    #[error("Native staking is not enabled for this market")]
+    NativeStakingDisabled {},
+
+    #[error("Native staking requires {0} as stable denom and an unbonding period")]
+    InvalidNativeStaking(String),
+
+    #[error("Native staking requires an unbonding period of at least {0} seconds")]
+    UnbondingPeriodTooShort(u64),
+
+    #[error("Validator not found: {0}")]
+    ValidatorNotFound(String),
+
+    #[error("Validator already registered: {0}")]
+    ValidatorExists(String),
+
+    #[error("No validators registered")]
+    NoValidators {},
+
+    #[error("No staking rewards to harvest")]
+    NothingToHarvest {},
+
+    #[error("No swap route for {0}")]
+    SwapRouteNotFound(String),
+
+    #[error("Invalid swap route: {0}")]
+    InvalidSwapRoute(String),
+
+    #[error("Nothing to sweep: {0}")]
+    NothingToSweep(String),
+
+    #[error("Invalid strategy: {0}")]
+    InvalidStrategy(String),
+
+    #[error("Strategy not found: {0}")]
+    StrategyNotFound(String),
+
+    #[error("Strategy already registered: {0}")]
+    StrategyExists(String),
+
+    #[error("Too many strategies registered")]
+    TooManyStrategies {},
+
+    #[error("Strategies are unavailable in native staking mode")]
+    StrategyWithNativeStaking {},
+
+    #[error("Withdraw the strategy position before removing it: {0}")]
+    StrategyPositionOpen(String),
+
+    #[error("Liquidity buffer must not exceed 1")]
+    InvalidLiquidityBuffer {},
+
+    #[error("Harvest bounty must not exceed 1")]
+    InvalidHarvestBounty {},
+
+    #[error("Epoch operations can not run before {0}")]
+    EpochNotPassed(u64),
+
+    #[error("An epoch bounty requires a nonzero epoch period")]
+    InvalidEpochPeriod {},
+
+    #[error("No reward token configured")]
+    RewardsDisabled {},
+
+    #[error("Invalid reward stream: {0}")]
+    InvalidStream(String),
+
+    #[error("Reward stream not found: {0}")]
+    StreamNotFound(u64),
+
+    #[error("Too many reward streams open")]
+    TooManyStreams {},
+
+    #[error("Reward stream {0} is still claimable")]
+    StreamActive(u64),
+
+    #[error("No governance token configured")]
+    BoostDisabled {},
+
+    #[error("Lock duration must be between 1 and {0} seconds and extend the lock")]
+    InvalidLockDuration(u64),
+
+    #[error("Governance tokens are locked until {0}")]
+    LockNotExpired(u64),
+
+    #[error("No governance tokens locked")]
+    NoLock {},
+
+    #[error("Max boost must be at least 1")]
+    InvalidMaxBoost {},
+
+    #[error("Governance token can not change while tokens are locked")]
+    LocksOutstanding {},
+
+    #[error("Referral share must not exceed 1")]
+    InvalidReferralShare {},
+
+    #[error("Depositors can not refer themselves")]
+    SelfReferral {},
+
+    #[error("Interest of {0} must be paid out before compounding again")]
+    InterestUnpaid(u128),
+
+    #[error("Interest can only be redirected to another address, with a share up to 1")]
+    InvalidRedirect {},
+
+    #[error("The market is not in prize mode")]
+    PrizeModeDisabled {},
+
+    #[error("Prize mode needs a draw period longer than the reveal period and 1 to 10 winners")]
+    InvalidDrawSchedule {},
+
+    #[error("Commitments for draw {0} are closed")]
+    CommitmentClosed(u64),
+
+    #[error("Secrets for draw {0} can not be revealed now")]
+    RevealNotOpen(u64),
+
+    #[error("Secret does not match the commitment")]
+    InvalidReveal {},
+
+    #[error("Draw {0} can not be awarded now")]
+    DrawNotReady(u64),
+
+    #[error("Draw {0} has been awarded")]
+    DrawAwarded(u64),
+
+    #[error("Receipt tokens can not be split in prize mode")]
+    SplitDisabled {},
+
+    #[error("Maturity must be in the future and not listed yet")]
+    InvalidMaturity {},
+
+    #[error("Maturity {0} not found")]
+    MaturityNotFound(u64),
+
+    #[error("Maturity {0} has not been reached")]
+    NotMatured(u64),
+
+    #[error("Maturity {0} has been reached")]
+    Matured(u64),
+
+    #[error("Amount exceeds the balance of {0}")]
+    ExceedsBalance(u128),
+
+    #[error("Junior tranche is not enabled")]
+    JuniorTrancheDisabled {},
+
+    #[error("Junior tranche is already enabled")]
+    JuniorTrancheEnabled {},
+
     #[error("Repay amount must be greater than 0 {0}")]
     ZeroRepay(String),
 }
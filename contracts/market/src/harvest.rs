@@ -0,0 +1,149 @@
+use cosmwasm_std::{
+    attr, Addr, BankMsg, Coin, CosmosMsg, Decimal, Deps, DepsMut, DistributionMsg, Env,
+    MessageInfo, Response, StdResult, Uint128,
+};
+
+use crate::error::ContractError;
+use crate::staking::delegate_msgs;
+use crate::state::{
+    read_config, read_state, read_swap_route, read_validators, store_state, store_validator,
+    Config, HarvestResponse, State,
+};
+use crate::swap::swap_msg;
+use crate::tranche::{senior_interest, waterfall};
+
+pub fn harvest(deps: DepsMut, env: Env, info: MessageInfo) -> Result<Response, ContractError> {
+    harvest_rewards(deps, env, Some(info.sender))
+}
+
+/// Compounds the staking rewards, paying `harvest_bounty` of them to
+/// `bounty_recipient` if set
+pub fn harvest_rewards(
+    mut deps: DepsMut,
+    env: Env,
+    bounty_recipient: Option<Addr>,
+) -> Result<Response, ContractError> {
+    let config: Config = read_config(deps.storage)?;
+    if !config.native_staking {
+        return Err(ContractError::NativeStakingDisabled {});
+    }
+
+    // rewards the chain withdrew on delegation changes are already liquid
+    let mut state: State = read_state(deps.storage)?;
+    let mut rewards = state.withdrawn_rewards;
+    state.withdrawn_rewards = Uint128::zero();
+    store_state(deps.storage, &state)?;
+
+    let mut msgs: Vec<CosmosMsg> = vec![];
+    let mut swaps: Vec<Coin> = vec![];
+    for mut validator in read_validators(deps.storage)?.into_iter() {
+        // a delegation change earlier in this block withdrew them already
+        if validator.rewards_height == env.block.height {
+            continue;
+        }
+
+        let delegation = match deps
+            .querier
+            .query_delegation(env.contract.address.as_str(), validator.address.as_str())?
+        {
+            Some(delegation) => delegation,
+            None => continue,
+        };
+
+        let mut withdraw = false;
+        for coin in delegation.accumulated_rewards.into_iter() {
+            if coin.amount.is_zero() {
+                continue;
+            }
+
+            if coin.denom == config.stable_denom {
+                rewards += coin.amount;
+            } else if read_swap_route(deps.storage, &coin.denom)?.is_some() {
+                match swaps.iter_mut().find(|c| c.denom == coin.denom) {
+                    Some(swap) => swap.amount += coin.amount,
+                    None => swaps.push(coin),
+                }
+            } else {
+                continue;
+            }
+            withdraw = true;
+        }
+        if !withdraw {
+            continue;
+        }
+
+        validator.rewards_height = env.block.height;
+        store_validator(deps.storage, &validator)?;
+        msgs.push(CosmosMsg::Distribution(
+            DistributionMsg::WithdrawDelegatorReward {
+                validator: validator.address,
+            },
+        ));
+    }
+
+    // swapped rewards are accounted at the least amount the swap returns
+    for swap in swaps.into_iter() {
+        let route = read_swap_route(deps.storage, &swap.denom)?
+            .ok_or_else(|| ContractError::SwapRouteNotFound(swap.denom.clone()))?;
+        let (msg, min_return) = swap_msg(deps.as_ref(), &env, &route, swap.amount)?;
+        msgs.push(msg);
+        rewards += min_return;
+    }
+
+    if rewards.is_zero() {
+        return Err(ContractError::NothingToHarvest {});
+    }
+
+    let mut bounty = Uint128::zero();
+    if let Some(bounty_recipient) = bounty_recipient {
+        bounty = rewards * config.harvest_bounty;
+        if !bounty.is_zero() {
+            msgs.push(CosmosMsg::Bank(BankMsg::Send {
+                to_address: bounty_recipient.to_string(),
+                amount: vec![Coin {
+                    denom: config.stable_denom.clone(),
+                    amount: bounty,
+                }],
+            }));
+        }
+    }
+    let compounded = rewards - bounty;
+    msgs.extend(delegate_msgs(deps.branch(), &env, &config, compounded)?);
+
+    // the compounded rewards back every outstanding receipt token, or only
+    // the senior interest with a junior tranche taking the rest
+    let mut state: State = read_state(deps.storage)?;
+    let interest = senior_interest(&config, &mut state, env.block.time.seconds());
+    let senior_yield = if state.junior_supply.is_zero() {
+        compounded
+    } else {
+        waterfall(&mut state, compounded, interest)
+    };
+    if state.tvl.is_zero() {
+        state.reserve += senior_yield;
+    } else {
+        state.exchange_rate = state.exchange_rate + Decimal::from_ratio(senior_yield, state.tvl);
+    }
+    state.last_harvest_time = env.block.time.seconds();
+    state.last_harvest_amount = rewards;
+    state.total_harvested += rewards;
+    store_state(deps.storage, &state)?;
+
+    Ok(Response::new().add_messages(msgs).add_attributes(vec![
+        attr("action", "harvest"),
+        attr("rewards", rewards),
+        attr("bounty", bounty),
+        attr("compounded", compounded),
+        attr("exchange_rate", state.exchange_rate.to_string()),
+    ]))
+}
+
+pub fn query_harvest(deps: Deps) -> StdResult<HarvestResponse> {
+    let state: State = read_state(deps.storage)?;
+    Ok(HarvestResponse {
+        last_harvest_time: state.last_harvest_time,
+        last_harvest_amount: state.last_harvest_amount,
+        total_harvested: state.total_harvested,
+        exchange_rate: state.exchange_rate,
+    })
+}
@@ -1,5 +1,5 @@
 use crate::state::DepositInfo;
-use cosmwasm_std::{Decimal, StdError, StdResult, Uint128};
+use cosmwasm_std::{Decimal, Fraction, StdError, StdResult, Uint128};
 use std::str::FromStr;
 
 /*
@@ -18,26 +18,39 @@ pub fn calculate_accrued_interest(
     interest_rate: Decimal,
     days: u64,
 ) -> StdResult<Uint128> {
-    if days == 0 {
-        return Ok(Uint128::zero());
-    }
-    let mut interested_balance = deposit.last_balance.clone();
+    Ok(compound_interest(deposit.last_balance, interest_rate, days))
+}
+
+/// Interest `balance` earns compounded over `days` periods
+pub fn compound_interest(balance: Uint128, interest_rate: Decimal, days: u64) -> Uint128 {
+    let mut interested_balance = balance;
     let mut counter: u64 = 0;
     while counter < days {
         interested_balance += interested_balance * interest_rate;
         counter += 1;
     }
-    Ok(interested_balance - deposit.last_balance.clone())
+    interested_balance - balance
 }
 
 pub fn get_decimals(value: String) -> StdResult<Decimal> {
-    let parts: &[&str] = &*value.split('.').collect::<Vec<&str>>();
+    let parts: &[&str] = &value.split('.').collect::<Vec<&str>>();
     match parts.len() {
         1 => Ok(Decimal::zero()),
         2 => {
-            let decimals = Decimal::from_str(&*("0.".to_owned() + parts[1]))?;
+            let decimals = Decimal::from_str(&("0.".to_owned() + parts[1]))?;
             Ok(decimals)
         }
         _ => Err(StdError::generic_err("Unexpected number of dots")),
     }
 }
+
+/// Receipt tokens minted for `amount` stable coins,
+/// `exchange_rate` must not be zero
+pub fn stable_to_receipt(amount: Uint128, exchange_rate: Decimal) -> Uint128 {
+    amount.multiply_ratio(exchange_rate.denominator(), exchange_rate.numerator())
+}
+
+/// Stable coins paid out for `amount` receipt tokens
+pub fn receipt_to_stable(amount: Uint128, exchange_rate: Decimal) -> Uint128 {
+    amount * exchange_rate
+}
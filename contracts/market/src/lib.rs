@@ -1,11 +1,31 @@
 // pub mod borrow;
+pub mod allowlist;
+pub mod boost;
 pub mod contract;
 pub mod deposit;
+pub mod epoch;
 pub mod error;
-// pub mod querier;
+pub mod harvest;
 pub mod helpers;
+pub mod prize;
+pub mod querier;
+pub mod queue;
+pub mod redirect;
+pub mod referral;
+// generated by rust-protobuf, linted against an older toolchain
+#[allow(unknown_lints, renamed_and_removed_lints)]
+#[allow(unused_parens, mismatched_lifetime_syntaxes)]
 pub mod response;
+pub mod rewards;
+pub mod slashing;
+pub mod split;
+pub mod staking;
 pub mod state;
+pub mod strategy;
+pub mod streams;
+pub mod swap;
+pub mod tranche;
+pub mod unbonding;
 
 #[cfg(test)]
 mod testing;
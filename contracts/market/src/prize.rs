@@ -0,0 +1,437 @@
+use std::convert::TryInto;
+
+use cosmwasm_std::{
+    attr, Binary, Deps, DepsMut, Env, MessageInfo, Response, StdError, StdResult, Storage, Uint128,
+};
+use sha2::{Digest, Sha256};
+
+use crate::contract::DURATION;
+use crate::error::ContractError;
+use crate::helpers::receipt_to_stable;
+use crate::queue::pay_or_enqueue;
+use crate::state::{
+    is_operator, read_commitment, read_commitments, read_config, read_deposit_addresses,
+    read_deposit_info, read_draw, read_operators, read_prize, read_state, remove_operator,
+    store_commitment, store_draw, store_operator, store_prize, store_state, Commitment, Config,
+    Draw, DrawResponse, OperatorsResponse, PrizeResponse, State, Twab,
+};
+
+/// Maximum number of prizes per draw
+pub const MAX_PRIZE_WINNERS: u32 = 10;
+
+pub fn draw_of(config: &Config, time: u64) -> u64 {
+    time.saturating_sub(config.draw_start) / config.draw_period
+}
+
+pub fn draw_start_time(config: &Config, draw_id: u64) -> u64 {
+    config.draw_start + draw_id * config.draw_period
+}
+
+/// Accumulates `balance` held since the last update into `twab`, closing
+/// the draw it was accumulated in when a new draw has started
+pub fn update_twab(config: &Config, twab: &mut Twab, balance: Uint128, time: u64) {
+    twab.lifetime += balance.multiply_ratio(time - twab.updated, 1u64);
+    let draw_id = draw_of(config, time);
+    if draw_id == twab.draw_id {
+        twab.cumulative += balance.multiply_ratio(time - twab.updated, 1u64);
+    } else {
+        // a balance untouched for a whole draw was held throughout it
+        twab.previous = if draw_id == twab.draw_id + 1 {
+            let draw_end = draw_start_time(config, draw_id);
+            twab.cumulative + balance.multiply_ratio(draw_end - twab.updated, 1u64)
+        } else {
+            balance.multiply_ratio(config.draw_period, 1u64)
+        };
+        twab.cumulative = balance.multiply_ratio(time - draw_start_time(config, draw_id), 1u64);
+        twab.draw_id = draw_id;
+    }
+    twab.updated = time;
+}
+
+/// Accumulates the market's `tvl` into `State.total_twab` in prize mode.
+/// Must run before `tvl` changes.
+pub fn update_total_twab(storage: &mut dyn Storage, config: &Config, time: u64) -> StdResult<()> {
+    if !config.prize_mode {
+        return Ok(());
+    }
+
+    let mut state: State = read_state(storage)?;
+    let tvl = state.tvl;
+    update_twab(config, &mut state.total_twab, tvl, time);
+    store_state(storage, &state)
+}
+
+/// Final twab of the draw before the one at `time`
+fn previous_twab(config: &Config, twab: &Twab, balance: Uint128, time: u64) -> Uint128 {
+    let mut twab = twab.clone();
+    update_twab(config, &mut twab, balance, time);
+    twab.previous
+}
+
+fn assert_prize_mode(config: &Config) -> Result<(), ContractError> {
+    if !config.prize_mode {
+        return Err(ContractError::PrizeModeDisabled {});
+    }
+    Ok(())
+}
+
+pub fn register_operator(
+    deps: DepsMut,
+    info: MessageInfo,
+    operator: String,
+) -> Result<Response, ContractError> {
+    let config: Config = read_config(deps.storage)?;
+    if deps.api.addr_canonicalize(info.sender.as_str())? != config.owner_addr {
+        return Err(ContractError::Unauthorized {});
+    }
+    assert_prize_mode(&config)?;
+
+    let operator = deps.api.addr_validate(&operator)?;
+    store_operator(
+        deps.storage,
+        &deps.api.addr_canonicalize(operator.as_str())?,
+    )?;
+
+    Ok(Response::new().add_attributes(vec![
+        attr("action", "register_operator"),
+        attr("operator", operator),
+    ]))
+}
+
+pub fn deregister_operator(
+    deps: DepsMut,
+    info: MessageInfo,
+    operator: String,
+) -> Result<Response, ContractError> {
+    let config: Config = read_config(deps.storage)?;
+    if deps.api.addr_canonicalize(info.sender.as_str())? != config.owner_addr {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    remove_operator(deps.storage, &deps.api.addr_canonicalize(&operator)?);
+
+    Ok(Response::new().add_attributes(vec![
+        attr("action", "remove_operator"),
+        attr("operator", operator),
+    ]))
+}
+
+/// Stores an operator's commitment for a draw that has not ended yet,
+/// replacing an earlier one
+pub fn commit_randomness(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    draw_id: u64,
+    commitment: Binary,
+) -> Result<Response, ContractError> {
+    let config: Config = read_config(deps.storage)?;
+    assert_prize_mode(&config)?;
+    let operator_raw = deps.api.addr_canonicalize(info.sender.as_str())?;
+    if !is_operator(deps.storage, &operator_raw) {
+        return Err(ContractError::Unauthorized {});
+    }
+    if draw_id < draw_of(&config, env.block.time.seconds()) {
+        return Err(ContractError::CommitmentClosed(draw_id));
+    }
+
+    store_commitment(
+        deps.storage,
+        draw_id,
+        &operator_raw,
+        &Commitment {
+            commitment,
+            secret: None,
+        },
+    )?;
+
+    Ok(Response::new().add_attributes(vec![
+        attr("action", "commit_randomness"),
+        attr("operator", info.sender),
+        attr("draw_id", draw_id.to_string()),
+    ]))
+}
+
+pub fn reveal_randomness(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    draw_id: u64,
+    secret: Binary,
+) -> Result<Response, ContractError> {
+    let config: Config = read_config(deps.storage)?;
+    assert_prize_mode(&config)?;
+    let time = env.block.time.seconds();
+    let draw_end = draw_start_time(&config, draw_id + 1);
+    if time < draw_end || time >= draw_end + config.reveal_period {
+        return Err(ContractError::RevealNotOpen(draw_id));
+    }
+
+    let operator_raw = deps.api.addr_canonicalize(info.sender.as_str())?;
+    let mut commitment = match read_commitment(deps.storage, draw_id, &operator_raw)? {
+        Some(commitment) => commitment,
+        None => return Err(ContractError::InvalidReveal {}),
+    };
+    if Sha256::digest(secret.as_slice())[..] != commitment.commitment[..] {
+        return Err(ContractError::InvalidReveal {});
+    }
+    commitment.secret = Some(secret);
+    store_commitment(deps.storage, draw_id, &operator_raw, &commitment)?;
+
+    Ok(Response::new().add_attributes(vec![
+        attr("action", "reveal_randomness"),
+        attr("operator", info.sender),
+        attr("draw_id", draw_id.to_string()),
+    ]))
+}
+
+/// Pools the interest of a finished draw, and of earlier draws nobody drew,
+/// and picks its winning positions from the revealed secrets. Without
+/// reveals or depositors the prize rolls over to the next draw, as it does
+/// when an operator withholds its secret, which costs it its registration.
+pub fn draw_prizes(deps: DepsMut, env: Env, draw_id: u64) -> Result<Response, ContractError> {
+    let config: Config = read_config(deps.storage)?;
+    assert_prize_mode(&config)?;
+    let time = env.block.time.seconds();
+    let reveal_end = draw_start_time(&config, draw_id + 1) + config.reveal_period;
+    if time < reveal_end || draw_of(&config, time) != draw_id + 1 {
+        return Err(ContractError::DrawNotReady(draw_id));
+    }
+    if read_draw(deps.storage, draw_id)?.is_some() {
+        return Err(ContractError::DrawAwarded(draw_id));
+    }
+
+    let mut hasher = Sha256::new();
+    let mut reveals: u32 = 0;
+    let mut defaulted: u32 = 0;
+    for (operator, commitment) in read_commitments(deps.storage, draw_id)?.into_iter() {
+        match commitment.secret {
+            Some(secret) => {
+                hasher.update(secret.as_slice());
+                reveals += 1;
+            }
+            // a withheld secret could bias the seed, the draw does not count
+            None => {
+                remove_operator(deps.storage, &operator);
+                defaulted += 1;
+            }
+        }
+    }
+    let seed = hasher.finalize().to_vec();
+
+    let mut state: State = read_state(deps.storage)?;
+    let tvl = state.tvl;
+    update_twab(&config, &mut state.total_twab, tvl, time);
+    let total_twab = state.total_twab.previous;
+
+    // the pooled interest is what the deposits would have accrued since
+    // the end of the last drawn draw, so missed draws roll into this one
+    let drawn_twab = state
+        .total_twab
+        .lifetime
+        .saturating_sub(state.total_twab.cumulative);
+    let pooled_twab = drawn_twab.saturating_sub(state.drawn_twab);
+    state.drawn_twab = drawn_twab;
+    let interest = (pooled_twab * config.interest_rate).multiply_ratio(1u64, DURATION);
+    state.accrued_interest_payments += interest;
+    let prize = receipt_to_stable(interest, state.exchange_rate) + state.prize_rollover;
+
+    let mut draw = Draw {
+        id: draw_id,
+        seed: Binary::from(seed.clone()),
+        total_twab,
+        prize_per_winner: Uint128::zero(),
+        targets: vec![],
+        next_target: 0,
+        winners: vec![],
+        cursor: None,
+        walked: Uint128::zero(),
+        awarded: false,
+    };
+    if reveals == 0 || defaulted > 0 || total_twab.is_zero() {
+        state.prize_rollover = prize;
+        draw.awarded = true;
+    } else {
+        draw.prize_per_winner = prize.multiply_ratio(1u64, config.prize_winners);
+        state.prize_rollover = prize - draw.prize_per_winner * Uint128::from(config.prize_winners);
+        draw.targets = (0..config.prize_winners)
+            .map(|i| {
+                let hash = Sha256::digest(&[seed.as_slice(), &i.to_be_bytes()].concat());
+                let value = u128::from_be_bytes(hash[0..16].try_into().unwrap());
+                Uint128::from(value % total_twab.u128())
+            })
+            .collect();
+        draw.targets.sort();
+    }
+    store_state(deps.storage, &state)?;
+    store_draw(deps.storage, &draw)?;
+
+    Ok(Response::new().add_attributes(vec![
+        attr("action", "draw_prizes"),
+        attr("draw_id", draw_id.to_string()),
+        attr("reveals", reveals.to_string()),
+        attr("defaulted", defaulted.to_string()),
+        attr("total_twab", total_twab),
+        attr("prize", prize),
+        attr("prize_per_winner", draw.prize_per_winner),
+    ]))
+}
+
+/// Walks the next `limit` depositors, awarding a prize to each whose twab
+/// range covers a drawn position. Prizes not found by the end of the walk,
+/// or once the next draw has ended, roll over.
+pub fn award_prizes(
+    deps: DepsMut,
+    env: Env,
+    draw_id: u64,
+    limit: Option<u32>,
+) -> Result<Response, ContractError> {
+    let config: Config = read_config(deps.storage)?;
+    assert_prize_mode(&config)?;
+    let time = env.block.time.seconds();
+    let mut draw = match read_draw(deps.storage, draw_id)? {
+        Some(draw) => draw,
+        None => return Err(ContractError::DrawNotReady(draw_id)),
+    };
+    if draw.awarded {
+        return Err(ContractError::DrawAwarded(draw_id));
+    }
+
+    let mut state: State = read_state(deps.storage)?;
+    let mut awarded = Uint128::zero();
+    // twabs of the draw are only known during the following draw
+    let accounts = if draw_of(&config, time) == draw_id + 1 {
+        read_deposit_addresses(deps.storage, draw.cursor.clone(), limit)?
+    } else {
+        vec![]
+    };
+    let targets = draw.targets.len() as u32;
+    for address_raw in accounts.iter() {
+        let deposit = read_deposit_info(deps.storage, address_raw)?;
+        let twab = previous_twab(&config, &deposit.twab, deposit.last_balance, time);
+        let walked = draw.walked + twab;
+        while draw.next_target < targets && draw.targets[draw.next_target as usize] < walked {
+            let mut prize = read_prize(deps.storage, address_raw);
+            prize.claimable += draw.prize_per_winner;
+            prize.total_won += draw.prize_per_winner;
+            store_prize(deps.storage, address_raw, &prize)?;
+
+            draw.winners.push(address_raw.clone());
+            draw.next_target += 1;
+            awarded += draw.prize_per_winner;
+        }
+        draw.walked = walked;
+        draw.cursor = Some(address_raw.clone());
+    }
+    state.total_prizes_claimable += awarded;
+
+    if accounts.is_empty() || draw.next_target == targets {
+        let unawarded = draw.prize_per_winner * Uint128::from(targets - draw.next_target);
+        state.prize_rollover += unawarded;
+        draw.awarded = true;
+    }
+    store_state(deps.storage, &state)?;
+    store_draw(deps.storage, &draw)?;
+
+    Ok(Response::new().add_attributes(vec![
+        attr("action", "award_prizes"),
+        attr("draw_id", draw_id.to_string()),
+        attr("awarded", awarded),
+        attr("finished", draw.awarded.to_string()),
+    ]))
+}
+
+pub fn claim_prize(deps: DepsMut, env: Env, info: MessageInfo) -> Result<Response, ContractError> {
+    let config: Config = read_config(deps.storage)?;
+    let winner_raw = deps.api.addr_canonicalize(info.sender.as_str())?;
+    let mut prize = read_prize(deps.storage, &winner_raw);
+    let claim_amount = prize.claimable;
+    if claim_amount.is_zero() {
+        return Err(ContractError::NothingToClaim {});
+    }
+
+    prize.claimable = Uint128::zero();
+    store_prize(deps.storage, &winner_raw, &prize)?;
+    let mut state: State = read_state(deps.storage)?;
+    state.total_prizes_claimable -= claim_amount;
+    store_state(deps.storage, &state)?;
+
+    // prizes are owed now and never wait for an unbonding
+    let (payout, queued) = pay_or_enqueue(deps, &env, &config, &info.sender, claim_amount, None)?;
+    let queued = !queued.is_zero();
+
+    Ok(Response::new().add_messages(payout).add_attributes(vec![
+        attr("action", "claim_prize"),
+        attr("winner", info.sender),
+        attr("claim_amount", claim_amount),
+        attr("queued", queued.to_string()),
+    ]))
+}
+
+pub fn query_draw(deps: Deps, env: Env, draw_id: Option<u64>) -> StdResult<DrawResponse> {
+    let config: Config = read_config(deps.storage)?;
+    if !config.prize_mode {
+        return Err(StdError::generic_err("The market is not in prize mode"));
+    }
+    let draw_id = draw_id.unwrap_or_else(|| draw_of(&config, env.block.time.seconds()));
+
+    let commitments = read_commitments(deps.storage, draw_id)?;
+    let draw = read_draw(deps.storage, draw_id)?;
+    let end_time = draw_start_time(&config, draw_id + 1);
+    Ok(DrawResponse {
+        id: draw_id,
+        start_time: draw_start_time(&config, draw_id),
+        end_time,
+        reveal_end_time: end_time + config.reveal_period,
+        commitments: commitments.len() as u32,
+        reveals: commitments
+            .iter()
+            .filter(|(_, commitment)| commitment.secret.is_some())
+            .count() as u32,
+        total_twab: draw.as_ref().map(|draw| draw.total_twab),
+        prize_per_winner: draw.as_ref().map(|draw| draw.prize_per_winner),
+        winners: match &draw {
+            Some(draw) => draw
+                .winners
+                .iter()
+                .map(|winner| Ok(deps.api.addr_humanize(winner)?.to_string()))
+                .collect::<StdResult<Vec<String>>>()?,
+            None => vec![],
+        },
+        awarded: draw.map(|draw| draw.awarded).unwrap_or(false),
+    })
+}
+
+pub fn query_prize(deps: Deps, env: Env, address: String) -> StdResult<PrizeResponse> {
+    let config: Config = read_config(deps.storage)?;
+    let state: State = read_state(deps.storage)?;
+    let address_raw = deps.api.addr_canonicalize(&address)?;
+    let prize = read_prize(deps.storage, &address_raw);
+    let deposit = read_deposit_info(deps.storage, &address_raw)?;
+
+    let (twab, total_twab) = if config.prize_mode {
+        let time = env.block.time.seconds();
+        let mut twab = deposit.twab;
+        update_twab(&config, &mut twab, deposit.last_balance, time);
+        let mut total_twab = state.total_twab;
+        update_twab(&config, &mut total_twab, state.tvl, time);
+        (twab.cumulative, total_twab.cumulative)
+    } else {
+        (Uint128::zero(), Uint128::zero())
+    };
+
+    Ok(PrizeResponse {
+        claimable: prize.claimable,
+        total_won: prize.total_won,
+        twab,
+        total_twab,
+    })
+}
+
+pub fn query_operators(deps: Deps) -> StdResult<OperatorsResponse> {
+    let operators = read_operators(deps.storage)?
+        .iter()
+        .map(|operator| Ok(deps.api.addr_humanize(operator)?.to_string()))
+        .collect::<StdResult<Vec<String>>>()?;
+    Ok(OperatorsResponse { operators })
+}
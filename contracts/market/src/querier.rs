@@ -0,0 +1,32 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use cosmwasm_std::{to_binary, Addr, Deps, QueryRequest, StdResult, WasmQuery};
+
+/// cw4 group `Member` query, used to approve depositors through an
+/// external membership contract
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum MembershipQueryMsg {
+    Member {
+        addr: String,
+        at_height: Option<u64>,
+    },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct MemberResponse {
+    pub weight: Option<u64>,
+}
+
+pub fn query_is_member(deps: Deps, membership_contract: Addr, addr: &Addr) -> StdResult<bool> {
+    let member: MemberResponse = deps.querier.query(&QueryRequest::Wasm(WasmQuery::Smart {
+        contract_addr: membership_contract.to_string(),
+        msg: to_binary(&MembershipQueryMsg::Member {
+            addr: addr.to_string(),
+            at_height: None,
+        })?,
+    }))?;
+
+    Ok(member.weight.is_some())
+}
@@ -0,0 +1,300 @@
+use std::cmp::min;
+
+use cosmwasm_std::{
+    attr, Addr, BankMsg, Binary, CanonicalAddr, Coin, CosmosMsg, Deps, DepsMut, Env, MessageInfo,
+    Response, StdResult, Storage, Uint128,
+};
+
+use terraswap::asset::AssetInfo;
+
+use crate::deposit::payout_msg;
+use crate::error::ContractError;
+use crate::state::{
+    read_claimable, read_config, read_queued_ids, read_queued_withdrawal, read_state, read_streams,
+    remove_queued_withdrawal, store_claimable, store_queued_ids, store_queued_withdrawal,
+    store_state, Config, QueuedWithdrawal, QueuedWithdrawalResponse, State,
+    WithdrawalQueueResponse,
+};
+use crate::strategy::strategy_withdraw_msgs;
+
+/// Maximum number of queued withdrawals filled by a single call
+const MAX_FILLS: u32 = 30;
+
+/// Stable coins held by the contract which are not set aside for claims,
+/// unbondings, interest payouts or the budgets of stable reward streams
+pub fn query_liquidity(
+    deps: Deps,
+    env: &Env,
+    config: &Config,
+    state: &State,
+) -> StdResult<Uint128> {
+    let balance = deps
+        .querier
+        .query_balance(env.contract.address.as_str(), config.stable_denom.as_str())?
+        .amount;
+    let stable = AssetInfo::NativeToken {
+        denom: config.stable_denom.clone(),
+    };
+    let stream_budgets: Uint128 = read_streams(deps.storage)?
+        .iter()
+        .filter(|stream| stream.asset == stable)
+        .map(|stream| stream.budget - stream.claimed)
+        .sum();
+
+    Ok(balance.saturating_sub(
+        state.total_claimable
+            + state.total_unbonding
+            + state.total_referral_pending
+            + state.total_unpaid_interest
+            + state.total_redirect_claimable
+            + state.total_prizes_claimable
+            + state.withdrawn_rewards
+            + stream_budgets,
+    ))
+}
+
+/// Pays `recipient` what the contract's liquidity and the strategies
+/// release right away when nobody is queued ahead, and queues the
+/// shortfall in FIFO order.
+/// Returns the payout messages and the amount queued.
+pub fn pay_or_enqueue(
+    mut deps: DepsMut,
+    env: &Env,
+    config: &Config,
+    recipient: &Addr,
+    amount: Uint128,
+    msg: Option<Binary>,
+) -> Result<(Vec<CosmosMsg>, Uint128), ContractError> {
+    let state: State = read_state(deps.storage)?;
+    let mut msgs: Vec<CosmosMsg> = vec![];
+    let mut paid = Uint128::zero();
+    if state.total_queued.is_zero() {
+        let mut available = query_liquidity(deps.as_ref(), env, config, &state)?;
+        if available < amount {
+            // only what the strategies actually release can be paid
+            let (withdraw_msgs, released) =
+                strategy_withdraw_msgs(deps.branch(), env, amount - available)?;
+            msgs = withdraw_msgs;
+            available += released;
+        }
+        paid = min(amount, available);
+    }
+
+    let queued = amount - paid;
+    if !queued.is_zero() {
+        // a queued withdrawal can not execute the callback atomically
+        if msg.is_some() {
+            return Err(ContractError::InsufficientLiquidity(
+                config.stable_denom.clone(),
+            ));
+        }
+
+        let mut state: State = read_state(deps.storage)?;
+        enqueue_withdrawal(
+            deps.storage,
+            &mut state,
+            deps.api.addr_canonicalize(recipient.as_str())?,
+            queued,
+            env.block.time.seconds(),
+        )?;
+        store_state(deps.storage, &state)?;
+    }
+
+    if !paid.is_zero() {
+        msgs.push(payout_msg(
+            recipient,
+            vec![Coin {
+                denom: config.stable_denom.clone(),
+                amount: paid,
+            }],
+            msg,
+        ));
+    }
+    Ok((msgs, queued))
+}
+
+fn enqueue_withdrawal(
+    storage: &mut dyn Storage,
+    state: &mut State,
+    owner: CanonicalAddr,
+    amount: Uint128,
+    time: u64,
+) -> StdResult<u64> {
+    let id = state.queue_tail;
+    store_queued_withdrawal(
+        storage,
+        id,
+        &QueuedWithdrawal {
+            owner: owner.clone(),
+            amount,
+            enqueued_before: state.total_enqueued,
+            requested_at: time,
+        },
+    )?;
+
+    let mut ids = read_queued_ids(storage, &owner);
+    ids.push(id);
+    store_queued_ids(storage, &owner, &ids)?;
+
+    state.queue_tail += 1;
+    state.total_queued += amount;
+    state.total_enqueued += amount;
+    Ok(id)
+}
+
+/// Fills queued withdrawals from the head of the queue with `liquidity`,
+/// moving the filled amounts into their owners' claimable balances
+pub fn process_queue(
+    storage: &mut dyn Storage,
+    state: &mut State,
+    liquidity: Uint128,
+) -> StdResult<Uint128> {
+    let mut available = liquidity;
+    let mut fills: u32 = 0;
+    while state.queue_head < state.queue_tail && !available.is_zero() && fills < MAX_FILLS {
+        let id = state.queue_head;
+        let mut withdrawal = read_queued_withdrawal(storage, id)?;
+        let fill = std::cmp::min(withdrawal.amount, available);
+
+        withdrawal.amount -= fill;
+        available -= fill;
+        state.total_queued -= fill;
+        state.total_claimable += fill;
+        state.total_filled += fill;
+
+        let claimable = read_claimable(storage, &withdrawal.owner);
+        store_claimable(storage, &withdrawal.owner, &(claimable + fill))?;
+
+        if withdrawal.amount.is_zero() {
+            remove_queued_withdrawal(storage, id);
+            let ids: Vec<u64> = read_queued_ids(storage, &withdrawal.owner)
+                .into_iter()
+                .filter(|queued_id| *queued_id != id)
+                .collect();
+            store_queued_ids(storage, &withdrawal.owner, &ids)?;
+            state.queue_head += 1;
+        } else {
+            store_queued_withdrawal(storage, id, &withdrawal)?;
+        }
+        fills += 1;
+    }
+
+    Ok(liquidity - available)
+}
+
+/// Fills the withdrawal queue with all currently available liquidity and
+/// withdraws the rest from the strategies, as `pay_or_enqueue` does.
+/// Returns the withdraw messages and the liquidity used.
+pub fn fill_queue(
+    mut deps: DepsMut,
+    env: &Env,
+    config: &Config,
+) -> Result<(Vec<CosmosMsg>, Uint128), ContractError> {
+    let mut state: State = read_state(deps.storage)?;
+    if state.total_queued.is_zero() {
+        return Ok((vec![], Uint128::zero()));
+    }
+
+    let liquidity = query_liquidity(deps.as_ref(), env, config, &state)?;
+    let filled = process_queue(deps.storage, &mut state, liquidity)?;
+    store_state(deps.storage, &state)?;
+    if state.total_queued.is_zero() {
+        return Ok((vec![], filled));
+    }
+
+    // the released funds arrive before any message appended after them
+    let (msgs, released) = strategy_withdraw_msgs(deps.branch(), env, state.total_queued)?;
+    process_queue(deps.storage, &mut state, released)?;
+    store_state(deps.storage, &state)?;
+    Ok((msgs, filled))
+}
+
+pub fn fund_reserve(
+    mut deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    let config: Config = read_config(deps.storage)?;
+    let amount: Uint128 = info
+        .funds
+        .iter()
+        .find(|c| c.denom == config.stable_denom)
+        .map(|c| c.amount)
+        .unwrap_or_else(Uint128::zero);
+
+    if amount.is_zero() {
+        return Err(ContractError::ZeroDeposit(config.stable_denom));
+    }
+
+    // the funds paying queued withdrawals are not kept in the reserve
+    let (msgs, filled) = fill_queue(deps.branch(), &env, &config)?;
+    let mut state: State = read_state(deps.storage)?;
+    state.reserve += amount.saturating_sub(filled);
+    store_state(deps.storage, &state)?;
+
+    Ok(Response::new().add_messages(msgs).add_attributes(vec![
+        attr("action", "fund_reserve"),
+        attr("amount", amount),
+        attr("queue_filled", filled),
+    ]))
+}
+
+pub fn claim_queued(
+    mut deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    let config: Config = read_config(deps.storage)?;
+    let (msgs, _) = fill_queue(deps.branch(), &env, &config)?;
+
+    let ident_raw = deps.api.addr_canonicalize(info.sender.as_str())?;
+    let claimable = read_claimable(deps.storage, &ident_raw);
+    if claimable.is_zero() {
+        return Err(ContractError::NothingToClaim {});
+    }
+
+    let mut state: State = read_state(deps.storage)?;
+    state.total_claimable -= claimable;
+    store_state(deps.storage, &state)?;
+    store_claimable(deps.storage, &ident_raw, &Uint128::zero())?;
+
+    Ok(Response::new()
+        .add_messages(msgs)
+        .add_message(CosmosMsg::Bank(BankMsg::Send {
+            to_address: info.sender.to_string(),
+            amount: vec![Coin {
+                denom: config.stable_denom,
+                amount: claimable,
+            }],
+        }))
+        .add_attributes(vec![
+            attr("action", "claim_queued"),
+            attr("claimer", info.sender),
+            attr("amount", claimable),
+        ]))
+}
+
+pub fn query_withdrawal_queue(deps: Deps, address: String) -> StdResult<WithdrawalQueueResponse> {
+    let state: State = read_state(deps.storage)?;
+    let ident_raw = deps.api.addr_canonicalize(&address)?;
+
+    let queued = read_queued_ids(deps.storage, &ident_raw)
+        .into_iter()
+        .map(|id| {
+            let withdrawal = read_queued_withdrawal(deps.storage, id)?;
+            Ok(QueuedWithdrawalResponse {
+                id,
+                amount: withdrawal.amount,
+                amount_ahead: withdrawal
+                    .enqueued_before
+                    .saturating_sub(state.total_filled),
+                requested_at: withdrawal.requested_at,
+            })
+        })
+        .collect::<StdResult<Vec<QueuedWithdrawalResponse>>>()?;
+
+    Ok(WithdrawalQueueResponse {
+        claimable: read_claimable(deps.storage, &ident_raw),
+        queued,
+    })
+}
@@ -0,0 +1,183 @@
+use cosmwasm_std::{
+    attr, CanonicalAddr, Decimal, Deps, DepsMut, Env, MessageInfo, Response, StdResult, Storage,
+    Uint128,
+};
+
+use crate::deposit::settle_accounts;
+use crate::error::ContractError;
+use crate::helpers::receipt_to_stable;
+use crate::queue::pay_or_enqueue;
+use crate::state::{
+    read_config, read_deposit_info, read_incoming_redirect, read_incoming_redirects, read_redirect,
+    read_state, store_deposit_info, store_incoming_redirect, store_redirect, store_state, Config,
+    DepositInfo, IncomingRedirectResponse, IncomingRedirectsResponse, InterestRedirectResponse,
+    State,
+};
+
+/// Credits `redirect_share` of `interest` accrued by `deposit` to its
+/// beneficiary as claimable stable coins. Returns the redirected interest.
+pub fn redirect_interest(
+    storage: &mut dyn Storage,
+    ident: &CanonicalAddr,
+    deposit: &mut DepositInfo,
+    interest: Uint128,
+) -> StdResult<Uint128> {
+    let beneficiary = match &deposit.redirect_to {
+        Some(beneficiary) => beneficiary.clone(),
+        None => return Ok(Uint128::zero()),
+    };
+    let redirected = interest * deposit.redirect_share;
+    if redirected.is_zero() {
+        return Ok(redirected);
+    }
+
+    let mut state: State = read_state(storage)?;
+    let amount = receipt_to_stable(redirected, state.exchange_rate);
+    state.total_redirect_claimable += amount;
+    store_state(storage, &state)?;
+    deposit.total_redirected += amount;
+
+    let mut redirect = read_redirect(storage, &beneficiary);
+    redirect.total_received += amount;
+    redirect.claimable += amount;
+    store_redirect(storage, &beneficiary, &redirect)?;
+
+    let received = read_incoming_redirect(storage, &beneficiary, ident);
+    store_incoming_redirect(storage, &beneficiary, ident, &(received + amount))?;
+    Ok(redirected)
+}
+
+/// Redirects interest accrued from now on, settling the sender first
+pub fn set_interest_redirect(
+    mut deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    beneficiary: Option<String>,
+    share: Option<Decimal>,
+) -> Result<Response, ContractError> {
+    let share = share.unwrap_or_else(Decimal::one);
+    if share.is_zero() || share > Decimal::one() {
+        return Err(ContractError::InvalidRedirect {});
+    }
+    let beneficiary = match beneficiary {
+        Some(beneficiary) => {
+            let beneficiary = deps.api.addr_validate(&beneficiary)?;
+            if beneficiary == info.sender {
+                return Err(ContractError::InvalidRedirect {});
+            }
+            Some(beneficiary)
+        }
+        None => None,
+    };
+
+    let sender_raw = deps.api.addr_canonicalize(info.sender.as_str())?;
+    let response = settle_accounts(
+        deps.branch(),
+        env,
+        vec![(sender_raw.clone(), info.sender)],
+        "set_interest_redirect",
+    )?;
+
+    let mut deposit = read_deposit_info(deps.storage, &sender_raw)?;
+    match &beneficiary {
+        Some(beneficiary) => {
+            // list the donor with the beneficiary before any interest arrives
+            let beneficiary_raw = deps.api.addr_canonicalize(beneficiary.as_str())?;
+            let received = read_incoming_redirect(deps.storage, &beneficiary_raw, &sender_raw);
+            store_incoming_redirect(deps.storage, &beneficiary_raw, &sender_raw, &received)?;
+            deposit.redirect_to = Some(beneficiary_raw);
+            deposit.redirect_share = share;
+        }
+        None => {
+            deposit.redirect_to = None;
+            deposit.redirect_share = Decimal::zero();
+        }
+    }
+    store_deposit_info(deps.storage, &sender_raw, &deposit)?;
+
+    Ok(response.add_attributes(vec![
+        attr(
+            "beneficiary",
+            beneficiary.map(|b| b.to_string()).unwrap_or_default(),
+        ),
+        attr("share", deposit.redirect_share.to_string()),
+    ]))
+}
+
+pub fn claim_redirected_interest(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    let config: Config = read_config(deps.storage)?;
+    let beneficiary_raw = deps.api.addr_canonicalize(info.sender.as_str())?;
+    let mut redirect = read_redirect(deps.storage, &beneficiary_raw);
+    let claim_amount = redirect.claimable;
+    if claim_amount.is_zero() {
+        return Err(ContractError::NothingToClaim {});
+    }
+
+    redirect.claimable = Uint128::zero();
+    store_redirect(deps.storage, &beneficiary_raw, &redirect)?;
+    let mut state: State = read_state(deps.storage)?;
+    state.total_redirect_claimable -= claim_amount;
+    store_state(deps.storage, &state)?;
+
+    // redirected interest is owed now and never waits for an unbonding
+    let (payout, queued) = pay_or_enqueue(deps, &env, &config, &info.sender, claim_amount, None)?;
+    let queued = !queued.is_zero();
+
+    Ok(Response::new().add_messages(payout).add_attributes(vec![
+        attr("action", "claim_redirected_interest"),
+        attr("beneficiary", info.sender),
+        attr("claim_amount", claim_amount),
+        attr("queued", queued.to_string()),
+    ]))
+}
+
+pub fn query_interest_redirect(deps: Deps, address: String) -> StdResult<InterestRedirectResponse> {
+    let address_raw = deps.api.addr_canonicalize(&address)?;
+    let deposit = read_deposit_info(deps.storage, &address_raw)?;
+    let redirect = read_redirect(deps.storage, &address_raw);
+
+    Ok(InterestRedirectResponse {
+        beneficiary: match deposit.redirect_to {
+            Some(beneficiary) => Some(deps.api.addr_humanize(&beneficiary)?.to_string()),
+            None => None,
+        },
+        share: deposit.redirect_share,
+        total_redirected: deposit.total_redirected,
+        total_received: redirect.total_received,
+        claimable: redirect.claimable,
+    })
+}
+
+pub fn query_incoming_redirects(
+    deps: Deps,
+    beneficiary: String,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<IncomingRedirectsResponse> {
+    let beneficiary_raw = deps.api.addr_canonicalize(&beneficiary)?;
+    let start_after = match start_after {
+        Some(start_after) => Some(deps.api.addr_canonicalize(&start_after)?),
+        None => None,
+    };
+
+    let redirects = read_incoming_redirects(deps.storage, &beneficiary_raw, start_after, limit)?
+        .into_iter()
+        .map(|(donor_raw, amount)| {
+            let active = read_deposit_info(deps.storage, &donor_raw)?
+                .redirect_to
+                .as_ref()
+                == Some(&beneficiary_raw);
+            Ok(IncomingRedirectResponse {
+                donor: deps.api.addr_humanize(&donor_raw)?.to_string(),
+                amount,
+                active,
+            })
+        })
+        .collect::<StdResult<Vec<IncomingRedirectResponse>>>()?;
+
+    Ok(IncomingRedirectsResponse { redirects })
+}
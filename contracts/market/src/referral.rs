@@ -0,0 +1,116 @@
+use std::cmp::min;
+
+use cosmwasm_std::{
+    attr, Addr, Deps, DepsMut, Env, MessageInfo, Response, StdResult, Storage, Uint128,
+};
+
+use crate::error::ContractError;
+use crate::helpers::receipt_to_stable;
+use crate::queue::pay_or_enqueue;
+use crate::state::{
+    read_config, read_referral, read_state, store_referral, store_state, Config, DepositInfo,
+    ReferralResponse, State,
+};
+
+/// Records `referrer` on a deposit without one
+pub fn record_referrer(
+    deps: DepsMut,
+    ident: &Addr,
+    deposit: &mut DepositInfo,
+    referrer: String,
+) -> Result<(), ContractError> {
+    if deposit.referrer.is_some() {
+        return Ok(());
+    }
+
+    let referrer = deps.api.addr_validate(&referrer)?;
+    if referrer == *ident {
+        return Err(ContractError::SelfReferral {});
+    }
+
+    let referrer_raw = deps.api.addr_canonicalize(referrer.as_str())?;
+    let mut referral = read_referral(deps.storage, &referrer_raw);
+    referral.referrals += 1;
+    store_referral(deps.storage, &referrer_raw, &referral)?;
+
+    deposit.referrer = Some(referrer_raw);
+    Ok(())
+}
+
+/// Sets aside `referral_share` of `interest` accrued by `deposit` for its
+/// referrer, as far as the reserve covers it
+pub fn accrue_referral_reward(
+    storage: &mut dyn Storage,
+    config: &Config,
+    deposit: &DepositInfo,
+    interest: Uint128,
+) -> StdResult<()> {
+    let referrer = match &deposit.referrer {
+        Some(referrer) => referrer,
+        None => return Ok(()),
+    };
+    if interest.is_zero() {
+        return Ok(());
+    }
+
+    let mut referral = read_referral(storage, referrer);
+    referral.referred_interest += interest;
+
+    let mut state: State = read_state(storage)?;
+    let reward = min(
+        receipt_to_stable(interest * config.referral_share, state.exchange_rate),
+        state.reserve,
+    );
+    if !reward.is_zero() {
+        state.reserve -= reward;
+        state.total_referral_pending += reward;
+        store_state(storage, &state)?;
+
+        referral.pending += reward;
+        referral.total_earned += reward;
+    }
+
+    store_referral(storage, referrer, &referral)
+}
+
+pub fn claim_referral_rewards(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    let config: Config = read_config(deps.storage)?;
+    let referrer_raw = deps.api.addr_canonicalize(info.sender.as_str())?;
+    let mut referral = read_referral(deps.storage, &referrer_raw);
+    let claim_amount = referral.pending;
+    if claim_amount.is_zero() {
+        return Err(ContractError::NothingToClaim {});
+    }
+
+    referral.pending = Uint128::zero();
+    store_referral(deps.storage, &referrer_raw, &referral)?;
+    let mut state: State = read_state(deps.storage)?;
+    state.total_referral_pending -= claim_amount;
+    store_state(deps.storage, &state)?;
+
+    // rewards come from the reserve and never wait for an unbonding
+    let (payout, queued) = pay_or_enqueue(deps, &env, &config, &info.sender, claim_amount, None)?;
+    let queued = !queued.is_zero();
+
+    Ok(Response::new().add_messages(payout).add_attributes(vec![
+        attr("action", "claim_referral_rewards"),
+        attr("referrer", info.sender),
+        attr("claim_amount", claim_amount),
+        attr("queued", queued.to_string()),
+    ]))
+}
+
+pub fn query_referral(deps: Deps, referrer: String) -> StdResult<ReferralResponse> {
+    let referral = read_referral(deps.storage, &deps.api.addr_canonicalize(&referrer)?);
+    Ok(ReferralResponse {
+        referrer,
+        referrals: referral.referrals,
+        referred_interest: referral.referred_interest,
+        total_earned: referral.total_earned,
+        pending: referral.pending,
+    })
+}
@@ -0,0 +1,170 @@
+use cosmwasm_bignumber::Decimal256;
+use cosmwasm_std::{
+    attr, to_binary, CanonicalAddr, Decimal, Deps, DepsMut, Env, MessageInfo, Response, StdResult,
+    Storage, Uint128, WasmMsg, WasmQuery,
+};
+use cw20::Cw20ExecuteMsg;
+use moneymarket::distribution_model::{AncEmissionRateResponse, QueryMsg as DistributionQueryMsg};
+
+use crate::contract::DURATION;
+
+use crate::error::ContractError;
+use crate::state::{
+    read_config, read_deposit_info, read_state, store_config, store_deposit_info, store_state,
+    Config, DepositInfo, PendingRewardsResponse, State,
+};
+use crate::streams::accrue_stream_rewards;
+
+/// Raises the global reward index by the rewards emitted since the last
+/// update, shared pro-rata over `State.total_working_balance`
+pub fn update_reward_index(state: &mut State, config: &Config, time: u64) {
+    if time <= state.last_reward_updated {
+        return;
+    }
+
+    let elapsed = Uint128::from(time - state.last_reward_updated);
+    let emitted = elapsed * config.emission_rate;
+    if !state.total_working_balance.is_zero() && !emitted.is_zero() {
+        state.global_reward_index =
+            state.global_reward_index + Decimal::from_ratio(emitted, state.total_working_balance);
+    }
+    state.last_reward_updated = time;
+}
+
+/// Moves rewards earned by `deposit` since its last index into its pending
+/// rewards, including every reward stream. Must run before the deposit's
+/// balance changes.
+pub fn accrue_rewards(
+    storage: &mut dyn Storage,
+    config: &Config,
+    ident: &CanonicalAddr,
+    deposit: &mut DepositInfo,
+    time: u64,
+) -> StdResult<()> {
+    let mut state: State = read_state(storage)?;
+    update_reward_index(&mut state, config, time);
+    store_state(storage, &state)?;
+
+    deposit.pending_rewards += earned_rewards(deposit, state.global_reward_index);
+    deposit.reward_index = state.global_reward_index;
+    accrue_stream_rewards(storage, ident, deposit.last_balance, time)
+}
+
+/// Asks the distribution model for the next emission rate given the deposit
+/// rate since the last epoch, the configured interest plus any exchange rate
+/// growth, both per `DURATION`
+pub fn adjust_emission_rate(deps: DepsMut, env: Env) -> StdResult<Response> {
+    let mut config: Config = read_config(deps.storage)?;
+    let distribution_model = match &config.distribution_model {
+        Some(distribution_model) => deps.api.addr_humanize(distribution_model)?,
+        None => return Ok(Response::default()),
+    };
+
+    let time = env.block.time.seconds();
+    let mut state: State = read_state(deps.storage)?;
+    let elapsed = time.saturating_sub(state.last_epoch_time);
+    let mut deposit_rate = Decimal256::from(config.interest_rate);
+    if elapsed > 0 && state.exchange_rate > state.last_epoch_exchange_rate {
+        let growth = Decimal256::from(state.exchange_rate - state.last_epoch_exchange_rate)
+            / Decimal256::from(state.last_epoch_exchange_rate);
+        deposit_rate += growth * Decimal256::from_ratio(DURATION, elapsed);
+    }
+
+    let response: AncEmissionRateResponse = deps.querier.query(
+        &WasmQuery::Smart {
+            contract_addr: distribution_model.to_string(),
+            msg: to_binary(&DistributionQueryMsg::AncEmissionRate {
+                deposit_rate,
+                target_deposit_rate: config.target_deposit_rate.into(),
+                threshold_deposit_rate: config.threshold_deposit_rate.into(),
+                current_emission_rate: config.emission_rate.into(),
+            })?,
+        }
+        .into(),
+    )?;
+
+    // rewards emitted so far are indexed at the previous rate
+    update_reward_index(&mut state, &config, time);
+    store_state(deps.storage, &state)?;
+    config.emission_rate = response.emission_rate.into();
+    store_config(deps.storage, &config)?;
+
+    Ok(Response::new().add_attributes(vec![
+        attr("deposit_rate", deposit_rate.to_string()),
+        attr("emission_rate", config.emission_rate.to_string()),
+    ]))
+}
+
+fn earned_rewards(deposit: &DepositInfo, global_reward_index: Decimal) -> Uint128 {
+    if global_reward_index <= deposit.reward_index {
+        return Uint128::zero();
+    }
+    deposit.working_balance * (global_reward_index - deposit.reward_index)
+}
+
+pub fn claim_rewards(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    to: Option<String>,
+) -> Result<Response, ContractError> {
+    let config: Config = read_config(deps.storage)?;
+    let reward_token = match &config.reward_token {
+        Some(reward_token) => deps.api.addr_humanize(reward_token)?,
+        None => return Err(ContractError::RewardsDisabled {}),
+    };
+    let recipient = match to {
+        Some(to) => deps.api.addr_validate(&to)?,
+        None => info.sender.clone(),
+    };
+
+    let sender_raw = deps.api.addr_canonicalize(info.sender.as_str())?;
+    let mut deposit = read_deposit_info(deps.storage, &sender_raw)?;
+    accrue_rewards(
+        deps.storage,
+        &config,
+        &sender_raw,
+        &mut deposit,
+        env.block.time.seconds(),
+    )?;
+    let claim_amount = deposit.pending_rewards;
+    if claim_amount.is_zero() {
+        return Err(ContractError::NothingToClaim {});
+    }
+    deposit.pending_rewards = Uint128::zero();
+    store_deposit_info(deps.storage, &sender_raw, &deposit)?;
+
+    Ok(Response::new()
+        .add_message(WasmMsg::Execute {
+            contract_addr: reward_token.to_string(),
+            funds: vec![],
+            msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                recipient: recipient.to_string(),
+                amount: claim_amount,
+            })?,
+        })
+        .add_attributes(vec![
+            attr("action", "claim_rewards"),
+            attr("claimer", info.sender),
+            attr("recipient", recipient),
+            attr("claim_amount", claim_amount),
+        ]))
+}
+
+pub fn query_pending_rewards(
+    deps: Deps,
+    env: Env,
+    address: String,
+) -> StdResult<PendingRewardsResponse> {
+    let config: Config = read_config(deps.storage)?;
+    let mut state: State = read_state(deps.storage)?;
+    update_reward_index(&mut state, &config, env.block.time.seconds());
+
+    let deposit = read_deposit_info(deps.storage, &deps.api.addr_canonicalize(&address)?)?;
+    Ok(PendingRewardsResponse {
+        pending_rewards: deposit.pending_rewards
+            + earned_rewards(&deposit, state.global_reward_index),
+        reward_index: deposit.reward_index,
+        global_reward_index: state.global_reward_index,
+    })
+}
@@ -0,0 +1,87 @@
+use std::cmp::min;
+
+use cosmwasm_std::{attr, Decimal, DepsMut, Env, Event, Response, Uint128};
+
+use crate::error::ContractError;
+use crate::staking::delegate_msgs;
+use crate::state::{read_config, read_state, read_validators, store_state, store_validator, State};
+use crate::tranche::absorb_loss;
+
+/// Takes `loss` from the junior tranche first, then the reserve, and cuts
+/// the exchange rate for the rest, down to zero once insolvent. Returns the
+/// amounts absorbed, covered by the reserve and socialized.
+pub fn socialize_loss(state: &mut State, loss: Uint128) -> (Uint128, Uint128, Uint128) {
+    let unabsorbed = absorb_loss(state, loss);
+    let covered = min(unabsorbed, state.reserve);
+    state.reserve -= covered;
+    let socialized = unabsorbed - covered;
+    if !socialized.is_zero() && !state.tvl.is_zero() {
+        let haircut = Decimal::from_ratio(socialized, state.tvl);
+        state.exchange_rate = if haircut < state.exchange_rate {
+            state.exchange_rate - haircut
+        } else {
+            Decimal::zero()
+        };
+    }
+    (loss - unabsorbed, covered, socialized)
+}
+
+/// Compares tracked delegations with the actual delegations and socializes
+/// any slashed stake
+pub fn reconcile(deps: DepsMut, env: Env) -> Result<Response, ContractError> {
+    let config = read_config(deps.storage)?;
+    if !config.native_staking {
+        return Err(ContractError::NativeStakingDisabled {});
+    }
+
+    let delegations = deps
+        .querier
+        .query_all_delegations(env.contract.address.as_str())?;
+
+    let mut shortfall = Uint128::zero();
+    for mut validator in read_validators(deps.storage)?.into_iter() {
+        let actual = delegations
+            .iter()
+            .find(|d| d.validator == validator.address && d.amount.denom == config.stable_denom)
+            .map(|d| d.amount.amount)
+            .unwrap_or_else(Uint128::zero);
+        if actual >= validator.delegated {
+            continue;
+        }
+
+        shortfall += validator.delegated - actual;
+        validator.delegated = actual;
+        store_validator(deps.storage, &validator)?;
+    }
+
+    if shortfall.is_zero() {
+        return Ok(Response::new().add_attributes(vec![
+            attr("action", "reconcile"),
+            attr("shortfall", shortfall),
+        ]));
+    }
+
+    let mut state = read_state(deps.storage)?;
+    state.total_delegated = state.total_delegated.saturating_sub(shortfall);
+    let (absorbed, covered, socialized) = socialize_loss(&mut state, shortfall);
+    state.total_slashed += shortfall;
+    store_state(deps.storage, &state)?;
+
+    // the reserve restores the slashed stake
+    let msgs = delegate_msgs(deps, &env, &config, covered)?;
+
+    Ok(Response::new()
+        .add_messages(msgs)
+        .add_event(
+            Event::new("slash")
+                .add_attribute("shortfall", shortfall)
+                .add_attribute("absorbed", absorbed)
+                .add_attribute("covered", covered)
+                .add_attribute("socialized", socialized)
+                .add_attribute("exchange_rate", state.exchange_rate.to_string()),
+        )
+        .add_attributes(vec![
+            attr("action", "reconcile"),
+            attr("shortfall", shortfall),
+        ]))
+}
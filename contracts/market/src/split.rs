@@ -0,0 +1,613 @@
+use std::cmp::min;
+
+use cosmwasm_std::{
+    attr, to_binary, Addr, CanonicalAddr, CosmosMsg, Decimal, Deps, DepsMut, Env, MessageInfo,
+    Response, StdError, StdResult, Storage, SubMsg, Uint128, WasmMsg,
+};
+use cw20::{Cw20ExecuteMsg, MinterResponse};
+use terraswap::token::InstantiateMsg as TokenInstantiateMsg;
+
+use crate::allowlist::assert_allowlisted;
+use crate::boost::update_working_balance;
+use crate::contract::DURATION;
+use crate::deposit::accrue_interest;
+use crate::error::ContractError;
+use crate::helpers::{calculate_accrued_interest, receipt_to_stable, stable_to_receipt};
+use crate::state::{
+    read_config, read_deposit_info, read_maturities, read_maturity, read_pending_maturity,
+    read_recombination, read_state, remove_recombination, store_deposit_info, store_maturity,
+    store_pending_maturity, store_recombination, store_state, store_tvl_indice, Config,
+    DepositInfo, MaturitiesResponse, Maturity, MaturityResponse, Recombination, State, Tvl,
+};
+
+/// Compounds the escrowed receipt tokens of `maturity` up to `time`, or the
+/// maturity if earlier. Returns the interest to mint to the market.
+fn accrue_maturity(
+    storage: &mut dyn Storage,
+    config: &Config,
+    maturity: &mut Maturity,
+    time: u64,
+) -> StdResult<Uint128> {
+    let end = min(time, maturity.maturity);
+    if end <= maturity.last_interaction {
+        return Ok(Uint128::zero());
+    }
+    let days = (end - maturity.last_interaction) / DURATION;
+    maturity.last_interaction += days * DURATION;
+
+    let escrow = DepositInfo {
+        last_balance: maturity.balance,
+        ..DepositInfo::default()
+    };
+    let accrued_interest = calculate_accrued_interest(&escrow, config.interest_rate, days)?;
+    if !accrued_interest.is_zero() {
+        maturity.balance += accrued_interest;
+        let mut state: State = read_state(storage)?;
+        state.accrued_interest_payments += accrued_interest;
+        store_state(storage, &state)?;
+    }
+    Ok(accrued_interest)
+}
+
+/// Loads `maturity` accrued up to `time`, fixing its redemption rates at the
+/// first interaction after it. Returns it with the interest to mint.
+fn load_maturity(
+    storage: &mut dyn Storage,
+    config: &Config,
+    maturity: u64,
+    time: u64,
+) -> Result<(Maturity, Uint128), ContractError> {
+    let mut maturity =
+        read_maturity(storage, maturity)?.ok_or(ContractError::MaturityNotFound(maturity))?;
+    let accrued_interest = accrue_maturity(storage, config, &mut maturity, time)?;
+
+    if !maturity.matured && time >= maturity.maturity {
+        maturity.matured = true;
+        if !maturity.principal.is_zero() {
+            let exchange_rate = read_state(storage)?.exchange_rate;
+            // an insolvent market leaves nothing for the yield tokens
+            let principal_receipt = if exchange_rate.is_zero() {
+                maturity.balance
+            } else {
+                min(
+                    stable_to_receipt(maturity.principal, exchange_rate),
+                    maturity.balance,
+                )
+            };
+            maturity.principal_rate = Decimal::from_ratio(principal_receipt, maturity.principal);
+            maturity.yield_rate =
+                Decimal::from_ratio(maturity.balance - principal_receipt, maturity.principal);
+        }
+    }
+    Ok((maturity, accrued_interest))
+}
+
+/// Mints `interest` receipt tokens to `recipient`
+fn mint_interest(
+    storage: &mut dyn Storage,
+    aterra_contract: &Addr,
+    recipient: &Addr,
+    interest: Uint128,
+    time: u64,
+) -> Result<Vec<CosmosMsg>, ContractError> {
+    if interest.is_zero() {
+        return Ok(vec![]);
+    }
+
+    store_tvl_indice(
+        storage,
+        &mut Tvl {
+            epoch: time,
+            tvl: interest,
+        },
+        1,
+    )?;
+    Ok(vec![CosmosMsg::Wasm(WasmMsg::Execute {
+        contract_addr: aterra_contract.to_string(),
+        funds: vec![],
+        msg: to_binary(&Cw20ExecuteMsg::Mint {
+            recipient: recipient.to_string(),
+            amount: interest,
+        })?,
+    })])
+}
+
+/// Takes `amount` receipt tokens sent to the market out of the position of
+/// `ident`, minting its accrued interest
+fn debit_position(
+    deps: DepsMut,
+    config: &Config,
+    aterra_contract: &Addr,
+    ident: &Addr,
+    amount: Uint128,
+    time: u64,
+) -> Result<Vec<CosmosMsg>, ContractError> {
+    let ident_raw = deps.api.addr_canonicalize(ident.as_str())?;
+    let mut deposit = read_deposit_info(deps.storage, &ident_raw)?;
+    if deposit.initial_interaction == 0 {
+        return Err(ContractError::ExceedsBalance(0));
+    }
+
+    let accrued_interest = accrue_interest(deps.storage, config, &ident_raw, &mut deposit, time)?;
+    if amount > deposit.last_balance {
+        return Err(ContractError::ExceedsBalance(deposit.last_balance.u128()));
+    }
+    deposit.last_balance -= amount;
+    update_working_balance(deps.storage, config, &ident_raw, &mut deposit, time)?;
+    store_deposit_info(deps.storage, &ident_raw, &deposit)?;
+
+    mint_interest(deps.storage, aterra_contract, ident, accrued_interest, time)
+}
+
+/// Transfers `amount` escrowed receipt tokens into the position of `ident`,
+/// minting its accrued interest
+fn credit_position(
+    deps: DepsMut,
+    config: &Config,
+    aterra_contract: &Addr,
+    ident: &Addr,
+    amount: Uint128,
+    time: u64,
+) -> Result<Vec<CosmosMsg>, ContractError> {
+    if amount.is_zero() {
+        return Ok(vec![]);
+    }
+    assert_allowlisted(deps.as_ref(), config, ident)?;
+
+    let ident_raw = deps.api.addr_canonicalize(ident.as_str())?;
+    let mut deposit = read_deposit_info(deps.storage, &ident_raw)?;
+    if deposit.initial_interaction == 0 {
+        deposit.initial_interaction = time;
+    }
+    if deposit.last_interaction == 0 {
+        deposit.last_interaction = time;
+    }
+    let accrued_interest = accrue_interest(deps.storage, config, &ident_raw, &mut deposit, time)?;
+    deposit.last_balance += amount;
+    update_working_balance(deps.storage, config, &ident_raw, &mut deposit, time)?;
+    store_deposit_info(deps.storage, &ident_raw, &deposit)?;
+
+    let mut msgs = mint_interest(deps.storage, aterra_contract, ident, accrued_interest, time)?;
+    msgs.push(CosmosMsg::Wasm(WasmMsg::Execute {
+        contract_addr: aterra_contract.to_string(),
+        funds: vec![],
+        msg: to_binary(&Cw20ExecuteMsg::Transfer {
+            recipient: ident.to_string(),
+            amount,
+        })?,
+    }));
+    Ok(msgs)
+}
+
+/// Longest symbol terraswap tokens accept
+const MAX_SYMBOL_LEN: usize = 12;
+
+/// Day of `maturity` since the unix epoch in base 26 letters, as token
+/// symbols only allow letters
+fn maturity_code(maturity: u64) -> String {
+    let mut day = maturity / 86400;
+    let mut code = vec![];
+    loop {
+        code.push(b'A' + (day % 26) as u8);
+        day /= 26;
+        if day == 0 {
+            break;
+        }
+    }
+    code.reverse();
+    String::from_utf8(code).unwrap()
+}
+
+fn instantiate_token_msg(
+    env: &Env,
+    code_id: u64,
+    name: String,
+    symbol: String,
+    reply_id: u64,
+) -> StdResult<SubMsg> {
+    Ok(SubMsg::reply_on_success(
+        CosmosMsg::Wasm(WasmMsg::Instantiate {
+            admin: None,
+            code_id,
+            funds: vec![],
+            label: "".to_string(),
+            msg: to_binary(&TokenInstantiateMsg {
+                name,
+                symbol,
+                decimals: 6u8,
+                initial_balances: vec![],
+                mint: Some(MinterResponse {
+                    minter: env.contract.address.to_string(),
+                    cap: None,
+                }),
+            })?,
+        }),
+        reply_id,
+    ))
+}
+
+/// Lists `maturity` and instantiates its principal and yield tokens,
+/// registered by replies 2 and 3
+pub fn add_maturity(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    maturity: u64,
+    token_code_id: u64,
+) -> Result<Response, ContractError> {
+    let config: Config = read_config(deps.storage)?;
+    if deps.api.addr_canonicalize(info.sender.as_str())? != config.owner_addr {
+        return Err(ContractError::Unauthorized {});
+    }
+    if config.prize_mode {
+        return Err(ContractError::SplitDisabled {});
+    }
+
+    let time = env.block.time.seconds();
+    if maturity <= time || read_maturity(deps.storage, maturity)?.is_some() {
+        return Err(ContractError::InvalidMaturity {});
+    }
+    store_maturity(
+        deps.storage,
+        &Maturity {
+            maturity,
+            principal_token: CanonicalAddr::from(vec![]),
+            yield_token: CanonicalAddr::from(vec![]),
+            balance: Uint128::zero(),
+            principal: Uint128::zero(),
+            last_interaction: time,
+            principal_rate: Decimal::zero(),
+            yield_rate: Decimal::zero(),
+            matured: false,
+        },
+    )?;
+    store_pending_maturity(deps.storage, maturity)?;
+
+    let denom = config.stable_denom[1..].to_uppercase();
+    let code = maturity_code(maturity);
+    // the denom is cut short to keep `pxyz{denom}-{code}` within the limit
+    let symbol: String = config.stable_denom[1..(config.stable_denom.len() - 1)]
+        .to_uppercase()
+        .chars()
+        .take(MAX_SYMBOL_LEN - "pxyz-".len() - code.len())
+        .collect();
+    Ok(Response::new()
+        .add_submessages(vec![
+            instantiate_token_msg(
+                &env,
+                token_code_id,
+                format!("xyz {} principal {}", denom, maturity),
+                format!("pxyz{}-{}", symbol, code),
+                2,
+            )?,
+            instantiate_token_msg(
+                &env,
+                token_code_id,
+                format!("xyz {} yield {}", denom, maturity),
+                format!("yxyz{}-{}", symbol, code),
+                3,
+            )?,
+        ])
+        .add_attributes(vec![
+            attr("action", "add_maturity"),
+            attr("maturity", maturity.to_string()),
+        ]))
+}
+
+pub fn register_maturity_token(
+    deps: DepsMut,
+    token_addr: Addr,
+    principal: bool,
+) -> Result<Response, ContractError> {
+    let maturity = read_pending_maturity(deps.storage)?;
+    let mut maturity =
+        read_maturity(deps.storage, maturity)?.ok_or(ContractError::MaturityNotFound(maturity))?;
+
+    let token_raw = deps.api.addr_canonicalize(token_addr.as_str())?;
+    let key = if principal {
+        maturity.principal_token = token_raw;
+        "principal_token"
+    } else {
+        maturity.yield_token = token_raw;
+        "yield_token"
+    };
+    store_maturity(deps.storage, &maturity)?;
+
+    Ok(Response::new().add_attributes(vec![attr(key, token_addr)]))
+}
+
+/// Escrows `amount` receipt tokens sent by `sender` until `maturity`,
+/// minting one principal and one yield token per stable coin of their value
+pub fn split(
+    mut deps: DepsMut,
+    env: Env,
+    sender: Addr,
+    amount: Uint128,
+    maturity: u64,
+) -> Result<Response, ContractError> {
+    let config: Config = read_config(deps.storage)?;
+    let time = env.block.time.seconds();
+    let aterra_contract = deps.api.addr_humanize(&config.aterra_contract)?;
+    let (mut maturity, accrued_interest) = load_maturity(deps.storage, &config, maturity, time)?;
+    if maturity.matured {
+        return Err(ContractError::Matured(maturity.maturity));
+    }
+
+    let mut msgs = mint_interest(
+        deps.storage,
+        &aterra_contract,
+        &env.contract.address,
+        accrued_interest,
+        time,
+    )?;
+    msgs.extend(debit_position(
+        deps.branch(),
+        &config,
+        &aterra_contract,
+        &sender,
+        amount,
+        time,
+    )?);
+
+    let principal = receipt_to_stable(amount, read_state(deps.storage)?.exchange_rate);
+    maturity.balance += amount;
+    maturity.principal += principal;
+    store_maturity(deps.storage, &maturity)?;
+
+    for token in [&maturity.principal_token, &maturity.yield_token] {
+        msgs.push(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: deps.api.addr_humanize(token)?.to_string(),
+            funds: vec![],
+            msg: to_binary(&Cw20ExecuteMsg::Mint {
+                recipient: sender.to_string(),
+                amount: principal,
+            })?,
+        }));
+    }
+
+    Ok(Response::new().add_messages(msgs).add_attributes(vec![
+        attr("action", "split"),
+        attr("splitter", sender),
+        attr("maturity", maturity.maturity.to_string()),
+        attr("amount", amount),
+        attr("principal", principal),
+    ]))
+}
+
+/// Escrows `amount` principal or yield tokens sent by `sender` before
+/// maturity, burning them with as many escrowed tokens of the other kind for
+/// their share of the escrowed receipt tokens
+pub fn recombine(
+    mut deps: DepsMut,
+    env: Env,
+    token: Addr,
+    sender: Addr,
+    amount: Uint128,
+    maturity: u64,
+) -> Result<Response, ContractError> {
+    let config: Config = read_config(deps.storage)?;
+    let time = env.block.time.seconds();
+    let aterra_contract = deps.api.addr_humanize(&config.aterra_contract)?;
+    let (mut maturity, accrued_interest) = load_maturity(deps.storage, &config, maturity, time)?;
+
+    let token_raw = deps.api.addr_canonicalize(token.as_str())?;
+    let sender_raw = deps.api.addr_canonicalize(sender.as_str())?;
+    let mut recombination = read_recombination(deps.storage, maturity.maturity, &sender_raw)?;
+    if token_raw == maturity.principal_token {
+        recombination.principal_tokens += amount;
+    } else if token_raw == maturity.yield_token {
+        recombination.yield_tokens += amount;
+    } else {
+        return Err(ContractError::Unauthorized {});
+    }
+    if maturity.matured {
+        return Err(ContractError::Matured(maturity.maturity));
+    }
+
+    let amount = min(recombination.principal_tokens, recombination.yield_tokens);
+    recombination.principal_tokens -= amount;
+    recombination.yield_tokens -= amount;
+    if recombination == Recombination::default() {
+        remove_recombination(deps.storage, maturity.maturity, &sender_raw);
+    } else {
+        store_recombination(deps.storage, maturity.maturity, &sender_raw, &recombination)?;
+    }
+
+    let receipt_amount = if amount.is_zero() {
+        Uint128::zero()
+    } else {
+        maturity.balance.multiply_ratio(amount, maturity.principal)
+    };
+    maturity.balance -= receipt_amount;
+    maturity.principal -= amount;
+    store_maturity(deps.storage, &maturity)?;
+
+    let mut msgs = mint_interest(
+        deps.storage,
+        &aterra_contract,
+        &env.contract.address,
+        accrued_interest,
+        time,
+    )?;
+    if !amount.is_zero() {
+        for token in [&maturity.principal_token, &maturity.yield_token] {
+            msgs.push(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: deps.api.addr_humanize(token)?.to_string(),
+                funds: vec![],
+                msg: to_binary(&Cw20ExecuteMsg::Burn { amount })?,
+            }));
+        }
+    }
+    msgs.extend(credit_position(
+        deps.branch(),
+        &config,
+        &aterra_contract,
+        &sender,
+        receipt_amount,
+        time,
+    )?);
+
+    Ok(Response::new().add_messages(msgs).add_attributes(vec![
+        attr("action", "recombine"),
+        attr("owner", sender),
+        attr("maturity", maturity.maturity.to_string()),
+        attr("amount", amount),
+        attr("receipt_amount", receipt_amount),
+    ]))
+}
+
+/// Returns the principal and yield tokens of `maturity` escrowed by the
+/// sender to recombine
+pub fn cancel_recombine(
+    deps: DepsMut,
+    info: MessageInfo,
+    maturity: u64,
+) -> Result<Response, ContractError> {
+    let maturity =
+        read_maturity(deps.storage, maturity)?.ok_or(ContractError::MaturityNotFound(maturity))?;
+    let sender_raw = deps.api.addr_canonicalize(info.sender.as_str())?;
+    let recombination = read_recombination(deps.storage, maturity.maturity, &sender_raw)?;
+    if recombination == Recombination::default() {
+        return Err(ContractError::NothingToClaim {});
+    }
+    remove_recombination(deps.storage, maturity.maturity, &sender_raw);
+
+    let mut msgs: Vec<CosmosMsg> = vec![];
+    for (token, amount) in [
+        (&maturity.principal_token, recombination.principal_tokens),
+        (&maturity.yield_token, recombination.yield_tokens),
+    ] {
+        if amount.is_zero() {
+            continue;
+        }
+        msgs.push(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: deps.api.addr_humanize(token)?.to_string(),
+            funds: vec![],
+            msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                recipient: info.sender.to_string(),
+                amount,
+            })?,
+        }));
+    }
+
+    Ok(Response::new().add_messages(msgs).add_attributes(vec![
+        attr("action", "cancel_recombine"),
+        attr("owner", info.sender),
+        attr("maturity", maturity.maturity.to_string()),
+        attr("principal_tokens", recombination.principal_tokens),
+        attr("yield_tokens", recombination.yield_tokens),
+    ]))
+}
+
+/// Burns `amount` principal or yield tokens sent by `sender` after maturity
+/// for receipt tokens at the rate fixed then
+pub fn redeem_split(
+    mut deps: DepsMut,
+    env: Env,
+    token: Addr,
+    sender: Addr,
+    amount: Uint128,
+    maturity: u64,
+    principal: bool,
+) -> Result<Response, ContractError> {
+    let config: Config = read_config(deps.storage)?;
+    let time = env.block.time.seconds();
+    let aterra_contract = deps.api.addr_humanize(&config.aterra_contract)?;
+    let (mut maturity, accrued_interest) = load_maturity(deps.storage, &config, maturity, time)?;
+
+    let token_raw = deps.api.addr_canonicalize(token.as_str())?;
+    let (expected, rate) = if principal {
+        (&maturity.principal_token, maturity.principal_rate)
+    } else {
+        (&maturity.yield_token, maturity.yield_rate)
+    };
+    if token_raw != *expected {
+        return Err(ContractError::Unauthorized {});
+    }
+    if !maturity.matured {
+        return Err(ContractError::NotMatured(maturity.maturity));
+    }
+
+    let receipt_amount = min(amount * rate, maturity.balance);
+    maturity.balance -= receipt_amount;
+    if principal {
+        maturity.principal = maturity.principal.saturating_sub(amount);
+    }
+    store_maturity(deps.storage, &maturity)?;
+
+    let mut msgs: Vec<CosmosMsg> = vec![CosmosMsg::Wasm(WasmMsg::Execute {
+        contract_addr: token.to_string(),
+        funds: vec![],
+        msg: to_binary(&Cw20ExecuteMsg::Burn { amount })?,
+    })];
+    msgs.extend(mint_interest(
+        deps.storage,
+        &aterra_contract,
+        &env.contract.address,
+        accrued_interest,
+        time,
+    )?);
+    msgs.extend(credit_position(
+        deps.branch(),
+        &config,
+        &aterra_contract,
+        &sender,
+        receipt_amount,
+        time,
+    )?);
+
+    Ok(Response::new().add_messages(msgs).add_attributes(vec![
+        attr(
+            "action",
+            if principal {
+                "redeem_principal"
+            } else {
+                "redeem_yield"
+            },
+        ),
+        attr("redeemer", sender),
+        attr("maturity", maturity.maturity.to_string()),
+        attr("amount", amount),
+        attr("receipt_amount", receipt_amount),
+    ]))
+}
+
+fn maturity_response(deps: Deps, state: &State, maturity: Maturity) -> StdResult<MaturityResponse> {
+    Ok(MaturityResponse {
+        maturity: maturity.maturity,
+        principal_token: deps
+            .api
+            .addr_humanize(&maturity.principal_token)?
+            .to_string(),
+        yield_token: deps.api.addr_humanize(&maturity.yield_token)?.to_string(),
+        balance: maturity.balance,
+        principal: maturity.principal,
+        accrued_yield: receipt_to_stable(maturity.balance, state.exchange_rate)
+            .saturating_sub(maturity.principal),
+        matured: maturity.matured,
+        principal_rate: maturity.principal_rate,
+        yield_rate: maturity.yield_rate,
+    })
+}
+
+pub fn query_maturity(deps: Deps, maturity: u64) -> StdResult<MaturityResponse> {
+    let state: State = read_state(deps.storage)?;
+    match read_maturity(deps.storage, maturity)? {
+        Some(maturity) => maturity_response(deps, &state, maturity),
+        None => Err(StdError::not_found("maturity")),
+    }
+}
+
+pub fn query_maturities(
+    deps: Deps,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<MaturitiesResponse> {
+    let state: State = read_state(deps.storage)?;
+    let maturities = read_maturities(deps.storage, start_after, limit)?
+        .into_iter()
+        .map(|maturity| maturity_response(deps, &state, maturity))
+        .collect::<StdResult<Vec<MaturityResponse>>>()?;
+
+    Ok(MaturitiesResponse { maturities })
+}
@@ -0,0 +1,386 @@
+use std::cmp::{min, Reverse};
+
+use cosmwasm_std::{
+    attr, Coin, CosmosMsg, Deps, DepsMut, Env, MessageInfo, Response, StakingMsg, StdResult,
+    Uint128,
+};
+
+use crate::error::ContractError;
+use crate::state::{
+    read_config, read_state, read_validator, read_validators, remove_validator as remove,
+    store_state, store_validator, Config, State, ValidatorInfo, ValidatorResponse,
+    ValidatorsResponse,
+};
+
+pub fn register_validator(
+    deps: DepsMut,
+    info: MessageInfo,
+    validator: String,
+    weight: Option<u64>,
+) -> Result<Response, ContractError> {
+    let config: Config = read_config(deps.storage)?;
+    if deps.api.addr_canonicalize(info.sender.as_str())? != config.owner_addr {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    if !config.native_staking {
+        return Err(ContractError::NativeStakingDisabled {});
+    }
+
+    if deps.querier.query_validator(validator.as_str())?.is_none() {
+        return Err(ContractError::ValidatorNotFound(validator));
+    }
+
+    if read_validator(deps.storage, &validator)?.is_some() {
+        return Err(ContractError::ValidatorExists(validator));
+    }
+
+    let weight = weight.unwrap_or(1);
+    store_validator(
+        deps.storage,
+        &ValidatorInfo {
+            address: validator.clone(),
+            weight,
+            delegated: Uint128::zero(),
+            rewards_height: 0,
+        },
+    )?;
+
+    Ok(Response::new().add_attributes(vec![
+        attr("action", "register_validator"),
+        attr("validator", validator),
+        attr("weight", weight.to_string()),
+    ]))
+}
+
+pub fn remove_validator(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    validator: String,
+) -> Result<Response, ContractError> {
+    let config: Config = read_config(deps.storage)?;
+    if deps.api.addr_canonicalize(info.sender.as_str())? != config.owner_addr {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let mut removed = read_validator(deps.storage, &validator)?
+        .ok_or_else(|| ContractError::ValidatorNotFound(validator.clone()))?;
+    remove(deps.storage, &validator);
+
+    let mut msgs: Vec<CosmosMsg> = vec![];
+    if !removed.delegated.is_zero() {
+        let mut validators = read_validators(deps.storage)?;
+        if validators.iter().all(|v| v.weight == 0) {
+            return Err(ContractError::NoValidators {});
+        }
+
+        // fill the remaining validators' deficits first, any rounding
+        // leftover goes to the heaviest validator
+        let mut state: State = read_state(deps.storage)?;
+        book_withdrawn_rewards(deps.as_ref(), &env, &config, &mut state, &mut removed)?;
+        let targets = target_delegations(&validators, state.total_delegated);
+        let mut amounts = vec![Uint128::zero(); validators.len()];
+        let mut remaining = removed.delegated;
+        for (i, v) in validators.iter().enumerate() {
+            let amount = min(targets[i].saturating_sub(v.delegated), remaining);
+            amounts[i] = amount;
+            remaining -= amount;
+        }
+        if let Some((i, _)) = validators.iter().enumerate().max_by_key(|(_, v)| v.weight) {
+            amounts[i] += remaining;
+        }
+
+        for (v, amount) in validators.iter_mut().zip(amounts) {
+            if amount.is_zero() {
+                continue;
+            }
+
+            v.delegated += amount;
+            book_withdrawn_rewards(deps.as_ref(), &env, &config, &mut state, v)?;
+            store_validator(deps.storage, v)?;
+            msgs.push(CosmosMsg::Staking(StakingMsg::Redelegate {
+                src_validator: validator.clone(),
+                dst_validator: v.address.clone(),
+                amount: Coin {
+                    denom: config.stable_denom.clone(),
+                    amount,
+                },
+            }));
+        }
+        store_state(deps.storage, &state)?;
+    }
+
+    Ok(Response::new().add_messages(msgs).add_attributes(vec![
+        attr("action", "remove_validator"),
+        attr("validator", validator),
+        attr("redelegated", removed.delegated),
+    ]))
+}
+
+pub fn update_validator_weight(
+    deps: DepsMut,
+    info: MessageInfo,
+    validator: String,
+    weight: u64,
+) -> Result<Response, ContractError> {
+    let config: Config = read_config(deps.storage)?;
+    if deps.api.addr_canonicalize(info.sender.as_str())? != config.owner_addr {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let mut validator_info = read_validator(deps.storage, &validator)?
+        .ok_or_else(|| ContractError::ValidatorNotFound(validator.clone()))?;
+    validator_info.weight = weight;
+    store_validator(deps.storage, &validator_info)?;
+
+    Ok(Response::new().add_attributes(vec![
+        attr("action", "update_validator_weight"),
+        attr("validator", validator),
+        attr("weight", weight.to_string()),
+    ]))
+}
+
+/// Redelegates from validators above their target to validators below it
+pub fn rebalance(deps: DepsMut, env: Env) -> Result<Response, ContractError> {
+    let config: Config = read_config(deps.storage)?;
+    if !config.native_staking {
+        return Err(ContractError::NativeStakingDisabled {});
+    }
+
+    let mut state: State = read_state(deps.storage)?;
+    let mut validators = read_validators(deps.storage)?;
+    let targets = target_delegations(&validators, state.total_delegated);
+    let mut remaining = if config.max_rebalance.is_zero() {
+        state.total_delegated
+    } else {
+        config.max_rebalance
+    };
+
+    let mut msgs: Vec<CosmosMsg> = vec![];
+    let mut redelegated = Uint128::zero();
+    // every step clears a surplus, a deficit or the remaining cap
+    for _ in 0..(2 * validators.len()) {
+        let surplus = |i: usize, v: &[ValidatorInfo]| v[i].delegated.saturating_sub(targets[i]);
+        let deficit = |i: usize, v: &[ValidatorInfo]| targets[i].saturating_sub(v[i].delegated);
+
+        let src = match (0..validators.len()).max_by_key(|i| surplus(*i, &validators)) {
+            Some(src) => src,
+            None => break,
+        };
+        let dst = match (0..validators.len()).max_by_key(|i| deficit(*i, &validators)) {
+            Some(dst) => dst,
+            None => break,
+        };
+
+        let amount = min(
+            min(surplus(src, &validators), deficit(dst, &validators)),
+            remaining,
+        );
+        if amount.is_zero() {
+            break;
+        }
+
+        validators[src].delegated -= amount;
+        validators[dst].delegated += amount;
+        for i in [src, dst] {
+            book_withdrawn_rewards(deps.as_ref(), &env, &config, &mut state, &mut validators[i])?;
+        }
+        remaining -= amount;
+        redelegated += amount;
+        msgs.push(CosmosMsg::Staking(StakingMsg::Redelegate {
+            src_validator: validators[src].address.clone(),
+            dst_validator: validators[dst].address.clone(),
+            amount: Coin {
+                denom: config.stable_denom.clone(),
+                amount,
+            },
+        }));
+    }
+
+    for validator in validators.iter() {
+        store_validator(deps.storage, validator)?;
+    }
+    store_state(deps.storage, &state)?;
+
+    Ok(Response::new().add_messages(msgs).add_attributes(vec![
+        attr("action", "rebalance"),
+        attr("redelegated", redelegated),
+    ]))
+}
+
+/// Delegation each validator should hold when `total` is delegated
+fn target_delegations(validators: &[ValidatorInfo], total: Uint128) -> Vec<Uint128> {
+    let total_weight: u64 = validators.iter().map(|v| v.weight).sum();
+    validators
+        .iter()
+        .map(|v| {
+            if total_weight == 0 {
+                Uint128::zero()
+            } else {
+                total.multiply_ratio(v.weight, total_weight)
+            }
+        })
+        .collect()
+}
+
+/// Books the stable rewards the chain withdraws along with any change to
+/// `validator`'s delegation, for the next harvest to compound. Rewards
+/// withdrawn earlier in this block still show up in queries and are skipped.
+fn book_withdrawn_rewards(
+    deps: Deps,
+    env: &Env,
+    config: &Config,
+    state: &mut State,
+    validator: &mut ValidatorInfo,
+) -> StdResult<()> {
+    if validator.rewards_height == env.block.height {
+        return Ok(());
+    }
+
+    let rewards = match deps
+        .querier
+        .query_delegation(env.contract.address.as_str(), validator.address.as_str())?
+    {
+        Some(delegation) => delegation.accumulated_rewards,
+        None => return Ok(()),
+    };
+    if rewards.iter().all(|coin| coin.amount.is_zero()) {
+        return Ok(());
+    }
+
+    validator.rewards_height = env.block.height;
+    state.withdrawn_rewards += rewards
+        .iter()
+        .filter(|coin| coin.denom == config.stable_denom)
+        .map(|coin| coin.amount)
+        .sum::<Uint128>();
+    Ok(())
+}
+
+/// Delegates `amount` to the validator furthest below its target
+pub fn delegate_msgs(
+    deps: DepsMut,
+    env: &Env,
+    config: &Config,
+    amount: Uint128,
+) -> Result<Vec<CosmosMsg>, ContractError> {
+    if amount.is_zero() {
+        return Ok(vec![]);
+    }
+
+    let mut state: State = read_state(deps.storage)?;
+    let validators = read_validators(deps.storage)?;
+    let targets = target_delegations(&validators, state.total_delegated + amount);
+    let mut validator = validators
+        .into_iter()
+        .zip(targets)
+        .filter(|(v, _)| v.weight > 0)
+        .max_by_key(|(v, target)| target.saturating_sub(v.delegated))
+        .map(|(v, _)| v)
+        .ok_or(ContractError::NoValidators {})?;
+    validator.delegated += amount;
+    book_withdrawn_rewards(deps.as_ref(), env, config, &mut state, &mut validator)?;
+    store_validator(deps.storage, &validator)?;
+
+    state.total_delegated += amount;
+    store_state(deps.storage, &state)?;
+
+    Ok(vec![CosmosMsg::Staking(StakingMsg::Delegate {
+        validator: validator.address,
+        amount: Coin {
+            denom: config.stable_denom.clone(),
+            amount,
+        },
+    })])
+}
+
+/// Undelegates up to `amount`, starting with the validators furthest above
+/// their target. Returns the undelegate messages and the amount undelegated.
+pub fn undelegate_msgs(
+    deps: DepsMut,
+    env: &Env,
+    config: &Config,
+    amount: Uint128,
+) -> Result<(Vec<CosmosMsg>, Uint128), ContractError> {
+    let mut state: State = read_state(deps.storage)?;
+    let validators = read_validators(deps.storage)?;
+    let targets = target_delegations(&validators, state.total_delegated.saturating_sub(amount));
+    let mut validators: Vec<(ValidatorInfo, Uint128)> =
+        validators.into_iter().zip(targets).collect();
+    validators
+        .sort_by_key(|(v, target)| Reverse((v.delegated.saturating_sub(*target), v.delegated)));
+
+    let mut msgs: Vec<CosmosMsg> = vec![];
+    let mut remaining = amount;
+    for (mut validator, _) in validators.into_iter() {
+        if remaining.is_zero() {
+            break;
+        }
+
+        let undelegate_amount = min(remaining, validator.delegated);
+        if undelegate_amount.is_zero() {
+            continue;
+        }
+
+        validator.delegated -= undelegate_amount;
+        remaining -= undelegate_amount;
+        book_withdrawn_rewards(deps.as_ref(), env, config, &mut state, &mut validator)?;
+        store_validator(deps.storage, &validator)?;
+        msgs.push(CosmosMsg::Staking(StakingMsg::Undelegate {
+            validator: validator.address,
+            amount: Coin {
+                denom: config.stable_denom.clone(),
+                amount: undelegate_amount,
+            },
+        }));
+    }
+
+    let undelegated = amount - remaining;
+    state.total_delegated -= undelegated;
+    store_state(deps.storage, &state)?;
+
+    Ok((msgs, undelegated))
+}
+
+/// Undelegates the stake redeemed since the last epoch in one batch, so
+/// every validator gets at most one unbonding entry per epoch
+pub fn undelegate_pending(deps: DepsMut, env: &Env) -> Result<Response, ContractError> {
+    let config: Config = read_config(deps.storage)?;
+    let mut state: State = read_state(deps.storage)?;
+    let pending = state.pending_undelegation;
+    state.pending_undelegation = Uint128::zero();
+    store_state(deps.storage, &state)?;
+
+    let (msgs, undelegated) = undelegate_msgs(deps, env, &config, pending)?;
+    Ok(Response::new()
+        .add_messages(msgs)
+        .add_attributes(vec![attr("undelegated", undelegated)]))
+}
+
+pub fn query_validators(deps: Deps, env: Env) -> StdResult<ValidatorsResponse> {
+    let state: State = read_state(deps.storage)?;
+    let validators = read_validators(deps.storage)?;
+    let targets = target_delegations(&validators, state.total_delegated);
+
+    let validators = validators
+        .into_iter()
+        .zip(targets)
+        .map(|(v, target)| {
+            let actual = deps
+                .querier
+                .query_delegation(env.contract.address.as_str(), v.address.as_str())?
+                .map(|delegation| delegation.amount.amount)
+                .unwrap_or_else(Uint128::zero);
+            Ok(ValidatorResponse {
+                address: v.address,
+                weight: v.weight,
+                target,
+                delegated: v.delegated,
+                actual,
+            })
+        })
+        .collect::<StdResult<Vec<ValidatorResponse>>>()?;
+
+    Ok(ValidatorsResponse { validators })
+}
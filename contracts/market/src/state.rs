@@ -1,40 +1,241 @@
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
-use cosmwasm_std::{CanonicalAddr, Decimal, StdResult, Storage, Uint128};
-use cosmwasm_storage::{bucket, bucket_read, ReadonlySingleton, Singleton};
+use cosmwasm_std::{Binary, CanonicalAddr, Decimal, Order, StdResult, Storage, Uint128};
+use cosmwasm_storage::{bucket, bucket_read, Bucket, ReadonlyBucket, ReadonlySingleton, Singleton};
 use cw20::Cw20ReceiveMsg;
+use terraswap::asset::AssetInfo;
 
 use crate::error::ContractError;
 
 pub const KEY_CONFIG: &[u8] = b"config";
 pub const KEY_STATE: &[u8] = b"state";
+pub const KEY_PENDING_MATURITY: &[u8] = b"pending_maturity";
 const DEPOSITS: &[u8] = b"deposit";
 const TVLS: &[u8] = b"tvl_history";
+const ALLOWLIST: &[u8] = b"allowlist";
+const WITHDRAWAL_QUEUE: &[u8] = b"withdrawal_queue";
+const QUEUED_IDS: &[u8] = b"queued_ids";
+const CLAIMABLE: &[u8] = b"claimable";
+const UNBONDINGS: &[u8] = b"unbonding";
+const VALIDATORS: &[u8] = b"validators";
+const SWAP_ROUTES: &[u8] = b"swap_route";
+const STRATEGIES: &[u8] = b"strategy";
+const STREAMS: &[u8] = b"stream";
+const SPONSORS: &[u8] = b"sponsor";
+const STREAM_REWARDS: &[u8] = b"stream_reward";
+const LOCKS: &[u8] = b"lock";
+const REFERRALS: &[u8] = b"referral";
+const REDIRECTS: &[u8] = b"redirect";
+const INCOMING_REDIRECTS: &[u8] = b"incoming_redirect";
+const OPERATORS: &[u8] = b"operator";
+const COMMITMENTS: &[u8] = b"commitment";
+const DRAWS: &[u8] = b"draw";
+const PRIZES: &[u8] = b"prize";
+const MATURITIES: &[u8] = b"maturity";
+const RECOMBINATIONS: &[u8] = b"recombination";
+
+/// Default unbonding period of the chain, 21 days
+pub const DEFAULT_CHAIN_UNBONDING_PERIOD: u64 = 21 * 86400;
+/// Default longest lock, four years
+pub const DEFAULT_MAX_LOCK_DURATION: u64 = 4 * 365 * 86400;
+
+const MAX_LIMIT: u32 = 30;
+const DEFAULT_LIMIT: u32 = 10;
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
-pub struct MigrateMsg {}
+pub struct MigrateMsg {
+    /// Owner of a market upgraded from a version without one
+    pub owner_addr: Option<String>,
+}
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub struct InstantiateMsg {
+    pub owner_addr: String,
     pub stable_denom: String,
     pub aterra_code_id: u64,
     pub interest: String,
+    /// Maximum stable value of `State.tvl` accepted by `DepositStable`,
+    /// unlimited if unset
+    pub tvl_cap: Option<Uint128>,
+    /// Maximum stable value a single depositor may hold, unlimited if unset
+    pub max_balance: Option<Uint128>,
+    /// Minimum amount accepted by a single `DepositStable`
+    pub min_deposit: Option<Uint128>,
+    /// Only allowlisted depositors may `DepositStable`
+    pub permissioned: Option<bool>,
+    /// cw4 style group contract whose members may deposit in permissioned mode
+    pub membership_contract: Option<String>,
+    /// Seconds redemptions unbond before they can be claimed, zero pays out immediately
+    pub unbonding_period: Option<u64>,
+    /// Delegate deposits to the registered validators, `stable_denom`
+    /// must be the bonded denom and `unbonding_period` cover the chain's
+    pub native_staking: Option<bool>,
+    /// Unbonding period of the chain's staking module in seconds,
+    /// 21 days if unset
+    pub chain_unbonding_period: Option<u64>,
+    /// Share of harvested staking rewards paid to the `Harvest` caller
+    pub harvest_bounty: Option<Decimal>,
+    /// Maximum amount redelegated by a single `Rebalance`, unlimited if unset
+    pub max_rebalance: Option<Uint128>,
+    /// Share of total assets kept liquid instead of allocated to strategies
+    pub liquidity_buffer: Option<Decimal>,
+    /// Minimum seconds between `ExecuteEpochOperations` calls
+    pub epoch_period: Option<u64>,
+    /// Stable coins paid from the reserve to the `ExecuteEpochOperations` caller
+    pub epoch_bounty: Option<Uint128>,
+    /// cw20 token emitted to depositors, held by the contract
+    pub reward_token: Option<String>,
+    /// Reward tokens emitted per second across all depositors
+    pub emission_rate: Option<Decimal>,
+    /// Distribution model adjusting `emission_rate` every epoch
+    pub distribution_model: Option<String>,
+    /// Deposit rate per `DURATION` the emissions steer towards
+    pub target_deposit_rate: Option<Decimal>,
+    /// Deposit rate per `DURATION` below which emissions grow the fastest
+    pub threshold_deposit_rate: Option<Decimal>,
+    /// cw20 token depositors lock to boost their reward emissions
+    pub governance_token: Option<String>,
+    /// Longest lock in seconds, granting the full voting power
+    pub max_lock_duration: Option<u64>,
+    /// Upper bound of the reward boost multiplier, at least 1
+    pub max_boost: Option<Decimal>,
+    /// Voting power per receipt token needed for the full boost
+    pub boost_ratio: Option<Decimal>,
+    /// Share of a depositor's interest paid from the reserve to their referrer
+    pub referral_share: Option<Decimal>,
+    /// Pool the interest of each draw period and award it to depositors
+    /// drawn by time weighted balance instead of paying it individually
+    pub prize_mode: Option<bool>,
+    /// Seconds per prize draw, draws start at instantiation
+    pub draw_period: Option<u64>,
+    /// Seconds after a draw ends during which operators reveal their secrets
+    pub reveal_period: Option<u64>,
+    /// Number of equal prizes per draw
+    pub prize_winners: Option<u32>,
+}
+
+fn default_chain_unbonding_period() -> u64 {
+    DEFAULT_CHAIN_UNBONDING_PERIOD
+}
+
+fn default_max_lock_duration() -> u64 {
+    DEFAULT_MAX_LOCK_DURATION
+}
+
+/// Owner of a market stored before it had one, set by `migrate`
+fn missing_owner() -> CanonicalAddr {
+    CanonicalAddr::from(vec![])
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct Config {
     pub contract_addr: CanonicalAddr,
+    #[serde(default = "missing_owner")]
+    pub owner_addr: CanonicalAddr,
     pub aterra_contract: CanonicalAddr,
     pub stable_denom: String,
     pub interest_rate: Decimal,
+    /// zero disables the cap
+    #[serde(default)]
+    pub tvl_cap: Uint128,
+    /// zero disables the cap
+    #[serde(default)]
+    pub max_balance: Uint128,
+    #[serde(default)]
+    pub min_deposit: Uint128,
+    #[serde(default)]
+    pub permissioned: bool,
+    #[serde(default)]
+    pub membership_contract: Option<CanonicalAddr>,
+    #[serde(default)]
+    pub unbonding_period: u64,
+    #[serde(default)]
+    pub native_staking: bool,
+    /// Minimum `unbonding_period` in native staking mode
+    #[serde(default = "default_chain_unbonding_period")]
+    pub chain_unbonding_period: u64,
+    #[serde(default)]
+    pub harvest_bounty: Decimal,
+    /// zero disables the cap
+    #[serde(default)]
+    pub max_rebalance: Uint128,
+    #[serde(default)]
+    pub liquidity_buffer: Decimal,
+    #[serde(default)]
+    pub epoch_period: u64,
+    #[serde(default)]
+    pub epoch_bounty: Uint128,
+    #[serde(default)]
+    pub reward_token: Option<CanonicalAddr>,
+    #[serde(default)]
+    pub emission_rate: Decimal,
+    #[serde(default)]
+    pub distribution_model: Option<CanonicalAddr>,
+    #[serde(default)]
+    pub target_deposit_rate: Decimal,
+    #[serde(default)]
+    pub threshold_deposit_rate: Decimal,
+    #[serde(default)]
+    pub governance_token: Option<CanonicalAddr>,
+    #[serde(default = "default_max_lock_duration")]
+    pub max_lock_duration: u64,
+    #[serde(default = "Decimal::one")]
+    pub max_boost: Decimal,
+    #[serde(default = "Decimal::one")]
+    pub boost_ratio: Decimal,
+    #[serde(default)]
+    pub referral_share: Decimal,
+    #[serde(default)]
+    pub prize_mode: bool,
+    #[serde(default)]
+    pub draw_start: u64,
+    #[serde(default)]
+    pub draw_period: u64,
+    #[serde(default)]
+    pub reveal_period: u64,
+    #[serde(default)]
+    pub prize_winners: u32,
+    /// Token of the junior tranche, taking residual yield and first losses
+    #[serde(default)]
+    pub junior_token: Option<CanonicalAddr>,
 }
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct ConfigResponse {
+    pub owner_addr: String,
     pub aterra_contract: String,
     pub stable_denom: String,
     pub interest_rate: Decimal,
+    pub tvl_cap: Uint128,
+    pub max_balance: Uint128,
+    pub min_deposit: Uint128,
+    pub permissioned: bool,
+    pub membership_contract: Option<String>,
+    pub unbonding_period: u64,
+    pub native_staking: bool,
+    pub chain_unbonding_period: u64,
+    pub harvest_bounty: Decimal,
+    pub max_rebalance: Uint128,
+    pub liquidity_buffer: Decimal,
+    pub epoch_period: u64,
+    pub epoch_bounty: Uint128,
+    pub reward_token: Option<String>,
+    pub emission_rate: Decimal,
+    pub distribution_model: Option<String>,
+    pub target_deposit_rate: Decimal,
+    pub threshold_deposit_rate: Decimal,
+    pub governance_token: Option<String>,
+    pub max_lock_duration: u64,
+    pub max_boost: Decimal,
+    pub boost_ratio: Decimal,
+    pub referral_share: Decimal,
+    pub prize_mode: bool,
+    pub draw_start: u64,
+    pub draw_period: u64,
+    pub reveal_period: u64,
+    pub prize_winners: u32,
+    pub junior_token: Option<String>,
 }
 
 pub fn store_config(storage: &mut dyn Storage, data: &Config) -> StdResult<()> {
@@ -45,13 +246,62 @@ pub fn read_config(storage: &dyn Storage) -> StdResult<Config> {
     ReadonlySingleton::new(storage, KEY_CONFIG).load()
 }
 
-#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, JsonSchema)]
 pub struct DepositInfo {
     pub last_interaction: u64,
     pub last_balance: Uint128,
     pub accrued_interest: Uint128,
     pub initial_interaction: u64,
     pub sum_deposits: Uint128,
+    /// `State.global_reward_index` when rewards were last accrued
+    #[serde(default)]
+    pub reward_index: Decimal,
+    #[serde(default)]
+    pub pending_rewards: Uint128,
+    /// Balance boosted by the locked governance tokens, earning emissions
+    #[serde(default)]
+    pub working_balance: Uint128,
+    /// First referrer of the depositor, never replaced
+    #[serde(default)]
+    pub referrer: Option<CanonicalAddr>,
+    /// Interest is paid out here in stable coins instead of compounding
+    #[serde(default)]
+    pub payout_to: Option<CanonicalAddr>,
+    /// Stable coins of interest waiting to be paid to `payout_to`
+    #[serde(default)]
+    pub unpaid_interest: Uint128,
+    /// Beneficiary receiving `redirect_share` of the interest
+    #[serde(default)]
+    pub redirect_to: Option<CanonicalAddr>,
+    #[serde(default)]
+    pub redirect_share: Decimal,
+    /// Stable coins of interest redirected in total
+    #[serde(default)]
+    pub total_redirected: Uint128,
+    /// Time weighted balance for prize draws
+    #[serde(default)]
+    pub twab: Twab,
+}
+
+/// Depositor addresses in ascending order, used to page through `SettleRange`
+pub fn read_deposit_addresses(
+    storage: &dyn Storage,
+    start_after: Option<CanonicalAddr>,
+    limit: Option<u32>,
+) -> StdResult<Vec<CanonicalAddr>> {
+    let deposits: ReadonlyBucket<DepositInfo> = bucket_read(storage, DEPOSITS);
+
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = calc_range_start(start_after);
+
+    deposits
+        .range(start.as_deref(), None, Order::Ascending)
+        .take(limit)
+        .map(|item| {
+            let (k, _) = item?;
+            Ok(CanonicalAddr::from(k))
+        })
+        .collect()
 }
 
 pub fn store_deposit_info(
@@ -62,17 +312,11 @@ pub fn store_deposit_info(
     bucket(storage, DEPOSITS).save(ident.as_slice(), deposit)
 }
 
-pub fn read_deposit_info(storage: &dyn Storage, ident: &CanonicalAddr) -> DepositInfo {
-    match bucket_read(storage, DEPOSITS).load(ident.as_slice()) {
-        Ok(v) => v,
-        _ => DepositInfo {
-            last_interaction: 0,
-            last_balance: Uint128::zero(),
-            accrued_interest: Uint128::zero(),
-            initial_interaction: 0,
-            sum_deposits: Uint128::zero(),
-        },
-    }
+/// Deposit of `ident`, empty if there is none
+pub fn read_deposit_info(storage: &dyn Storage, ident: &CanonicalAddr) -> StdResult<DepositInfo> {
+    Ok(bucket_read(storage, DEPOSITS)
+        .may_load(ident.as_slice())?
+        .unwrap_or_default())
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
@@ -80,6 +324,101 @@ pub struct State {
     pub tvl: Uint128,
     pub tvl_indices: i64,
     pub accrued_interest_payments: Uint128,
+    /// Stable coins redeemable per receipt token, raised by harvested yield
+    #[serde(default = "Decimal::one")]
+    pub exchange_rate: Decimal,
+    #[serde(default)]
+    pub last_harvest_time: u64,
+    #[serde(default)]
+    pub last_harvest_amount: Uint128,
+    #[serde(default)]
+    pub total_harvested: Uint128,
+    /// Stable coins contributed through `FundReserve`, spent covering slashes
+    #[serde(default)]
+    pub reserve: Uint128,
+    /// First unfilled withdrawal queue id
+    #[serde(default)]
+    pub queue_head: u64,
+    /// Next withdrawal queue id
+    #[serde(default)]
+    pub queue_tail: u64,
+    /// Unfilled amount waiting in the withdrawal queue
+    #[serde(default)]
+    pub total_queued: Uint128,
+    /// Filled amount set aside for `ClaimQueued`
+    #[serde(default)]
+    pub total_claimable: Uint128,
+    /// Redeemed amount waiting for the unbonding period to pass
+    #[serde(default)]
+    pub total_unbonding: Uint128,
+    /// Amount delegated to validators in native staking mode
+    #[serde(default)]
+    pub total_delegated: Uint128,
+    /// Redeemed stake undelegated in one batch at the next epoch
+    #[serde(default)]
+    pub pending_undelegation: Uint128,
+    /// Stable rewards the chain withdrew on delegation changes, compounded
+    /// by the next harvest
+    #[serde(default)]
+    pub withdrawn_rewards: Uint128,
+    /// Delegated stake lost to slashing, found by `Reconcile`
+    #[serde(default)]
+    pub total_slashed: Uint128,
+    /// Reward tokens emitted per working balance
+    #[serde(default)]
+    pub global_reward_index: Decimal,
+    #[serde(default)]
+    pub last_reward_updated: u64,
+    /// Sum of the depositors' working balances
+    #[serde(default)]
+    pub total_working_balance: Uint128,
+    /// Governance tokens locked for boosts
+    #[serde(default)]
+    pub total_locked: Uint128,
+    /// Referral rewards taken from the reserve and not claimed yet
+    #[serde(default)]
+    pub total_referral_pending: Uint128,
+    /// Redirected interest not claimed by the beneficiaries yet
+    #[serde(default)]
+    pub total_redirect_claimable: Uint128,
+    /// Sum of the depositors' `unpaid_interest`
+    #[serde(default)]
+    pub total_unpaid_interest: Uint128,
+    /// Time weighted `tvl` for prize draws
+    #[serde(default)]
+    pub total_twab: Twab,
+    /// `total_twab.lifetime` at the end of the last drawn draw
+    #[serde(default)]
+    pub drawn_twab: Uint128,
+    /// Prizes of draws without winners, added to the next draw
+    #[serde(default)]
+    pub prize_rollover: Uint128,
+    /// Prizes awarded and not claimed yet
+    #[serde(default)]
+    pub total_prizes_claimable: Uint128,
+    /// Id assigned to the next reward stream
+    #[serde(default)]
+    pub next_stream_id: u64,
+    /// Block time of the last `ExecuteEpochOperations`
+    #[serde(default)]
+    pub last_epoch_time: u64,
+    /// Exchange rate at the last `ExecuteEpochOperations`
+    #[serde(default = "Decimal::one")]
+    pub last_epoch_exchange_rate: Decimal,
+    /// Block time up to which senior interest was set against yield
+    #[serde(default)]
+    pub yield_checkpoint_time: u64,
+    /// Running totals used to derive queue positions
+    #[serde(default)]
+    pub total_enqueued: Uint128,
+    #[serde(default)]
+    pub total_filled: Uint128,
+    /// Junior tokens outstanding
+    #[serde(default)]
+    pub junior_supply: Uint128,
+    /// Stable coins redeemable per junior token, moved by the waterfall
+    #[serde(default = "Decimal::one")]
+    pub junior_exchange_rate: Decimal,
 }
 
 pub fn store_state(storage: &mut dyn Storage, data: &State) -> StdResult<()> {
@@ -90,6 +429,914 @@ pub fn read_state(storage: &dyn Storage) -> StdResult<State> {
     ReadonlySingleton::new(storage, KEY_STATE).load()
 }
 
+pub fn store_allowlisted(storage: &mut dyn Storage, ident: &CanonicalAddr) -> StdResult<()> {
+    bucket(storage, ALLOWLIST).save(ident.as_slice(), &true)
+}
+
+pub fn remove_allowlisted(storage: &mut dyn Storage, ident: &CanonicalAddr) {
+    bucket::<bool>(storage, ALLOWLIST).remove(ident.as_slice())
+}
+
+pub fn is_allowlisted(storage: &dyn Storage, ident: &CanonicalAddr) -> bool {
+    bucket_read::<bool>(storage, ALLOWLIST)
+        .may_load(ident.as_slice())
+        .unwrap_or_default()
+        .unwrap_or(false)
+}
+
+pub fn read_allowlist(
+    storage: &dyn Storage,
+    start_after: Option<CanonicalAddr>,
+    limit: Option<u32>,
+) -> StdResult<Vec<CanonicalAddr>> {
+    let allowlist: ReadonlyBucket<bool> = bucket_read(storage, ALLOWLIST);
+
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = calc_range_start(start_after);
+
+    allowlist
+        .range(start.as_deref(), None, Order::Ascending)
+        .take(limit)
+        .map(|item| {
+            let (k, _) = item?;
+            Ok(CanonicalAddr::from(k))
+        })
+        .collect()
+}
+
+// this will set the first key after the provided key, by appending a 1 byte
+fn calc_range_start(start_after: Option<CanonicalAddr>) -> Option<Vec<u8>> {
+    start_after.map(|addr| {
+        let mut v = addr.as_slice().to_vec();
+        v.push(1);
+        v
+    })
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct AllowlistResponse {
+    pub addresses: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct QueuedWithdrawal {
+    pub owner: CanonicalAddr,
+    /// Amount still waiting for liquidity
+    pub amount: Uint128,
+    /// `State.total_enqueued` when the withdrawal was queued
+    pub enqueued_before: Uint128,
+    pub requested_at: u64,
+}
+
+pub fn store_queued_withdrawal(
+    storage: &mut dyn Storage,
+    id: u64,
+    withdrawal: &QueuedWithdrawal,
+) -> StdResult<()> {
+    bucket(storage, WITHDRAWAL_QUEUE).save(&id.to_be_bytes(), withdrawal)
+}
+
+pub fn remove_queued_withdrawal(storage: &mut dyn Storage, id: u64) {
+    bucket::<QueuedWithdrawal>(storage, WITHDRAWAL_QUEUE).remove(&id.to_be_bytes())
+}
+
+pub fn read_queued_withdrawal(storage: &dyn Storage, id: u64) -> StdResult<QueuedWithdrawal> {
+    bucket_read(storage, WITHDRAWAL_QUEUE).load(&id.to_be_bytes())
+}
+
+pub fn store_queued_ids(
+    storage: &mut dyn Storage,
+    ident: &CanonicalAddr,
+    ids: &[u64],
+) -> StdResult<()> {
+    if ids.is_empty() {
+        bucket::<Vec<u64>>(storage, QUEUED_IDS).remove(ident.as_slice());
+        Ok(())
+    } else {
+        bucket(storage, QUEUED_IDS).save(ident.as_slice(), &ids.to_vec())
+    }
+}
+
+pub fn read_queued_ids(storage: &dyn Storage, ident: &CanonicalAddr) -> Vec<u64> {
+    bucket_read(storage, QUEUED_IDS)
+        .load(ident.as_slice())
+        .unwrap_or_default()
+}
+
+pub fn store_claimable(
+    storage: &mut dyn Storage,
+    ident: &CanonicalAddr,
+    amount: &Uint128,
+) -> StdResult<()> {
+    bucket(storage, CLAIMABLE).save(ident.as_slice(), amount)
+}
+
+pub fn read_claimable(storage: &dyn Storage, ident: &CanonicalAddr) -> Uint128 {
+    bucket_read(storage, CLAIMABLE)
+        .load(ident.as_slice())
+        .unwrap_or_else(|_| Uint128::zero())
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct QueuedWithdrawalResponse {
+    pub id: u64,
+    pub amount: Uint128,
+    /// Unfilled amount queued ahead of this withdrawal
+    pub amount_ahead: Uint128,
+    pub requested_at: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct WithdrawalQueueResponse {
+    pub claimable: Uint128,
+    pub queued: Vec<QueuedWithdrawalResponse>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Unbonding {
+    /// Receives the funds once the redeemer claims them
+    pub recipient: CanonicalAddr,
+    pub amount: Uint128,
+    pub release_time: u64,
+}
+
+pub fn store_unbondings(
+    storage: &mut dyn Storage,
+    ident: &CanonicalAddr,
+    unbondings: &[Unbonding],
+) -> StdResult<()> {
+    if unbondings.is_empty() {
+        bucket::<Vec<Unbonding>>(storage, UNBONDINGS).remove(ident.as_slice());
+        Ok(())
+    } else {
+        bucket(storage, UNBONDINGS).save(ident.as_slice(), &unbondings.to_vec())
+    }
+}
+
+pub fn read_unbondings(storage: &dyn Storage, ident: &CanonicalAddr) -> Vec<Unbonding> {
+    bucket_read(storage, UNBONDINGS)
+        .load(ident.as_slice())
+        .unwrap_or_default()
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct UnbondingEntryResponse {
+    pub recipient: String,
+    pub amount: Uint128,
+    pub release_time: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct UnbondingResponse {
+    pub unbondings: Vec<UnbondingEntryResponse>,
+    /// Sum of unbondings past their release time
+    pub claimable: Uint128,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ValidatorInfo {
+    pub address: String,
+    /// Share of the delegations the validator should hold relative
+    /// to the other validators' weights
+    pub weight: u64,
+    /// Amount the market expects to be delegated to the validator
+    pub delegated: Uint128,
+    /// Block height at which the validator's rewards were last withdrawn
+    #[serde(default)]
+    pub rewards_height: u64,
+}
+
+pub fn store_validator(storage: &mut dyn Storage, validator: &ValidatorInfo) -> StdResult<()> {
+    bucket(storage, VALIDATORS).save(validator.address.as_bytes(), validator)
+}
+
+pub fn read_validator(storage: &dyn Storage, address: &str) -> StdResult<Option<ValidatorInfo>> {
+    bucket_read(storage, VALIDATORS).may_load(address.as_bytes())
+}
+
+pub fn remove_validator(storage: &mut dyn Storage, address: &str) {
+    bucket::<ValidatorInfo>(storage, VALIDATORS).remove(address.as_bytes())
+}
+
+pub fn read_validators(storage: &dyn Storage) -> StdResult<Vec<ValidatorInfo>> {
+    let validators: ReadonlyBucket<ValidatorInfo> = bucket_read(storage, VALIDATORS);
+    validators
+        .range(None, None, Order::Ascending)
+        .map(|item| {
+            let (_, v) = item?;
+            Ok(v)
+        })
+        .collect()
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ValidatorResponse {
+    pub address: String,
+    pub weight: u64,
+    /// Delegation the validator should hold according to its weight
+    pub target: Uint128,
+    /// Delegation the market has accounted for
+    pub delegated: Uint128,
+    /// Delegation reported by the staking module
+    pub actual: Uint128,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ValidatorsResponse {
+    pub validators: Vec<ValidatorResponse>,
+}
+
+/// Terraswap pair used to swap `denom` into `stable_denom`
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct SwapRoute {
+    pub denom: String,
+    pub pair_addr: CanonicalAddr,
+    pub max_spread: Decimal,
+    /// Oracle pricing `denom` in `stable_denom`, bounding the swap return
+    pub oracle_addr: CanonicalAddr,
+}
+
+pub fn store_swap_route(storage: &mut dyn Storage, route: &SwapRoute) -> StdResult<()> {
+    bucket(storage, SWAP_ROUTES).save(route.denom.as_bytes(), route)
+}
+
+pub fn read_swap_route(storage: &dyn Storage, denom: &str) -> StdResult<Option<SwapRoute>> {
+    bucket_read(storage, SWAP_ROUTES).may_load(denom.as_bytes())
+}
+
+pub fn remove_swap_route(storage: &mut dyn Storage, denom: &str) {
+    bucket::<SwapRoute>(storage, SWAP_ROUTES).remove(denom.as_bytes())
+}
+
+pub fn read_swap_routes(storage: &dyn Storage) -> StdResult<Vec<SwapRoute>> {
+    let routes: ReadonlyBucket<SwapRoute> = bucket_read(storage, SWAP_ROUTES);
+    routes
+        .range(None, None, Order::Ascending)
+        .map(|item| {
+            let (_, v) = item?;
+            Ok(v)
+        })
+        .collect()
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct SwapRouteResponse {
+    pub denom: String,
+    pub pair_addr: String,
+    pub max_spread: Decimal,
+    pub oracle_addr: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct SwapRoutesResponse {
+    pub routes: Vec<SwapRouteResponse>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum StrategyKind {
+    /// Anchor style money market, deposited through `DepositStable`
+    /// and redeemed by sending its aTerra token
+    Anchor,
+    /// Contract implementing the strategy interface
+    Generic,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct StrategyInfo {
+    pub contract_addr: CanonicalAddr,
+    pub kind: StrategyKind,
+    /// aTerra token of an Anchor strategy
+    pub aterra_contract: Option<CanonicalAddr>,
+    pub weight: u64,
+    /// Stable coins deposited, raised to the position's value by `SkimYield`
+    pub principal: Uint128,
+}
+
+pub fn store_strategy(storage: &mut dyn Storage, strategy: &StrategyInfo) -> StdResult<()> {
+    bucket(storage, STRATEGIES).save(strategy.contract_addr.as_slice(), strategy)
+}
+
+pub fn read_strategy(
+    storage: &dyn Storage,
+    contract_addr: &CanonicalAddr,
+) -> StdResult<Option<StrategyInfo>> {
+    bucket_read(storage, STRATEGIES).may_load(contract_addr.as_slice())
+}
+
+pub fn remove_strategy(storage: &mut dyn Storage, contract_addr: &CanonicalAddr) {
+    bucket::<StrategyInfo>(storage, STRATEGIES).remove(contract_addr.as_slice())
+}
+
+pub fn read_strategies(storage: &dyn Storage) -> StdResult<Vec<StrategyInfo>> {
+    let strategies: ReadonlyBucket<StrategyInfo> = bucket_read(storage, STRATEGIES);
+    strategies
+        .range(None, None, Order::Ascending)
+        .map(|item| {
+            let (_, v) = item?;
+            Ok(v)
+        })
+        .collect()
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct StrategyResponse {
+    pub contract_addr: String,
+    pub kind: StrategyKind,
+    pub weight: u64,
+    /// Value the strategy should hold according to its weight
+    pub target: Uint128,
+    pub principal: Uint128,
+    /// Stable coins the position can be withdrawn for
+    pub value: Uint128,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct StrategiesResponse {
+    pub strategies: Vec<StrategyResponse>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct StrategyAssetResponse {
+    pub contract_addr: String,
+    pub value: Uint128,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct AssetBreakdownResponse {
+    /// Stable coins held by the contract and not set aside for claims
+    pub liquid: Uint128,
+    pub claimable: Uint128,
+    pub delegated: Uint128,
+    pub strategies: Vec<StrategyAssetResponse>,
+    pub total: Uint128,
+    /// Liquid amount the allocation keeps for redemptions
+    pub buffer_target: Uint128,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PendingRewardsResponse {
+    pub pending_rewards: Uint128,
+    pub reward_index: Decimal,
+    pub global_reward_index: Decimal,
+}
+
+/// Incentive campaign emitting `budget` of `asset` evenly between
+/// `start_time` and `end_time`
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct RewardStream {
+    pub id: u64,
+    pub sponsor: CanonicalAddr,
+    pub asset: AssetInfo,
+    pub budget: Uint128,
+    pub start_time: u64,
+    pub end_time: u64,
+    /// Stream rewards emitted per receipt token
+    pub global_index: Decimal,
+    pub last_updated: u64,
+    pub claimed: Uint128,
+}
+
+pub fn store_stream(storage: &mut dyn Storage, stream: &RewardStream) -> StdResult<()> {
+    bucket(storage, STREAMS).save(&stream.id.to_be_bytes(), stream)
+}
+
+pub fn read_stream(storage: &dyn Storage, id: u64) -> StdResult<Option<RewardStream>> {
+    bucket_read(storage, STREAMS).may_load(&id.to_be_bytes())
+}
+
+pub fn remove_stream(storage: &mut dyn Storage, id: u64) {
+    bucket::<RewardStream>(storage, STREAMS).remove(&id.to_be_bytes())
+}
+
+pub fn store_sponsor(storage: &mut dyn Storage, sponsor: &CanonicalAddr) -> StdResult<()> {
+    bucket(storage, SPONSORS).save(sponsor.as_slice(), &true)
+}
+
+pub fn remove_sponsor(storage: &mut dyn Storage, sponsor: &CanonicalAddr) {
+    bucket::<bool>(storage, SPONSORS).remove(sponsor.as_slice())
+}
+
+pub fn is_sponsor(storage: &dyn Storage, sponsor: &CanonicalAddr) -> bool {
+    bucket_read::<bool>(storage, SPONSORS)
+        .may_load(sponsor.as_slice())
+        .unwrap_or_default()
+        .unwrap_or(false)
+}
+
+pub fn read_streams(storage: &dyn Storage) -> StdResult<Vec<RewardStream>> {
+    let streams: ReadonlyBucket<RewardStream> = bucket_read(storage, STREAMS);
+    streams
+        .range(None, None, Order::Ascending)
+        .map(|item| {
+            let (_, v) = item?;
+            Ok(v)
+        })
+        .collect()
+}
+
+/// A depositor's position in a reward stream
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, JsonSchema)]
+pub struct StreamReward {
+    pub index: Decimal,
+    pub pending: Uint128,
+}
+
+fn stream_reward_key(id: u64, ident: &CanonicalAddr) -> Vec<u8> {
+    [&id.to_be_bytes(), ident.as_slice()].concat()
+}
+
+pub fn store_stream_reward(
+    storage: &mut dyn Storage,
+    id: u64,
+    ident: &CanonicalAddr,
+    reward: &StreamReward,
+) -> StdResult<()> {
+    bucket(storage, STREAM_REWARDS).save(&stream_reward_key(id, ident), reward)
+}
+
+pub fn read_stream_reward(storage: &dyn Storage, id: u64, ident: &CanonicalAddr) -> StreamReward {
+    bucket_read(storage, STREAM_REWARDS)
+        .may_load(&stream_reward_key(id, ident))
+        .unwrap_or_default()
+        .unwrap_or_default()
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct StreamResponse {
+    pub id: u64,
+    pub sponsor: String,
+    pub asset: AssetInfo,
+    pub budget: Uint128,
+    pub start_time: u64,
+    pub end_time: u64,
+    pub claimed: Uint128,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct StreamsResponse {
+    pub streams: Vec<StreamResponse>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PendingStreamRewardResponse {
+    pub id: u64,
+    pub asset: AssetInfo,
+    pub pending: Uint128,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PendingStreamRewardsResponse {
+    pub rewards: Vec<PendingStreamRewardResponse>,
+}
+
+/// Governance tokens locked until `end`
+#[derive(Serialize, Deserialize, Clone, Default, Debug, PartialEq, JsonSchema)]
+pub struct LockInfo {
+    pub amount: Uint128,
+    pub end: u64,
+}
+
+pub fn store_lock(
+    storage: &mut dyn Storage,
+    ident: &CanonicalAddr,
+    lock: &LockInfo,
+) -> StdResult<()> {
+    bucket(storage, LOCKS).save(ident.as_slice(), lock)
+}
+
+pub fn remove_lock(storage: &mut dyn Storage, ident: &CanonicalAddr) {
+    bucket::<LockInfo>(storage, LOCKS).remove(ident.as_slice())
+}
+
+pub fn read_lock(storage: &dyn Storage, ident: &CanonicalAddr) -> LockInfo {
+    bucket_read(storage, LOCKS)
+        .may_load(ident.as_slice())
+        .unwrap_or_default()
+        .unwrap_or_default()
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct BoostInfoResponse {
+    pub locked_amount: Uint128,
+    pub lock_end: u64,
+    pub voting_power: Uint128,
+    /// Multiplier the balance would get if updated now
+    pub boost: Decimal,
+    /// Working balance currently earning emissions
+    pub working_balance: Uint128,
+    pub total_working_balance: Uint128,
+}
+
+/// Attribution and rewards of a referrer
+#[derive(Serialize, Deserialize, Clone, Default, Debug, PartialEq, JsonSchema)]
+pub struct ReferralInfo {
+    /// Depositors who recorded this referrer
+    pub referrals: u64,
+    /// Receipt tokens of interest accrued by the referred depositors
+    pub referred_interest: Uint128,
+    /// Stable coins earned in total
+    pub total_earned: Uint128,
+    /// Stable coins waiting for `ClaimReferralRewards`
+    pub pending: Uint128,
+}
+
+pub fn store_referral(
+    storage: &mut dyn Storage,
+    referrer: &CanonicalAddr,
+    referral: &ReferralInfo,
+) -> StdResult<()> {
+    bucket(storage, REFERRALS).save(referrer.as_slice(), referral)
+}
+
+pub fn read_referral(storage: &dyn Storage, referrer: &CanonicalAddr) -> ReferralInfo {
+    bucket_read(storage, REFERRALS)
+        .may_load(referrer.as_slice())
+        .unwrap_or_default()
+        .unwrap_or_default()
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ReferralResponse {
+    pub referrer: String,
+    pub referrals: u64,
+    pub referred_interest: Uint128,
+    pub total_earned: Uint128,
+    pub pending: Uint128,
+}
+
+/// Interest redirected to a beneficiary, in stable coins
+#[derive(Serialize, Deserialize, Clone, Default, Debug, PartialEq, JsonSchema)]
+pub struct RedirectInfo {
+    pub total_received: Uint128,
+    pub claimable: Uint128,
+}
+
+pub fn store_redirect(
+    storage: &mut dyn Storage,
+    beneficiary: &CanonicalAddr,
+    redirect: &RedirectInfo,
+) -> StdResult<()> {
+    bucket(storage, REDIRECTS).save(beneficiary.as_slice(), redirect)
+}
+
+pub fn read_redirect(storage: &dyn Storage, beneficiary: &CanonicalAddr) -> RedirectInfo {
+    bucket_read(storage, REDIRECTS)
+        .may_load(beneficiary.as_slice())
+        .unwrap_or_default()
+        .unwrap_or_default()
+}
+
+/// Total interest `donor` redirected to `beneficiary`
+pub fn store_incoming_redirect(
+    storage: &mut dyn Storage,
+    beneficiary: &CanonicalAddr,
+    donor: &CanonicalAddr,
+    amount: &Uint128,
+) -> StdResult<()> {
+    Bucket::multilevel(storage, &[INCOMING_REDIRECTS, beneficiary.as_slice()])
+        .save(donor.as_slice(), amount)
+}
+
+pub fn read_incoming_redirect(
+    storage: &dyn Storage,
+    beneficiary: &CanonicalAddr,
+    donor: &CanonicalAddr,
+) -> Uint128 {
+    ReadonlyBucket::multilevel(storage, &[INCOMING_REDIRECTS, beneficiary.as_slice()])
+        .may_load(donor.as_slice())
+        .unwrap_or_default()
+        .unwrap_or_default()
+}
+
+pub fn read_incoming_redirects(
+    storage: &dyn Storage,
+    beneficiary: &CanonicalAddr,
+    start_after: Option<CanonicalAddr>,
+    limit: Option<u32>,
+) -> StdResult<Vec<(CanonicalAddr, Uint128)>> {
+    let redirects: ReadonlyBucket<Uint128> =
+        ReadonlyBucket::multilevel(storage, &[INCOMING_REDIRECTS, beneficiary.as_slice()]);
+
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = calc_range_start(start_after);
+
+    redirects
+        .range(start.as_deref(), None, Order::Ascending)
+        .take(limit)
+        .map(|item| {
+            let (k, v) = item?;
+            Ok((CanonicalAddr::from(k), v))
+        })
+        .collect()
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct InterestRedirectResponse {
+    pub beneficiary: Option<String>,
+    pub share: Decimal,
+    pub total_redirected: Uint128,
+    pub total_received: Uint128,
+    pub claimable: Uint128,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct IncomingRedirectResponse {
+    pub donor: String,
+    pub amount: Uint128,
+    /// Whether the donor still redirects to this beneficiary
+    pub active: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct IncomingRedirectsResponse {
+    pub redirects: Vec<IncomingRedirectResponse>,
+}
+
+/// Balance times seconds accumulated within a prize draw
+#[derive(Serialize, Deserialize, Clone, Default, Debug, PartialEq, JsonSchema)]
+pub struct Twab {
+    pub draw_id: u64,
+    pub cumulative: Uint128,
+    /// Final cumulative of the draw before `draw_id`
+    pub previous: Uint128,
+    pub updated: u64,
+    /// Balance times seconds accumulated across all draws
+    #[serde(default)]
+    pub lifetime: Uint128,
+}
+
+pub fn store_operator(storage: &mut dyn Storage, operator: &CanonicalAddr) -> StdResult<()> {
+    bucket(storage, OPERATORS).save(operator.as_slice(), &true)
+}
+
+pub fn remove_operator(storage: &mut dyn Storage, operator: &CanonicalAddr) {
+    bucket::<bool>(storage, OPERATORS).remove(operator.as_slice())
+}
+
+pub fn is_operator(storage: &dyn Storage, operator: &CanonicalAddr) -> bool {
+    bucket_read::<bool>(storage, OPERATORS)
+        .may_load(operator.as_slice())
+        .unwrap_or_default()
+        .unwrap_or_default()
+}
+
+pub fn read_operators(storage: &dyn Storage) -> StdResult<Vec<CanonicalAddr>> {
+    let operators: ReadonlyBucket<bool> = bucket_read(storage, OPERATORS);
+    operators
+        .range(None, None, Order::Ascending)
+        .map(|item| {
+            let (k, _) = item?;
+            Ok(CanonicalAddr::from(k))
+        })
+        .collect()
+}
+
+/// An operator's sha256 commitment to a secret for a draw
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Commitment {
+    pub commitment: Binary,
+    pub secret: Option<Binary>,
+}
+
+pub fn store_commitment(
+    storage: &mut dyn Storage,
+    draw_id: u64,
+    operator: &CanonicalAddr,
+    commitment: &Commitment,
+) -> StdResult<()> {
+    Bucket::multilevel(storage, &[COMMITMENTS, &draw_id.to_be_bytes()])
+        .save(operator.as_slice(), commitment)
+}
+
+pub fn read_commitment(
+    storage: &dyn Storage,
+    draw_id: u64,
+    operator: &CanonicalAddr,
+) -> StdResult<Option<Commitment>> {
+    ReadonlyBucket::multilevel(storage, &[COMMITMENTS, &draw_id.to_be_bytes()])
+        .may_load(operator.as_slice())
+}
+
+/// Commitments of a draw in operator order
+pub fn read_commitments(
+    storage: &dyn Storage,
+    draw_id: u64,
+) -> StdResult<Vec<(CanonicalAddr, Commitment)>> {
+    let commitments: ReadonlyBucket<Commitment> =
+        ReadonlyBucket::multilevel(storage, &[COMMITMENTS, &draw_id.to_be_bytes()]);
+    commitments
+        .range(None, None, Order::Ascending)
+        .map(|item| {
+            let (k, v) = item?;
+            Ok((CanonicalAddr::from(k), v))
+        })
+        .collect()
+}
+
+/// Awarding of a finished draw, walking the depositors in address order
+/// to find the holders of the drawn `targets`
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Draw {
+    pub id: u64,
+    pub seed: Binary,
+    pub total_twab: Uint128,
+    pub prize_per_winner: Uint128,
+    /// Sorted random positions in `[0, total_twab)`
+    pub targets: Vec<Uint128>,
+    pub next_target: u32,
+    pub winners: Vec<CanonicalAddr>,
+    /// Last depositor walked and the twab walked so far
+    pub cursor: Option<CanonicalAddr>,
+    pub walked: Uint128,
+    pub awarded: bool,
+}
+
+pub fn store_draw(storage: &mut dyn Storage, draw: &Draw) -> StdResult<()> {
+    bucket(storage, DRAWS).save(&draw.id.to_be_bytes(), draw)
+}
+
+pub fn read_draw(storage: &dyn Storage, draw_id: u64) -> StdResult<Option<Draw>> {
+    bucket_read(storage, DRAWS).may_load(&draw_id.to_be_bytes())
+}
+
+#[derive(Serialize, Deserialize, Clone, Default, Debug, PartialEq, JsonSchema)]
+pub struct PrizeInfo {
+    pub claimable: Uint128,
+    pub total_won: Uint128,
+}
+
+pub fn store_prize(
+    storage: &mut dyn Storage,
+    winner: &CanonicalAddr,
+    prize: &PrizeInfo,
+) -> StdResult<()> {
+    bucket(storage, PRIZES).save(winner.as_slice(), prize)
+}
+
+pub fn read_prize(storage: &dyn Storage, winner: &CanonicalAddr) -> PrizeInfo {
+    bucket_read(storage, PRIZES)
+        .may_load(winner.as_slice())
+        .unwrap_or_default()
+        .unwrap_or_default()
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct DrawResponse {
+    pub id: u64,
+    pub start_time: u64,
+    pub end_time: u64,
+    pub reveal_end_time: u64,
+    pub commitments: u32,
+    pub reveals: u32,
+    pub total_twab: Option<Uint128>,
+    pub prize_per_winner: Option<Uint128>,
+    pub winners: Vec<String>,
+    pub awarded: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PrizeResponse {
+    pub claimable: Uint128,
+    pub total_won: Uint128,
+    /// Time weighted balance in the current draw so far
+    pub twab: Uint128,
+    pub total_twab: Uint128,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct OperatorsResponse {
+    pub operators: Vec<String>,
+}
+
+/// Principal and yield tokens split from receipt tokens escrowed until
+/// `maturity`
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Maturity {
+    pub maturity: u64,
+    pub principal_token: CanonicalAddr,
+    pub yield_token: CanonicalAddr,
+    /// Escrowed receipt tokens, compounding until maturity
+    pub balance: Uint128,
+    /// Stable coins of principal outstanding, one per principal token
+    pub principal: Uint128,
+    pub last_interaction: u64,
+    /// Receipt tokens paid per principal and yield token, fixed at maturity
+    pub principal_rate: Decimal,
+    pub yield_rate: Decimal,
+    pub matured: bool,
+}
+
+pub fn store_maturity(storage: &mut dyn Storage, maturity: &Maturity) -> StdResult<()> {
+    bucket(storage, MATURITIES).save(&maturity.maturity.to_be_bytes(), maturity)
+}
+
+pub fn read_maturity(storage: &dyn Storage, maturity: u64) -> StdResult<Option<Maturity>> {
+    bucket_read(storage, MATURITIES).may_load(&maturity.to_be_bytes())
+}
+
+pub fn read_maturities(
+    storage: &dyn Storage,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<Vec<Maturity>> {
+    let maturities: ReadonlyBucket<Maturity> = bucket_read(storage, MATURITIES);
+
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.map(|maturity| (maturity + 1).to_be_bytes().to_vec());
+
+    maturities
+        .range(start.as_deref(), None, Order::Ascending)
+        .take(limit)
+        .map(|item| {
+            let (_, v) = item?;
+            Ok(v)
+        })
+        .collect()
+}
+
+/// Principal and yield tokens of a maturity escrowed by their owner to
+/// recombine, burned as soon as both are sent
+#[derive(Serialize, Deserialize, Clone, Default, Debug, PartialEq, JsonSchema)]
+pub struct Recombination {
+    pub principal_tokens: Uint128,
+    pub yield_tokens: Uint128,
+}
+
+pub fn store_recombination(
+    storage: &mut dyn Storage,
+    maturity: u64,
+    owner: &CanonicalAddr,
+    recombination: &Recombination,
+) -> StdResult<()> {
+    Bucket::multilevel(storage, &[RECOMBINATIONS, &maturity.to_be_bytes()])
+        .save(owner.as_slice(), recombination)
+}
+
+pub fn remove_recombination(storage: &mut dyn Storage, maturity: u64, owner: &CanonicalAddr) {
+    Bucket::<Recombination>::multilevel(storage, &[RECOMBINATIONS, &maturity.to_be_bytes()])
+        .remove(owner.as_slice())
+}
+
+pub fn read_recombination(
+    storage: &dyn Storage,
+    maturity: u64,
+    owner: &CanonicalAddr,
+) -> StdResult<Recombination> {
+    Ok(
+        ReadonlyBucket::multilevel(storage, &[RECOMBINATIONS, &maturity.to_be_bytes()])
+            .may_load(owner.as_slice())?
+            .unwrap_or_default(),
+    )
+}
+
+/// Maturity whose tokens are being instantiated
+pub fn store_pending_maturity(storage: &mut dyn Storage, maturity: u64) -> StdResult<()> {
+    Singleton::new(storage, KEY_PENDING_MATURITY).save(&maturity)
+}
+
+pub fn read_pending_maturity(storage: &dyn Storage) -> StdResult<u64> {
+    ReadonlySingleton::new(storage, KEY_PENDING_MATURITY).load()
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct MaturityResponse {
+    pub maturity: u64,
+    pub principal_token: String,
+    pub yield_token: String,
+    pub balance: Uint128,
+    pub principal: Uint128,
+    /// Stable coins of interest earned for yield token holders so far
+    pub accrued_yield: Uint128,
+    pub matured: bool,
+    pub principal_rate: Decimal,
+    pub yield_rate: Decimal,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct MaturitiesResponse {
+    pub maturities: Vec<MaturityResponse>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct TranchesResponse {
+    pub senior_supply: Uint128,
+    pub senior_exchange_rate: Decimal,
+    pub junior_token: Option<String>,
+    pub junior_supply: Uint128,
+    pub junior_exchange_rate: Decimal,
+    /// Stable coins the junior tokens are redeemable for
+    pub junior_value: Uint128,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct HarvestResponse {
+    pub last_harvest_time: u64,
+    pub last_harvest_amount: Uint128,
+    pub total_harvested: Uint128,
+    pub exchange_rate: Decimal,
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct Tvl {
     pub tvl: Uint128,
@@ -102,7 +1349,7 @@ pub fn store_tvl_indice(
     direction: usize,
 ) -> Result<(), ContractError> {
     let mut state: State = ReadonlySingleton::new(storage, KEY_STATE).load()?;
-    let epoch_counter = state.tvl_indices.clone();
+    let epoch_counter = state.tvl_indices;
     state.tvl_indices += 1;
 
     if direction == 1 {
@@ -162,8 +1409,69 @@ pub fn read_tvl_indices(storage: &dyn Storage, tvl_indices: i64) -> StdResult<Ve
 pub enum QueryMsg {
     Config {},
     State {},
-    Ident { address: String, epoch: u64 },
-    Tvl { indice: i64 },
+    Ident {
+        address: String,
+        epoch: u64,
+    },
+    Tvl {
+        indice: i64,
+    },
+    Allowlist {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    WithdrawalQueue {
+        address: String,
+    },
+    Unbonding {
+        address: String,
+    },
+    Validators {},
+    Harvest {},
+    SwapRoutes {},
+    Strategies {},
+    AssetBreakdown {},
+    PendingRewards {
+        address: String,
+    },
+    Streams {},
+    PendingStreamRewards {
+        address: String,
+    },
+    BoostInfo {
+        address: String,
+    },
+    Referral {
+        referrer: String,
+    },
+    /// Outgoing redirect of `address` and the interest it received
+    InterestRedirect {
+        address: String,
+    },
+    /// Donors redirecting interest to `beneficiary`
+    IncomingRedirects {
+        beneficiary: String,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    /// Prize draw `draw_id`, the current one if unset
+    Draw {
+        draw_id: Option<u64>,
+    },
+    Prize {
+        address: String,
+    },
+    Operators {},
+    /// Principal and yield tokens of `maturity`
+    Maturity {
+        maturity: u64,
+    },
+    Maturities {
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+    /// Supply and exchange rates of the senior and junior tranches
+    Tranches {},
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
@@ -171,16 +1479,319 @@ pub enum QueryMsg {
 pub enum Cw20HookMsg {
     /// Return stable coins to a user
     /// according to exchange rate
-    RedeemNStable {},
-    RedeemAllStable {},
+    /// Funds are paid to `to` (the cw20 sender if unset), when `msg`
+    /// is set `to` is executed with `msg` and the funds attached
+    RedeemNStable {
+        to: Option<String>,
+        msg: Option<Binary>,
+    },
+    RedeemAllStable {
+        to: Option<String>,
+        msg: Option<Binary>,
+    },
+    /// Fund a reward stream with the sent cw20 tokens
+    CreateStream { start_time: u64, end_time: u64 },
+    /// Lock the sent governance tokens for `duration` seconds from now,
+    /// keeping a later existing lock end
+    Lock { duration: u64 },
+    /// Split the sent receipt tokens into principal and yield tokens
+    /// of `maturity`, one of each per stable coin of their value
+    Split { maturity: u64 },
+    /// Escrow the sent principal or yield tokens of `maturity` before it,
+    /// burning them with as many escrowed tokens of the other kind for the
+    /// receipt tokens backing them
+    Recombine { maturity: u64 },
+    /// Redeem the sent principal tokens for receipt tokens after maturity
+    RedeemPrincipal { maturity: u64 },
+    /// Redeem the sent yield tokens for the interest earned until maturity
+    RedeemYield { maturity: u64 },
+    /// Redeem the sent junior tokens for stable coins paid to `to`
+    /// (the cw20 sender if unset)
+    RedeemJunior { to: Option<String> },
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
+#[allow(clippy::large_enum_variant)]
 pub enum ExecuteMsg {
     Receive(Cw20ReceiveMsg),
 
-    DepositStable {},
-    ClaimRewards { to: Option<String> },
-}
+    /// Deposit stable coins, crediting the position and receipt
+    /// tokens to `recipient` or to the sender if unset.
+    /// The first `referrer` of a position is kept for good,
+    /// it is ignored on deposits made for another recipient.
+    DepositStable {
+        recipient: Option<String>,
+        referrer: Option<String>,
+    },
+    ClaimRewards {
+        to: Option<String>,
+    },
+
+    ////////////////////
+    /// Owner operations
+    ////////////////////
+    /// Update deposit config values, a zero cap removes the cap
+    UpdateConfig {
+        owner_addr: Option<String>,
+        tvl_cap: Option<Uint128>,
+        max_balance: Option<Uint128>,
+        min_deposit: Option<Uint128>,
+    },
+    /// Update permissioned mode, an empty membership contract removes it
+    UpdatePermissionConfig {
+        permissioned: Option<bool>,
+        membership_contract: Option<String>,
+    },
+    /// Update unbonding and native staking config values
+    UpdateStakingConfig {
+        unbonding_period: Option<u64>,
+        chain_unbonding_period: Option<u64>,
+        harvest_bounty: Option<Decimal>,
+        max_rebalance: Option<Uint128>,
+    },
+    /// Update strategy allocation and epoch operation config values
+    UpdateEpochConfig {
+        liquidity_buffer: Option<Decimal>,
+        epoch_period: Option<u64>,
+        epoch_bounty: Option<Uint128>,
+    },
+    /// Update reward emission config values,
+    /// an empty reward token removes it
+    UpdateRewardConfig {
+        reward_token: Option<String>,
+        emission_rate: Option<Decimal>,
+    },
+    /// Update the distribution model adjusting the emission rate,
+    /// an empty distribution model removes it
+    UpdateDistributionConfig {
+        distribution_model: Option<String>,
+        target_deposit_rate: Option<Decimal>,
+        threshold_deposit_rate: Option<Decimal>,
+    },
+    /// Update boost config values. Working balances keep their boost
+    /// until each depositor's next interaction or `Kick`.
+    UpdateBoostConfig {
+        governance_token: Option<String>,
+        max_lock_duration: Option<u64>,
+        max_boost: Option<Decimal>,
+        boost_ratio: Option<Decimal>,
+    },
+    /// Update the share of interest paid to referrers
+    UpdateReferralConfig {
+        referral_share: Decimal,
+    },
+
+    /// Allow addresses to deposit in permissioned mode
+    AddToAllowlist {
+        addresses: Vec<String>,
+    },
+    /// Revoke deposit permission, existing positions stay redeemable
+    RemoveFromAllowlist {
+        addresses: Vec<String>,
+    },
+    /// Allow addresses besides the owner to create reward streams
+    AddSponsors {
+        addresses: Vec<String>,
+    },
+    /// Revoke stream creation, open streams keep emitting
+    RemoveSponsors {
+        addresses: Vec<String>,
+    },
 
+    /// Add a validator deposits are delegated to in native staking mode,
+    /// `weight` defaults to 1
+    RegisterValidator {
+        validator: String,
+        weight: Option<u64>,
+    },
+    /// Remove a validator, redelegating its stake to the others
+    RemoveValidator {
+        validator: String,
+    },
+    /// Change the target weight of a validator, applied by `Rebalance`
+    UpdateValidatorWeight {
+        validator: String,
+        weight: u64,
+    },
+
+    /// Swap `denom` into `stable_denom` through a terraswap pair,
+    /// failing when the return falls more than `max_spread` below
+    /// the price of `oracle_addr`
+    SetSwapRoute {
+        denom: String,
+        pair_addr: String,
+        max_spread: Decimal,
+        oracle_addr: String,
+    },
+    RemoveSwapRoute {
+        denom: String,
+    },
+    /// Register a yield strategy receiving a `weight` share of
+    /// allocated liquidity
+    AddStrategy {
+        contract_addr: String,
+        kind: StrategyKind,
+        weight: u64,
+    },
+    /// Remove a strategy whose position has been withdrawn
+    RemoveStrategy {
+        contract_addr: String,
+    },
+    UpdateStrategyWeight {
+        contract_addr: String,
+        weight: u64,
+    },
+    /// Withdraw `amount` stable coins from a strategy,
+    /// the whole position if unset
+    WithdrawStrategy {
+        contract_addr: String,
+        amount: Option<Uint128>,
+    },
+
+    /// Allow an address to provide randomness for prize draws
+    RegisterOperator {
+        operator: String,
+    },
+    RemoveOperator {
+        operator: String,
+    },
+    /// List a maturity to split receipt tokens for,
+    /// instantiating its tokens from `token_code_id`
+    AddMaturity {
+        maturity: u64,
+        token_code_id: u64,
+    },
+    /// Instantiate the junior tranche token from `token_code_id`
+    EnableJuniorTranche {
+        token_code_id: u64,
+    },
+
+    ////////////////////
+    /// Keeper operations
+    ////////////////////
+    /// Withdraw staking rewards and delegate them again, raising the
+    /// exchange rate and paying the caller `harvest_bounty`. Rewards in
+    /// other denoms are swapped first when they have a swap route,
+    /// otherwise they stay in the contract.
+    Harvest {},
+    /// Redelegate toward the validators' target weights,
+    /// moving at most `max_rebalance`
+    Rebalance {},
+    /// Compare tracked delegations with the actual ones and socialize
+    /// slashed stake, covering it from the reserve before lowering
+    /// the exchange rate
+    Reconcile {},
+    /// Swap the contract's `denom` balance into `stable_denom`
+    /// and add it to the reserve
+    Sweep {
+        denom: String,
+    },
+    /// Move strategy yield above the fixed interest rate into the reserve
+    SkimYield {},
+    /// Allocate liquidity above the buffer to the strategies by weight
+    Allocate {},
+    /// Snapshot the TVL, reconcile, harvest staking rewards and undelegate
+    /// redeemed stake or skim and allocate strategy yield, at most once per
+    /// `epoch_period`.
+    /// Pays the caller `epoch_bounty` from the reserve, no harvest bounty.
+    ExecuteEpochOperations {},
+    /// Accrue interest for `addresses` and mint it as receipt tokens,
+    /// paying out the interest of depositors with a payout address
+    Settle {
+        addresses: Vec<String>,
+    },
+    /// Settle a page of depositors in address order
+    SettleRange {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    /// Fund a reward stream with the single native coin sent along,
+    /// emitted to depositors between `start_time` and `end_time`.
+    /// Only the owner and sponsors may create streams.
+    CreateStream {
+        start_time: u64,
+        end_time: u64,
+    },
+    /// Remove a stream after its claim period,
+    /// refunding the unclaimed budget to its sponsor
+    CloseStream {
+        id: u64,
+    },
+    /// Claim rewards of the streams in `ids`, all streams if unset
+    ClaimStreamRewards {
+        ids: Option<Vec<u64>>,
+        to: Option<String>,
+    },
+    /// Commit to a secret for a draw before it ends,
+    /// `commitment` being the sha256 hash of the secret
+    CommitRandomness {
+        draw_id: u64,
+        commitment: Binary,
+    },
+    /// Reveal the committed secret within `reveal_period` after the draw
+    RevealRandomness {
+        draw_id: u64,
+        secret: Binary,
+    },
+    /// Seed a finished draw from the revealed secrets and
+    /// pick its winning positions. The prize pools the interest of
+    /// earlier draws never drawn; it rolls over when a committed
+    /// secret was not revealed, deregistering the defaulting operators.
+    DrawPrizes {
+        draw_id: u64,
+    },
+    /// Walk up to `limit` depositors to find the draw's winners
+    AwardPrizes {
+        draw_id: u64,
+        limit: Option<u32>,
+    },
+    /// Recalculate the working balance of `address`,
+    /// applying the decay of its boost
+    Kick {
+        address: String,
+    },
+
+    ////////////////////
+    /// User operations
+    ////////////////////
+    /// Top up the reserve with stable coins, filling queued withdrawals
+    FundReserve {},
+    /// Withdraw stable coins filled from the withdrawal queue
+    ClaimQueued {},
+    /// Withdraw redemptions past their unbonding period to their recipients
+    ClaimUnbonded {},
+    /// Move the lock end to `duration` seconds from now
+    ExtendLock {
+        duration: u64,
+    },
+    /// Withdraw governance tokens of an expired lock
+    Unlock {},
+    /// Withdraw stable coins earned by referring depositors,
+    /// queued when liquidity is short
+    ClaimReferralRewards {},
+    /// Pay interest to `to` when settled instead of compounding it,
+    /// compound again if unset
+    SetInterestPayout {
+        to: Option<String>,
+    },
+    /// Redirect `share` (all if unset) of future interest to `beneficiary`,
+    /// keeping the principal. Removes the redirect if `beneficiary` is unset.
+    SetInterestRedirect {
+        beneficiary: Option<String>,
+        share: Option<Decimal>,
+    },
+    /// Withdraw interest redirected to the sender,
+    /// queued when liquidity is short
+    ClaimRedirectedInterest {},
+    /// Withdraw prizes won in prize mode, queued when liquidity is short
+    ClaimPrize {},
+    /// Return the principal and yield tokens of `maturity` escrowed by the
+    /// sender to recombine
+    CancelRecombine {
+        maturity: u64,
+    },
+    /// Deposit stable coins into the junior tranche, earning the yield left
+    /// after the senior interest and absorbing losses first
+    DepositJunior {},
+}
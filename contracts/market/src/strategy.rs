@@ -0,0 +1,527 @@
+use std::cmp::{min, Reverse};
+
+use cosmwasm_bignumber::{Decimal256, Uint256};
+use cosmwasm_std::{
+    attr, to_binary, Addr, Api, Coin, CosmosMsg, Deps, DepsMut, Env, MessageInfo, Response,
+    StdResult, Uint128, WasmMsg,
+};
+use cw20::{BalanceResponse, Cw20ExecuteMsg, Cw20QueryMsg};
+use moneymarket::market::{
+    ConfigResponse as MarketConfigResponse, Cw20HookMsg as MarketHookMsg, EpochStateResponse,
+    ExecuteMsg as MarketExecuteMsg, QueryMsg as MarketQueryMsg,
+};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::error::ContractError;
+use crate::queue::query_liquidity;
+use crate::slashing::socialize_loss;
+use crate::state::{
+    read_config, read_state, read_strategies, read_strategy, remove_strategy as remove,
+    store_state, store_strategy, AssetBreakdownResponse, Config, State, StrategiesResponse,
+    StrategyAssetResponse, StrategyInfo, StrategyKind, StrategyResponse,
+};
+use crate::tranche::{senior_interest, waterfall};
+
+/// Maximum number of registered strategies
+const MAX_STRATEGIES: usize = 10;
+
+/// Interface of generic strategy contracts. `Deposit` receives stable coins
+/// as funds, `Withdraw` sends `amount` stable coins back to the caller.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum StrategyExecuteMsg {
+    Deposit {},
+    Withdraw { amount: Uint128 },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum StrategyQueryMsg {
+    /// Stable coins `address` could withdraw from the strategy
+    TotalValue { address: String },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct TotalValueResponse {
+    pub value: Uint128,
+}
+
+pub fn add_strategy(
+    deps: DepsMut,
+    info: MessageInfo,
+    contract_addr: String,
+    kind: StrategyKind,
+    weight: u64,
+) -> Result<Response, ContractError> {
+    let config: Config = read_config(deps.storage)?;
+    if deps.api.addr_canonicalize(info.sender.as_str())? != config.owner_addr {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    if config.native_staking {
+        return Err(ContractError::StrategyWithNativeStaking {});
+    }
+
+    let contract = deps.api.addr_validate(&contract_addr)?;
+    let contract_raw = deps.api.addr_canonicalize(contract.as_str())?;
+    if read_strategy(deps.storage, &contract_raw)?.is_some() {
+        return Err(ContractError::StrategyExists(contract_addr));
+    }
+
+    if read_strategies(deps.storage)?.len() >= MAX_STRATEGIES {
+        return Err(ContractError::TooManyStrategies {});
+    }
+
+    let aterra_contract = match kind {
+        StrategyKind::Anchor => {
+            let market_config: MarketConfigResponse = deps
+                .querier
+                .query_wasm_smart(contract.as_str(), &MarketQueryMsg::Config {})?;
+            if market_config.stable_denom != config.stable_denom {
+                return Err(ContractError::InvalidStrategy(contract_addr));
+            }
+            Some(deps.api.addr_canonicalize(&market_config.aterra_contract)?)
+        }
+        StrategyKind::Generic => None,
+    };
+
+    store_strategy(
+        deps.storage,
+        &StrategyInfo {
+            contract_addr: contract_raw,
+            kind,
+            aterra_contract,
+            weight,
+            principal: Uint128::zero(),
+        },
+    )?;
+
+    Ok(Response::new().add_attributes(vec![
+        attr("action", "add_strategy"),
+        attr("strategy", contract),
+        attr("weight", weight.to_string()),
+    ]))
+}
+
+/// Removes a strategy once its position has been withdrawn
+pub fn remove_strategy(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    contract_addr: String,
+) -> Result<Response, ContractError> {
+    let config: Config = read_config(deps.storage)?;
+    if deps.api.addr_canonicalize(info.sender.as_str())? != config.owner_addr {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let contract_raw = deps.api.addr_canonicalize(&contract_addr)?;
+    let strategy = read_strategy(deps.storage, &contract_raw)?
+        .ok_or_else(|| ContractError::StrategyNotFound(contract_addr.clone()))?;
+    if !query_strategy_value(deps.as_ref(), &env, &strategy)?.is_zero() {
+        return Err(ContractError::StrategyPositionOpen(contract_addr));
+    }
+    remove(deps.storage, &contract_raw);
+
+    Ok(Response::new().add_attributes(vec![
+        attr("action", "remove_strategy"),
+        attr("strategy", contract_addr),
+    ]))
+}
+
+pub fn update_strategy_weight(
+    deps: DepsMut,
+    info: MessageInfo,
+    contract_addr: String,
+    weight: u64,
+) -> Result<Response, ContractError> {
+    let config: Config = read_config(deps.storage)?;
+    if deps.api.addr_canonicalize(info.sender.as_str())? != config.owner_addr {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let contract_raw = deps.api.addr_canonicalize(&contract_addr)?;
+    let mut strategy = read_strategy(deps.storage, &contract_raw)?
+        .ok_or_else(|| ContractError::StrategyNotFound(contract_addr.clone()))?;
+    strategy.weight = weight;
+    store_strategy(deps.storage, &strategy)?;
+
+    Ok(Response::new().add_attributes(vec![
+        attr("action", "update_strategy_weight"),
+        attr("strategy", contract_addr),
+        attr("weight", weight.to_string()),
+    ]))
+}
+
+/// Withdraws `amount` stable coins from a strategy, its whole value if unset
+pub fn withdraw_strategy(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    contract_addr: String,
+    amount: Option<Uint128>,
+) -> Result<Response, ContractError> {
+    let config: Config = read_config(deps.storage)?;
+    if deps.api.addr_canonicalize(info.sender.as_str())? != config.owner_addr {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let contract_raw = deps.api.addr_canonicalize(&contract_addr)?;
+    let mut strategy = read_strategy(deps.storage, &contract_raw)?
+        .ok_or_else(|| ContractError::StrategyNotFound(contract_addr.clone()))?;
+    let value = query_strategy_value(deps.as_ref(), &env, &strategy)?;
+    let amount = min(amount.unwrap_or(value), value);
+    if amount.is_zero() {
+        return Err(ContractError::NothingToClaim {});
+    }
+
+    let (msg, withdrawn) = strategy_withdraw_msg(deps.as_ref(), &env, &strategy, amount)?;
+    strategy.principal = strategy.principal.saturating_sub(withdrawn);
+    store_strategy(deps.storage, &strategy)?;
+
+    Ok(Response::new().add_message(msg).add_attributes(vec![
+        attr("action", "withdraw_strategy"),
+        attr("strategy", contract_addr),
+        attr("withdrawn", withdrawn),
+    ]))
+}
+
+/// Puts liquidity above the buffer to work in the strategies
+pub fn allocate(deps: DepsMut, env: Env) -> Result<Response, ContractError> {
+    let config: Config = read_config(deps.storage)?;
+    let (msgs, allocated) = allocate_msgs(deps, &env, &config, Uint128::zero())?;
+
+    Ok(Response::new().add_messages(msgs).add_attributes(vec![
+        attr("action", "allocate"),
+        attr("allocated", allocated),
+    ]))
+}
+
+/// Deposits the liquidity above `liquidity_buffer` of total assets into the
+/// strategies furthest below their weighted target, keeping `holdback` for
+/// payments made after the deposits. Returns the deposit messages and the
+/// amount deposited.
+pub fn allocate_msgs(
+    deps: DepsMut,
+    env: &Env,
+    config: &Config,
+    holdback: Uint128,
+) -> Result<(Vec<CosmosMsg>, Uint128), ContractError> {
+    let mut strategies = read_strategies(deps.storage)?;
+    if strategies.is_empty() {
+        return Ok((vec![], Uint128::zero()));
+    }
+
+    let values = strategies
+        .iter()
+        .map(|s| query_strategy_value(deps.as_ref(), env, s))
+        .collect::<StdResult<Vec<Uint128>>>()?;
+    let state: State = read_state(deps.storage)?;
+    let liquidity = query_liquidity(deps.as_ref(), env, config, &state)?.saturating_sub(holdback);
+    let total = liquidity + values.iter().copied().sum::<Uint128>();
+    let buffer = total * config.liquidity_buffer;
+    let targets = strategy_targets(&strategies, total - buffer);
+
+    let mut order: Vec<usize> = (0..strategies.len()).collect();
+    order.sort_by_key(|i| Reverse(targets[*i].saturating_sub(values[*i])));
+
+    let mut msgs: Vec<CosmosMsg> = vec![];
+    let mut available = liquidity.saturating_sub(buffer);
+    let mut allocated = Uint128::zero();
+    for i in order.into_iter() {
+        let amount = min(targets[i].saturating_sub(values[i]), available);
+        if amount.is_zero() {
+            continue;
+        }
+
+        let strategy = &mut strategies[i];
+        strategy.principal += amount;
+        store_strategy(deps.storage, strategy)?;
+        msgs.push(strategy_deposit_msg(deps.api, config, strategy, amount)?);
+        available -= amount;
+        allocated += amount;
+    }
+
+    Ok((msgs, allocated))
+}
+
+/// Withdraws up to `amount` from the strategies holding the most value.
+/// Returns the withdraw messages and the stable coins they release,
+/// which arrive before any message appended after them.
+pub fn strategy_withdraw_msgs(
+    deps: DepsMut,
+    env: &Env,
+    amount: Uint128,
+) -> Result<(Vec<CosmosMsg>, Uint128), ContractError> {
+    let mut strategies = read_strategies(deps.storage)?
+        .into_iter()
+        .map(|s| Ok((query_strategy_value(deps.as_ref(), env, &s)?, s)))
+        .collect::<StdResult<Vec<(Uint128, StrategyInfo)>>>()?;
+    strategies.sort_by_key(|(value, _)| Reverse(*value));
+
+    let mut msgs: Vec<CosmosMsg> = vec![];
+    let mut withdrawn = Uint128::zero();
+    for (value, mut strategy) in strategies.into_iter() {
+        let remaining = amount.saturating_sub(withdrawn);
+        if remaining.is_zero() {
+            break;
+        }
+
+        let take = min(remaining, value);
+        if take.is_zero() {
+            continue;
+        }
+
+        let (msg, released) = strategy_withdraw_msg(deps.as_ref(), env, &strategy, take)?;
+        strategy.principal = strategy.principal.saturating_sub(released);
+        store_strategy(deps.storage, &strategy)?;
+        msgs.push(msg);
+        withdrawn += released;
+    }
+
+    Ok((msgs, withdrawn))
+}
+
+/// Moves strategy yield earned above the fixed interest paid to
+/// depositors into the junior tranche, or withdraws it into the reserve
+/// without one. Strategy losses are taken from the junior tranche.
+pub fn skim_yield(mut deps: DepsMut, env: Env) -> Result<Response, ContractError> {
+    let mut earned = Uint128::zero();
+    let mut lost = Uint128::zero();
+    for mut strategy in read_strategies(deps.storage)?.into_iter() {
+        let value = query_strategy_value(deps.as_ref(), &env, &strategy)?;
+        earned += value.saturating_sub(strategy.principal);
+        lost += strategy.principal.saturating_sub(value);
+        strategy.principal = value;
+        store_strategy(deps.storage, &strategy)?;
+    }
+
+    let config: Config = read_config(deps.storage)?;
+    let mut state: State = read_state(deps.storage)?;
+    let interest = senior_interest(&config, &mut state, env.block.time.seconds());
+    let spread = waterfall(&mut state, earned, interest);
+    let (absorbed, covered, socialized) = socialize_loss(&mut state, lost);
+    store_state(deps.storage, &state)?;
+
+    // the reserve only holds liquid funds
+    let (msgs, withdrawn) = if spread.is_zero() {
+        (vec![], Uint128::zero())
+    } else {
+        strategy_withdraw_msgs(deps.branch(), &env, spread)?
+    };
+    let mut state: State = read_state(deps.storage)?;
+    state.reserve += min(spread, withdrawn);
+    store_state(deps.storage, &state)?;
+
+    Ok(Response::new().add_messages(msgs).add_attributes(vec![
+        attr("action", "skim_yield"),
+        attr("earned", earned),
+        attr("lost", lost),
+        attr("interest", interest),
+        attr("spread", spread),
+        attr("absorbed", absorbed),
+        attr("covered", covered),
+        attr("socialized", socialized),
+        attr("exchange_rate", state.exchange_rate.to_string()),
+        attr(
+            "junior_exchange_rate",
+            state.junior_exchange_rate.to_string(),
+        ),
+    ]))
+}
+
+/// Share of `total` each strategy should hold according to its weight
+fn strategy_targets(strategies: &[StrategyInfo], total: Uint128) -> Vec<Uint128> {
+    let total_weight: u64 = strategies.iter().map(|s| s.weight).sum();
+    strategies
+        .iter()
+        .map(|s| {
+            if total_weight == 0 {
+                Uint128::zero()
+            } else {
+                total.multiply_ratio(s.weight, total_weight)
+            }
+        })
+        .collect()
+}
+
+fn strategy_deposit_msg(
+    api: &dyn Api,
+    config: &Config,
+    strategy: &StrategyInfo,
+    amount: Uint128,
+) -> StdResult<CosmosMsg> {
+    let msg = match strategy.kind {
+        StrategyKind::Anchor => to_binary(&MarketExecuteMsg::DepositStable {})?,
+        StrategyKind::Generic => to_binary(&StrategyExecuteMsg::Deposit {})?,
+    };
+
+    Ok(CosmosMsg::Wasm(WasmMsg::Execute {
+        contract_addr: api.addr_humanize(&strategy.contract_addr)?.to_string(),
+        funds: vec![Coin {
+            denom: config.stable_denom.clone(),
+            amount,
+        }],
+        msg,
+    }))
+}
+
+/// Builds a withdrawal of `amount` stable coins, returning the message and
+/// the stable coins it releases
+fn strategy_withdraw_msg(
+    deps: Deps,
+    env: &Env,
+    strategy: &StrategyInfo,
+    amount: Uint128,
+) -> StdResult<(CosmosMsg, Uint128)> {
+    let contract_addr = deps.api.addr_humanize(&strategy.contract_addr)?;
+    match strategy.kind {
+        StrategyKind::Anchor => {
+            let (aterra, balance, exchange_rate) = anchor_position(deps, env, strategy)?;
+
+            // round up so the redemption covers `amount`
+            let needed = Uint256::from(amount) / exchange_rate + Uint256::one();
+            let redeem_amount: Uint128 = min(needed, Uint256::from(balance)).into();
+            let redeemed: Uint128 = (Uint256::from(redeem_amount) * exchange_rate).into();
+
+            Ok((
+                CosmosMsg::Wasm(WasmMsg::Execute {
+                    contract_addr: aterra.to_string(),
+                    funds: vec![],
+                    msg: to_binary(&Cw20ExecuteMsg::Send {
+                        contract: contract_addr.to_string(),
+                        amount: redeem_amount,
+                        msg: to_binary(&MarketHookMsg::RedeemStable {})?,
+                    })?,
+                }),
+                redeemed,
+            ))
+        }
+        StrategyKind::Generic => Ok((
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: contract_addr.to_string(),
+                funds: vec![],
+                msg: to_binary(&StrategyExecuteMsg::Withdraw { amount })?,
+            }),
+            amount,
+        )),
+    }
+}
+
+/// aTerra token, balance held by the contract and the market's
+/// current exchange rate of an Anchor strategy
+fn anchor_position(
+    deps: Deps,
+    env: &Env,
+    strategy: &StrategyInfo,
+) -> StdResult<(Addr, Uint128, Decimal256)> {
+    let market = deps.api.addr_humanize(&strategy.contract_addr)?;
+    let aterra = match &strategy.aterra_contract {
+        Some(aterra) => deps.api.addr_humanize(aterra)?,
+        None => return Ok((market, Uint128::zero(), Decimal256::one())),
+    };
+
+    let balance: BalanceResponse = deps.querier.query_wasm_smart(
+        aterra.as_str(),
+        &Cw20QueryMsg::Balance {
+            address: env.contract.address.to_string(),
+        },
+    )?;
+    let epoch_state: EpochStateResponse = deps.querier.query_wasm_smart(
+        market.as_str(),
+        &MarketQueryMsg::EpochState {
+            block_height: Some(env.block.height),
+            distributed_interest: None,
+        },
+    )?;
+
+    Ok((aterra, balance.balance, epoch_state.exchange_rate))
+}
+
+/// Stable coins the contract could withdraw from `strategy`
+pub fn query_strategy_value(deps: Deps, env: &Env, strategy: &StrategyInfo) -> StdResult<Uint128> {
+    match strategy.kind {
+        StrategyKind::Anchor => {
+            let (_, balance, exchange_rate) = anchor_position(deps, env, strategy)?;
+            Ok((Uint256::from(balance) * exchange_rate).into())
+        }
+        StrategyKind::Generic => {
+            let res: TotalValueResponse = deps.querier.query_wasm_smart(
+                deps.api.addr_humanize(&strategy.contract_addr)?.as_str(),
+                &StrategyQueryMsg::TotalValue {
+                    address: env.contract.address.to_string(),
+                },
+            )?;
+            Ok(res.value)
+        }
+    }
+}
+
+/// Stable coins held across all strategies
+pub fn query_strategies_value(deps: Deps, env: &Env) -> StdResult<Uint128> {
+    read_strategies(deps.storage)?
+        .iter()
+        .map(|s| query_strategy_value(deps, env, s))
+        .sum()
+}
+
+pub fn query_strategies(deps: Deps, env: Env) -> StdResult<StrategiesResponse> {
+    let config: Config = read_config(deps.storage)?;
+    let state: State = read_state(deps.storage)?;
+    let strategies = read_strategies(deps.storage)?;
+    let values = strategies
+        .iter()
+        .map(|s| query_strategy_value(deps, &env, s))
+        .collect::<StdResult<Vec<Uint128>>>()?;
+    let liquidity = query_liquidity(deps, &env, &config, &state)?;
+    let total = liquidity + values.iter().copied().sum::<Uint128>();
+    let targets = strategy_targets(&strategies, total - total * config.liquidity_buffer);
+
+    let strategies = strategies
+        .into_iter()
+        .zip(values)
+        .zip(targets)
+        .map(|((s, value), target)| {
+            Ok(StrategyResponse {
+                contract_addr: deps.api.addr_humanize(&s.contract_addr)?.to_string(),
+                kind: s.kind,
+                weight: s.weight,
+                target,
+                principal: s.principal,
+                value,
+            })
+        })
+        .collect::<StdResult<Vec<StrategyResponse>>>()?;
+
+    Ok(StrategiesResponse { strategies })
+}
+
+pub fn query_asset_breakdown(deps: Deps, env: Env) -> StdResult<AssetBreakdownResponse> {
+    let config: Config = read_config(deps.storage)?;
+    let state: State = read_state(deps.storage)?;
+    let liquid = query_liquidity(deps, &env, &config, &state)?;
+
+    let strategies = read_strategies(deps.storage)?
+        .iter()
+        .map(|s| {
+            Ok(StrategyAssetResponse {
+                contract_addr: deps.api.addr_humanize(&s.contract_addr)?.to_string(),
+                value: query_strategy_value(deps, &env, s)?,
+            })
+        })
+        .collect::<StdResult<Vec<StrategyAssetResponse>>>()?;
+    let total = liquid
+        + state.total_claimable
+        + state.total_delegated
+        + strategies.iter().map(|s| s.value).sum::<Uint128>();
+
+    Ok(AssetBreakdownResponse {
+        liquid,
+        claimable: state.total_claimable,
+        delegated: state.total_delegated,
+        strategies,
+        total,
+        buffer_target: total * config.liquidity_buffer,
+    })
+}
@@ -0,0 +1,342 @@
+use std::cmp::{max, min};
+
+use cosmwasm_std::{
+    attr, to_binary, Addr, Api, BankMsg, CanonicalAddr, Coin, CosmosMsg, Decimal, Deps, DepsMut,
+    Env, MessageInfo, Response, StdResult, Storage, Uint128, WasmMsg,
+};
+use cw20::Cw20ExecuteMsg;
+use terraswap::asset::{Asset, AssetInfo};
+
+use crate::error::ContractError;
+use crate::state::{
+    is_sponsor, read_config, read_deposit_info, read_state, read_stream, read_stream_reward,
+    read_streams, remove_sponsor, remove_stream, store_sponsor, store_state, store_stream,
+    store_stream_reward, Config, PendingStreamRewardResponse, PendingStreamRewardsResponse,
+    RewardStream, State, StreamResponse, StreamsResponse,
+};
+
+/// Maximum number of streams open at the same time
+const MAX_STREAMS: usize = 10;
+/// Seconds after a stream ends during which its rewards can be claimed
+const STREAM_CLAIM_PERIOD: u64 = 30 * 86400;
+/// Latest end of a new stream, in seconds from now
+const MAX_STREAM_END: u64 = 2 * 365 * 86400;
+
+pub fn add_sponsors(
+    deps: DepsMut,
+    info: MessageInfo,
+    addresses: Vec<String>,
+) -> Result<Response, ContractError> {
+    let config: Config = read_config(deps.storage)?;
+    if deps.api.addr_canonicalize(info.sender.as_str())? != config.owner_addr {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    for address in addresses.iter() {
+        store_sponsor(deps.storage, &deps.api.addr_canonicalize(address)?)?;
+    }
+
+    Ok(Response::new().add_attributes(vec![
+        attr("action", "add_sponsors"),
+        attr("addresses", addresses.join(",")),
+    ]))
+}
+
+pub fn remove_sponsors(
+    deps: DepsMut,
+    info: MessageInfo,
+    addresses: Vec<String>,
+) -> Result<Response, ContractError> {
+    let config: Config = read_config(deps.storage)?;
+    if deps.api.addr_canonicalize(info.sender.as_str())? != config.owner_addr {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    for address in addresses.iter() {
+        remove_sponsor(deps.storage, &deps.api.addr_canonicalize(address)?);
+    }
+
+    Ok(Response::new().add_attributes(vec![
+        attr("action", "remove_sponsors"),
+        attr("addresses", addresses.join(",")),
+    ]))
+}
+
+/// Opens a reward stream funded with `asset`, emitted evenly
+/// between `start_time` and `end_time`. Only the owner and sponsors
+/// may take one of the limited stream slots.
+pub fn create_stream(
+    deps: DepsMut,
+    env: Env,
+    sponsor: Addr,
+    asset: Asset,
+    start_time: u64,
+    end_time: u64,
+) -> Result<Response, ContractError> {
+    let config: Config = read_config(deps.storage)?;
+    let sponsor_raw = deps.api.addr_canonicalize(sponsor.as_str())?;
+    if sponsor_raw != config.owner_addr && !is_sponsor(deps.storage, &sponsor_raw) {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let time = env.block.time.seconds();
+    if asset.amount.is_zero()
+        || start_time < time
+        || end_time <= start_time
+        || end_time > time + MAX_STREAM_END
+    {
+        return Err(ContractError::InvalidStream(asset.info.to_string()));
+    }
+
+    if read_streams(deps.storage)?.len() >= MAX_STREAMS {
+        return Err(ContractError::TooManyStreams {});
+    }
+
+    let mut state: State = read_state(deps.storage)?;
+    let id = state.next_stream_id;
+    state.next_stream_id += 1;
+    store_state(deps.storage, &state)?;
+
+    store_stream(
+        deps.storage,
+        &RewardStream {
+            id,
+            sponsor: sponsor_raw,
+            asset: asset.info.clone(),
+            budget: asset.amount,
+            start_time,
+            end_time,
+            global_index: Decimal::zero(),
+            last_updated: start_time,
+            claimed: Uint128::zero(),
+        },
+    )?;
+
+    Ok(Response::new().add_attributes(vec![
+        attr("action", "create_stream"),
+        attr("stream_id", id.to_string()),
+        attr("sponsor", sponsor),
+        attr("asset", asset.info.to_string()),
+        attr("budget", asset.amount),
+    ]))
+}
+
+/// Funds a native stream with the coins sent along
+pub fn create_native_stream(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    start_time: u64,
+    end_time: u64,
+) -> Result<Response, ContractError> {
+    if info.funds.len() != 1 {
+        return Err(ContractError::InvalidStream(format!("{:?}", info.funds)));
+    }
+
+    let asset = Asset {
+        info: AssetInfo::NativeToken {
+            denom: info.funds[0].denom.clone(),
+        },
+        amount: info.funds[0].amount,
+    };
+    create_stream(deps, env, info.sender, asset, start_time, end_time)
+}
+
+/// Removes an ended stream once its claim period has passed, returning
+/// the unclaimed budget to the sponsor
+pub fn close_stream(deps: DepsMut, env: Env, id: u64) -> Result<Response, ContractError> {
+    let stream = read_stream(deps.storage, id)?.ok_or(ContractError::StreamNotFound(id))?;
+    let claim_end = stream
+        .end_time
+        .checked_add(STREAM_CLAIM_PERIOD)
+        .ok_or(ContractError::StreamActive(id))?;
+    if env.block.time.seconds() < claim_end {
+        return Err(ContractError::StreamActive(id));
+    }
+    remove_stream(deps.storage, id);
+
+    let refund = stream.budget - stream.claimed;
+    let sponsor = deps.api.addr_humanize(&stream.sponsor)?;
+    let mut msgs: Vec<CosmosMsg> = vec![];
+    if !refund.is_zero() {
+        msgs.push(asset_transfer_msg(&stream.asset, &sponsor, refund)?);
+    }
+
+    Ok(Response::new().add_messages(msgs).add_attributes(vec![
+        attr("action", "close_stream"),
+        attr("stream_id", id.to_string()),
+        attr("refund", refund),
+    ]))
+}
+
+/// Raises the stream's index by the budget emitted since its last update,
+/// shared pro-rata over `tvl`
+fn update_stream_index(stream: &mut RewardStream, tvl: Uint128, time: u64) {
+    let from = max(stream.last_updated, stream.start_time);
+    let to = min(time, stream.end_time);
+    if to <= from {
+        return;
+    }
+
+    let emitted = stream
+        .budget
+        .multiply_ratio(to - from, stream.end_time - stream.start_time);
+    if !tvl.is_zero() && !emitted.is_zero() {
+        stream.global_index = stream.global_index + Decimal::from_ratio(emitted, tvl);
+    }
+    stream.last_updated = to;
+}
+
+fn earned(balance: Uint128, global_index: Decimal, index: Decimal) -> Uint128 {
+    if global_index <= index {
+        return Uint128::zero();
+    }
+    balance * (global_index - index)
+}
+
+/// Moves stream rewards earned by `balance` into the depositor's pending
+/// rewards. Must run before the depositor's balance changes.
+pub fn accrue_stream_rewards(
+    storage: &mut dyn Storage,
+    ident: &CanonicalAddr,
+    balance: Uint128,
+    time: u64,
+) -> StdResult<()> {
+    let tvl = read_state(storage)?.tvl;
+    for mut stream in read_streams(storage)?.into_iter() {
+        update_stream_index(&mut stream, tvl, time);
+        store_stream(storage, &stream)?;
+
+        let mut reward = read_stream_reward(storage, stream.id, ident);
+        reward.pending += earned(balance, stream.global_index, reward.index);
+        reward.index = stream.global_index;
+        store_stream_reward(storage, stream.id, ident, &reward)?;
+    }
+    Ok(())
+}
+
+/// Pays pending rewards of the streams in `ids`, all streams if unset
+pub fn claim_stream_rewards(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    ids: Option<Vec<u64>>,
+    to: Option<String>,
+) -> Result<Response, ContractError> {
+    let recipient = match to {
+        Some(to) => deps.api.addr_validate(&to)?,
+        None => info.sender.clone(),
+    };
+    let sender_raw = deps.api.addr_canonicalize(info.sender.as_str())?;
+    let deposit = read_deposit_info(deps.storage, &sender_raw)?;
+    accrue_stream_rewards(
+        deps.storage,
+        &sender_raw,
+        deposit.last_balance,
+        env.block.time.seconds(),
+    )?;
+
+    let mut msgs: Vec<CosmosMsg> = vec![];
+    for mut stream in read_streams(deps.storage)?.into_iter() {
+        if let Some(ids) = &ids {
+            if !ids.contains(&stream.id) {
+                continue;
+            }
+        }
+
+        let mut reward = read_stream_reward(deps.storage, stream.id, &sender_raw);
+        if reward.pending.is_zero() {
+            continue;
+        }
+
+        msgs.push(asset_transfer_msg(
+            &stream.asset,
+            &recipient,
+            reward.pending,
+        )?);
+        stream.claimed += reward.pending;
+        reward.pending = Uint128::zero();
+        store_stream(deps.storage, &stream)?;
+        store_stream_reward(deps.storage, stream.id, &sender_raw, &reward)?;
+    }
+
+    if msgs.is_empty() {
+        return Err(ContractError::NothingToClaim {});
+    }
+
+    Ok(Response::new().add_messages(msgs).add_attributes(vec![
+        attr("action", "claim_stream_rewards"),
+        attr("claimer", info.sender),
+        attr("recipient", recipient),
+    ]))
+}
+
+fn asset_transfer_msg(
+    asset: &AssetInfo,
+    recipient: &Addr,
+    amount: Uint128,
+) -> StdResult<CosmosMsg> {
+    Ok(match asset {
+        AssetInfo::NativeToken { denom } => CosmosMsg::Bank(BankMsg::Send {
+            to_address: recipient.to_string(),
+            amount: vec![Coin {
+                denom: denom.clone(),
+                amount,
+            }],
+        }),
+        AssetInfo::Token { contract_addr } => CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: contract_addr.clone(),
+            funds: vec![],
+            msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                recipient: recipient.to_string(),
+                amount,
+            })?,
+        }),
+    })
+}
+
+fn stream_response(api: &dyn Api, stream: RewardStream) -> StdResult<StreamResponse> {
+    Ok(StreamResponse {
+        id: stream.id,
+        sponsor: api.addr_humanize(&stream.sponsor)?.to_string(),
+        asset: stream.asset,
+        budget: stream.budget,
+        start_time: stream.start_time,
+        end_time: stream.end_time,
+        claimed: stream.claimed,
+    })
+}
+
+pub fn query_streams(deps: Deps) -> StdResult<StreamsResponse> {
+    let streams = read_streams(deps.storage)?
+        .into_iter()
+        .map(|stream| stream_response(deps.api, stream))
+        .collect::<StdResult<Vec<StreamResponse>>>()?;
+
+    Ok(StreamsResponse { streams })
+}
+
+pub fn query_pending_stream_rewards(
+    deps: Deps,
+    env: Env,
+    address: String,
+) -> StdResult<PendingStreamRewardsResponse> {
+    let ident = deps.api.addr_canonicalize(&address)?;
+    let balance = read_deposit_info(deps.storage, &ident)?.last_balance;
+    let tvl = read_state(deps.storage)?.tvl;
+
+    let rewards = read_streams(deps.storage)?
+        .into_iter()
+        .map(|mut stream| {
+            update_stream_index(&mut stream, tvl, env.block.time.seconds());
+            let reward = read_stream_reward(deps.storage, stream.id, &ident);
+            PendingStreamRewardResponse {
+                id: stream.id,
+                asset: stream.asset,
+                pending: reward.pending + earned(balance, stream.global_index, reward.index),
+            }
+        })
+        .collect();
+
+    Ok(PendingStreamRewardsResponse { rewards })
+}
@@ -0,0 +1,194 @@
+use cosmwasm_bignumber::{Decimal256, Uint256};
+use cosmwasm_std::{
+    attr, to_binary, Coin, CosmosMsg, Decimal, Deps, DepsMut, Env, MessageInfo, Response,
+    StdResult, Uint128, WasmMsg,
+};
+use moneymarket::querier::{query_price, TimeConstraints};
+use terraswap::asset::{Asset, AssetInfo, PairInfo};
+use terraswap::pair::{ExecuteMsg as PairExecuteMsg, QueryMsg as PairQueryMsg};
+
+use crate::error::ContractError;
+use crate::state::{
+    read_config, read_state, read_swap_route, read_swap_routes, remove_swap_route as remove,
+    store_state, store_swap_route, Config, State, SwapRoute, SwapRouteResponse, SwapRoutesResponse,
+};
+
+/// Oracle prices older than this many seconds are not trusted for swaps
+const PRICE_TIMEFRAME: u64 = 60;
+
+pub fn set_swap_route(
+    deps: DepsMut,
+    info: MessageInfo,
+    denom: String,
+    pair_addr: String,
+    max_spread: Decimal,
+    oracle_addr: String,
+) -> Result<Response, ContractError> {
+    let config: Config = read_config(deps.storage)?;
+    if deps.api.addr_canonicalize(info.sender.as_str())? != config.owner_addr {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    if denom == config.stable_denom || max_spread > Decimal::one() {
+        return Err(ContractError::InvalidSwapRoute(denom.clone()));
+    }
+
+    let pair = deps.api.addr_validate(&pair_addr)?;
+    let pair_info: PairInfo = deps
+        .querier
+        .query_wasm_smart(pair.as_str(), &PairQueryMsg::Pair {})?;
+    for asset_denom in [&denom, &config.stable_denom] {
+        let asset_info = AssetInfo::NativeToken {
+            denom: asset_denom.clone(),
+        };
+        if !pair_info.asset_infos.contains(&asset_info) {
+            return Err(ContractError::InvalidSwapRoute(denom.clone()));
+        }
+    }
+
+    let oracle = deps.api.addr_validate(&oracle_addr)?;
+    match query_price(
+        deps.as_ref(),
+        oracle.clone(),
+        denom.clone(),
+        config.stable_denom.clone(),
+        None,
+    ) {
+        Ok(price) if !price.rate.is_zero() => {}
+        _ => return Err(ContractError::InvalidSwapRoute(denom)),
+    }
+
+    store_swap_route(
+        deps.storage,
+        &SwapRoute {
+            denom: denom.clone(),
+            pair_addr: deps.api.addr_canonicalize(pair.as_str())?,
+            max_spread,
+            oracle_addr: deps.api.addr_canonicalize(oracle.as_str())?,
+        },
+    )?;
+
+    Ok(Response::new().add_attributes(vec![
+        attr("action", "set_swap_route"),
+        attr("denom", denom),
+        attr("pair_addr", pair),
+        attr("max_spread", max_spread.to_string()),
+        attr("oracle_addr", oracle),
+    ]))
+}
+
+pub fn remove_swap_route(
+    deps: DepsMut,
+    info: MessageInfo,
+    denom: String,
+) -> Result<Response, ContractError> {
+    let config: Config = read_config(deps.storage)?;
+    if deps.api.addr_canonicalize(info.sender.as_str())? != config.owner_addr {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    if read_swap_route(deps.storage, &denom)?.is_none() {
+        return Err(ContractError::SwapRouteNotFound(denom));
+    }
+    remove(deps.storage, &denom);
+
+    Ok(Response::new().add_attributes(vec![
+        attr("action", "remove_swap_route"),
+        attr("denom", denom),
+    ]))
+}
+
+/// Swaps the contract's balance of a non stable denom into the reserve
+pub fn sweep(deps: DepsMut, env: Env, denom: String) -> Result<Response, ContractError> {
+    let route = read_swap_route(deps.storage, &denom)?
+        .ok_or_else(|| ContractError::SwapRouteNotFound(denom.clone()))?;
+    let balance = deps
+        .querier
+        .query_balance(env.contract.address.as_str(), denom.as_str())?
+        .amount;
+    if balance.is_zero() {
+        return Err(ContractError::NothingToSweep(denom));
+    }
+
+    let (msg, min_return) = swap_msg(deps.as_ref(), &env, &route, balance)?;
+    let mut state: State = read_state(deps.storage)?;
+    state.reserve += min_return;
+    store_state(deps.storage, &state)?;
+
+    Ok(Response::new().add_message(msg).add_attributes(vec![
+        attr("action", "sweep"),
+        attr("denom", denom),
+        attr("offer_amount", balance),
+        attr("min_return", min_return),
+    ]))
+}
+
+/// Builds a swap of `amount` through the route's pair, returning the message
+/// and the least amount of stable coins the swap can return.
+/// The return is bound to the oracle price, as the pool's own price can be
+/// moved within the same block.
+pub fn swap_msg(
+    deps: Deps,
+    env: &Env,
+    route: &SwapRoute,
+    amount: Uint128,
+) -> Result<(CosmosMsg, Uint128), ContractError> {
+    let config: Config = read_config(deps.storage)?;
+    let price = query_price(
+        deps,
+        deps.api.addr_humanize(&route.oracle_addr)?,
+        route.denom.clone(),
+        config.stable_denom,
+        Some(TimeConstraints {
+            block_time: env.block.time.seconds(),
+            valid_timeframe: PRICE_TIMEFRAME,
+        }),
+    )?
+    .rate;
+    if price.is_zero() {
+        return Err(ContractError::InvalidSwapRoute(route.denom.clone()));
+    }
+
+    let pair_addr = deps.api.addr_humanize(&route.pair_addr)?;
+    let offer_asset = Asset {
+        info: AssetInfo::NativeToken {
+            denom: route.denom.clone(),
+        },
+        amount,
+    };
+
+    let msg = CosmosMsg::Wasm(WasmMsg::Execute {
+        contract_addr: pair_addr.to_string(),
+        funds: vec![Coin {
+            denom: route.denom.clone(),
+            amount,
+        }],
+        msg: to_binary(&PairExecuteMsg::Swap {
+            offer_asset,
+            // priced in the offered denom per stable coin
+            belief_price: Some((Decimal256::one() / price).into()),
+            max_spread: Some(route.max_spread),
+            to: None,
+        })?,
+    });
+
+    let min_return =
+        Uint256::from(amount) * price * Decimal256::from(Decimal::one() - route.max_spread);
+    Ok((msg, min_return.into()))
+}
+
+pub fn query_swap_routes(deps: Deps) -> StdResult<SwapRoutesResponse> {
+    let routes = read_swap_routes(deps.storage)?
+        .into_iter()
+        .map(|route| {
+            Ok(SwapRouteResponse {
+                denom: route.denom,
+                pair_addr: deps.api.addr_humanize(&route.pair_addr)?.to_string(),
+                max_spread: route.max_spread,
+                oracle_addr: deps.api.addr_humanize(&route.oracle_addr)?.to_string(),
+            })
+        })
+        .collect::<StdResult<Vec<SwapRouteResponse>>>()?;
+
+    Ok(SwapRoutesResponse { routes })
+}
@@ -0,0 +1,160 @@
+use crate::contract::{execute, query_allowlist};
+use crate::error::ContractError;
+use crate::state::{ExecuteMsg, InstantiateMsg};
+use crate::testing::mock_querier::{
+    instantiate_market, mock_dependencies, mock_instantiate_msg, MockDeps, OWNER,
+};
+
+use cosmwasm_std::testing::{mock_env, mock_info};
+use cosmwasm_std::{Coin, Response};
+
+const GROUP: &str = "group0000";
+
+fn permissioned_msg() -> InstantiateMsg {
+    let mut msg = mock_instantiate_msg();
+    msg.permissioned = Some(true);
+    msg
+}
+
+fn allowlist_msg(add: bool, addresses: &[&str]) -> ExecuteMsg {
+    let addresses = addresses.iter().map(|a| a.to_string()).collect();
+    if add {
+        ExecuteMsg::AddToAllowlist { addresses }
+    } else {
+        ExecuteMsg::RemoveFromAllowlist { addresses }
+    }
+}
+
+fn deposit(deps: &mut MockDeps, sender: &str) -> Result<Response, ContractError> {
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(sender, &[Coin::new(1000, "uusd")]),
+        ExecuteMsg::DepositStable {
+            recipient: None,
+            referrer: None,
+        },
+    )
+}
+
+#[test]
+fn only_the_owner_edits_the_allowlist() {
+    let mut deps = mock_dependencies(&[]);
+    instantiate_market(&mut deps, permissioned_msg());
+
+    for msg in [
+        allowlist_msg(true, &["addr0000"]),
+        allowlist_msg(false, &["addr0000"]),
+    ] {
+        let res = execute(deps.as_mut(), mock_env(), mock_info("addr0000", &[]), msg);
+        match res {
+            Err(ContractError::Unauthorized {}) => {}
+            _ => panic!("DO NOT ENTER HERE"),
+        }
+    }
+
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(OWNER, &[]),
+        allowlist_msg(true, &["addr0000", "addr0001"]),
+    )
+    .unwrap();
+    let mut addresses = query_allowlist(deps.as_ref(), None, None)
+        .unwrap()
+        .addresses;
+    addresses.sort();
+    assert_eq!(addresses, vec!["addr0000", "addr0001"]);
+
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(OWNER, &[]),
+        allowlist_msg(false, &["addr0001"]),
+    )
+    .unwrap();
+    assert_eq!(
+        query_allowlist(deps.as_ref(), None, None)
+            .unwrap()
+            .addresses,
+        vec!["addr0000"]
+    );
+}
+
+#[test]
+fn permissioned_markets_reject_unlisted_depositors() {
+    let mut deps = mock_dependencies(&[]);
+    instantiate_market(&mut deps, permissioned_msg());
+
+    match deposit(&mut deps, "addr0000") {
+        Err(ContractError::NotAllowlisted(ident)) => assert_eq!(ident, "addr0000"),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(OWNER, &[]),
+        allowlist_msg(true, &["addr0000"]),
+    )
+    .unwrap();
+    deposit(&mut deps, "addr0000").unwrap();
+
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(OWNER, &[]),
+        allowlist_msg(false, &["addr0000"]),
+    )
+    .unwrap();
+    match deposit(&mut deps, "addr0000") {
+        Err(ContractError::NotAllowlisted(ident)) => assert_eq!(ident, "addr0000"),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    // open markets accept anyone
+    let mut deps = mock_dependencies(&[]);
+    instantiate_market(&mut deps, mock_instantiate_msg());
+    deposit(&mut deps, "addr0000").unwrap();
+}
+
+#[test]
+fn membership_contract_approves_depositors() {
+    let mut deps = mock_dependencies(&[]);
+    deps.querier.with_members(GROUP, &["addr0001"]);
+    let mut msg = permissioned_msg();
+    msg.membership_contract = Some(GROUP.to_string());
+    instantiate_market(&mut deps, msg);
+
+    deposit(&mut deps, "addr0001").unwrap();
+    match deposit(&mut deps, "addr0002") {
+        Err(ContractError::NotAllowlisted(ident)) => assert_eq!(ident, "addr0002"),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    // the allowlist still applies next to the group
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(OWNER, &[]),
+        allowlist_msg(true, &["addr0002"]),
+    )
+    .unwrap();
+    deposit(&mut deps, "addr0002").unwrap();
+
+    // without the group its members are turned away
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(OWNER, &[]),
+        ExecuteMsg::UpdatePermissionConfig {
+            permissioned: None,
+            membership_contract: Some("".to_string()),
+        },
+    )
+    .unwrap();
+    match deposit(&mut deps, "addr0001") {
+        Err(ContractError::NotAllowlisted(ident)) => assert_eq!(ident, "addr0001"),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+}
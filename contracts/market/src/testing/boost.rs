@@ -0,0 +1,220 @@
+use crate::boost::query_boost_info;
+use crate::contract::{execute, query_state};
+use crate::error::ContractError;
+use crate::state::{BoostInfoResponse, Cw20HookMsg, ExecuteMsg};
+use crate::testing::mock_querier::{
+    instantiate_market, mock_dependencies, mock_instantiate_msg, MockDeps,
+};
+
+use cosmwasm_std::testing::{mock_env, mock_info};
+use cosmwasm_std::{
+    attr, to_binary, Coin, CosmosMsg, Decimal, Env, Response, SubMsg, Uint128, WasmMsg,
+};
+use cw20::{Cw20ExecuteMsg, Cw20ReceiveMsg};
+use std::str::FromStr;
+
+const GOVERNANCE_TOKEN: &str = "gov0000";
+const MAX_LOCK_DURATION: u64 = 1000;
+
+/// Market boosting up to 2.5x with a lock of one governance token per
+/// stable coin, with two depositors of 1000
+fn boost_market() -> MockDeps {
+    let mut deps = mock_dependencies(&[]);
+    let mut msg = mock_instantiate_msg();
+    msg.governance_token = Some(GOVERNANCE_TOKEN.to_string());
+    msg.max_lock_duration = Some(MAX_LOCK_DURATION);
+    msg.max_boost = Some(Decimal::percent(250));
+    instantiate_market(&mut deps, msg);
+
+    for depositor in ["addr0000", "addr0001"] {
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(depositor, &[Coin::new(1000, "uusd")]),
+            ExecuteMsg::DepositStable {
+                recipient: None,
+                referrer: None,
+            },
+        )
+        .unwrap();
+    }
+    deps
+}
+
+fn seconds_later(seconds: u64) -> Env {
+    let mut env = mock_env();
+    env.block.time = env.block.time.plus_seconds(seconds);
+    env
+}
+
+fn lock(
+    deps: &mut MockDeps,
+    token: &str,
+    amount: u128,
+    duration: u64,
+) -> Result<Response, ContractError> {
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(token, &[]),
+        ExecuteMsg::Receive(Cw20ReceiveMsg {
+            sender: "addr0000".to_string(),
+            amount: Uint128::from(amount),
+            msg: to_binary(&Cw20HookMsg::Lock { duration }).unwrap(),
+        }),
+    )
+}
+
+fn boost_info(deps: &MockDeps, env: Env) -> BoostInfoResponse {
+    query_boost_info(deps.as_ref(), env, "addr0000".to_string()).unwrap()
+}
+
+#[test]
+fn locks_take_the_governance_token_only() {
+    let mut deps = mock_dependencies(&[]);
+    instantiate_market(&mut deps, mock_instantiate_msg());
+    match lock(&mut deps, GOVERNANCE_TOKEN, 1000, MAX_LOCK_DURATION) {
+        Err(ContractError::BoostDisabled {}) => {}
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let mut deps = boost_market();
+    match lock(&mut deps, "token0000", 1000, MAX_LOCK_DURATION) {
+        Err(ContractError::Unauthorized {}) => {}
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+    for duration in [0, MAX_LOCK_DURATION + 1] {
+        match lock(&mut deps, GOVERNANCE_TOKEN, 1000, duration) {
+            Err(ContractError::InvalidLockDuration(max)) => assert_eq!(max, MAX_LOCK_DURATION),
+            _ => panic!("DO NOT ENTER HERE"),
+        }
+    }
+}
+
+#[test]
+fn boost_decays_with_the_voting_power() {
+    let mut deps = boost_market();
+    let time = mock_env().block.time.seconds();
+
+    // a full length lock covering the balance earns the full boost
+    let res = lock(&mut deps, GOVERNANCE_TOKEN, 1000, MAX_LOCK_DURATION).unwrap();
+    assert!(res.attributes.contains(&attr("working_balance", "2500")));
+    assert_eq!(
+        boost_info(&deps, mock_env()),
+        BoostInfoResponse {
+            locked_amount: Uint128::from(1000u128),
+            lock_end: time + MAX_LOCK_DURATION,
+            voting_power: Uint128::from(1000u128),
+            boost: Decimal::percent(250),
+            working_balance: Uint128::from(2500u128),
+            total_working_balance: Uint128::from(3500u128),
+        }
+    );
+
+    // halfway through the lock half the extra balance is left, applied
+    // once the depositor is kicked
+    let info = boost_info(&deps, seconds_later(500));
+    assert_eq!(info.voting_power, Uint128::from(500u128));
+    assert_eq!(info.boost, Decimal::from_str("1.75").unwrap());
+    assert_eq!(info.working_balance, Uint128::from(2500u128));
+
+    let res = execute(
+        deps.as_mut(),
+        seconds_later(500),
+        mock_info("keeper0000", &[]),
+        ExecuteMsg::Kick {
+            address: "addr0000".to_string(),
+        },
+    )
+    .unwrap();
+    assert!(res.attributes.contains(&attr("working_balance", "1750")));
+    assert_eq!(
+        query_state(deps.as_ref()).unwrap().total_working_balance,
+        Uint128::from(2750u128)
+    );
+
+    // past the end the boost is gone
+    execute(
+        deps.as_mut(),
+        seconds_later(MAX_LOCK_DURATION),
+        mock_info("keeper0000", &[]),
+        ExecuteMsg::Kick {
+            address: "addr0000".to_string(),
+        },
+    )
+    .unwrap();
+    let info = boost_info(&deps, seconds_later(MAX_LOCK_DURATION));
+    assert_eq!(info.voting_power, Uint128::zero());
+    assert_eq!(info.boost, Decimal::one());
+    assert_eq!(info.working_balance, Uint128::from(1000u128));
+    assert_eq!(info.total_working_balance, Uint128::from(2000u128));
+}
+
+#[test]
+fn locks_extend_and_unlock_after_their_end() {
+    let mut deps = boost_market();
+    let time = mock_env().block.time.seconds();
+    let extend = |deps: &mut MockDeps, sender: &str, duration: u64| {
+        execute(
+            deps.as_mut(),
+            seconds_later(500),
+            mock_info(sender, &[]),
+            ExecuteMsg::ExtendLock { duration },
+        )
+    };
+    match extend(&mut deps, "addr0000", MAX_LOCK_DURATION) {
+        Err(ContractError::NoLock {}) => {}
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    lock(&mut deps, GOVERNANCE_TOKEN, 1000, MAX_LOCK_DURATION).unwrap();
+    for duration in [200, MAX_LOCK_DURATION + 1] {
+        match extend(&mut deps, "addr0000", duration) {
+            Err(ContractError::InvalidLockDuration(max)) => assert_eq!(max, MAX_LOCK_DURATION),
+            _ => panic!("DO NOT ENTER HERE"),
+        }
+    }
+
+    // extending restores the voting power
+    let res = extend(&mut deps, "addr0000", MAX_LOCK_DURATION).unwrap();
+    assert!(res.attributes.contains(&attr("working_balance", "2500")));
+    let lock_end = time + 500 + MAX_LOCK_DURATION;
+    assert_eq!(boost_info(&deps, seconds_later(500)).lock_end, lock_end);
+
+    let unlock = |deps: &mut MockDeps, env: Env| {
+        execute(
+            deps.as_mut(),
+            env,
+            mock_info("addr0000", &[]),
+            ExecuteMsg::Unlock {},
+        )
+    };
+    match unlock(&mut deps, seconds_later(MAX_LOCK_DURATION)) {
+        Err(ContractError::LockNotExpired(end)) => assert_eq!(end, lock_end),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let end = seconds_later(500 + MAX_LOCK_DURATION);
+    let res = unlock(&mut deps, end.clone()).unwrap();
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: GOVERNANCE_TOKEN.to_string(),
+            funds: vec![],
+            msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                recipient: "addr0000".to_string(),
+                amount: Uint128::from(1000u128),
+            })
+            .unwrap(),
+        }))]
+    );
+    assert!(res.attributes.contains(&attr("working_balance", "1000")));
+    assert_eq!(
+        query_state(deps.as_ref()).unwrap().total_locked,
+        Uint128::zero()
+    );
+    match unlock(&mut deps, end) {
+        Err(ContractError::NoLock {}) => {}
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+}
@@ -0,0 +1,82 @@
+use crate::contract::{execute, query_config};
+use crate::error::ContractError;
+use crate::state::ExecuteMsg;
+use crate::testing::mock_querier::{
+    instantiate_market, mock_dependencies, mock_instantiate_msg, OWNER,
+};
+
+use cosmwasm_bignumber::Decimal256;
+use cosmwasm_std::testing::{mock_env, mock_info, MOCK_CONTRACT_ADDR};
+use cosmwasm_std::{attr, Coin, Decimal, Event};
+use moneymarket::distribution_model::InstantiateMsg as DistributionInstantiateMsg;
+use std::str::FromStr;
+
+const EPOCH_PERIOD: u64 = 86400;
+
+#[test]
+fn epoch_adjusts_emission_rate_through_the_distribution_model() {
+    let mut deps = mock_dependencies(&[]);
+    deps.querier.with_distribution_model(
+        "distribution0000",
+        DistributionInstantiateMsg {
+            owner: OWNER.to_string(),
+            emission_cap: Decimal256::from_str("12").unwrap(),
+            emission_floor: Decimal256::one(),
+            increment_multiplier: Decimal256::from_str("1.1").unwrap(),
+            decrement_multiplier: Decimal256::from_str("0.9").unwrap(),
+        },
+    );
+    let mut msg = mock_instantiate_msg();
+    msg.epoch_period = Some(EPOCH_PERIOD);
+    msg.reward_token = Some("reward0000".to_string());
+    msg.emission_rate = Some(Decimal::from_str("10").unwrap());
+    msg.distribution_model = Some("distribution0000".to_string());
+    msg.target_deposit_rate = Some(Decimal::percent(2));
+    msg.threshold_deposit_rate = Some(Decimal::percent(1));
+    instantiate_market(&mut deps, msg);
+    deps.querier
+        .update_balance(MOCK_CONTRACT_ADDR, vec![Coin::new(1000, "uusd")]);
+
+    // deposits pay below the threshold, the model raises emissions
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("keeper0000", &[]),
+        ExecuteMsg::ExecuteEpochOperations {},
+    )
+    .unwrap();
+    assert!(res
+        .events
+        .contains(&Event::new("adjust_emission_rate").add_attributes(vec![
+            attr("deposit_rate", "0.01"),
+            attr("emission_rate", "11"),
+        ])));
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("keeper0000", &[]),
+        ExecuteMsg::ExecuteEpochOperations {},
+    );
+    match res {
+        Err(ContractError::EpochNotPassed(time)) => {
+            assert_eq!(time, mock_env().block.time.seconds() + EPOCH_PERIOD)
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    // up to the model's cap
+    let mut env = mock_env();
+    env.block.time = env.block.time.plus_seconds(EPOCH_PERIOD);
+    execute(
+        deps.as_mut(),
+        env,
+        mock_info("keeper0000", &[]),
+        ExecuteMsg::ExecuteEpochOperations {},
+    )
+    .unwrap();
+    assert_eq!(
+        query_config(deps.as_ref()).unwrap().emission_rate,
+        Decimal::from_str("12").unwrap()
+    );
+}
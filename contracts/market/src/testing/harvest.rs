@@ -0,0 +1,157 @@
+use crate::contract::{execute, instantiate, query_state};
+use crate::error::ContractError;
+use crate::state::ExecuteMsg;
+use crate::testing::mock_querier::{mock_dependencies, OWNER};
+use crate::testing::staking::{native_staking_market, native_staking_msg};
+
+use cosmwasm_std::testing::{mock_env, mock_info};
+use cosmwasm_std::{
+    BankMsg, Coin, CosmosMsg, Decimal, DistributionMsg, StakingMsg, SubMsg, Uint128,
+};
+
+#[test]
+fn harvest_bounty_is_a_share() {
+    let mut deps = mock_dependencies(&[]);
+    deps.querier.with_validators("uusd", &["validator0000"]);
+    let mut msg = native_staking_msg();
+    msg.harvest_bounty = Some(Decimal::percent(101));
+    let res = instantiate(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(OWNER, &[Coin::new(1000000, "uusd")]),
+        msg,
+    );
+    match res {
+        Err(ContractError::InvalidHarvestBounty {}) => {}
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+}
+
+#[test]
+fn harvest_compounds_rewards() {
+    let mut msg = native_staking_msg();
+    msg.harvest_bounty = Some(Decimal::percent(10));
+    let mut deps = native_staking_market(msg);
+
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0000", &[Coin::new(1000, "uusd")]),
+        ExecuteMsg::DepositStable {
+            recipient: None,
+            referrer: None,
+        },
+    )
+    .unwrap();
+
+    deps.querier.with_delegation("validator0001", 1000, &[]);
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("keeper0000", &[]),
+        ExecuteMsg::Harvest {},
+    );
+    match res {
+        Err(ContractError::NothingToHarvest {}) => {}
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    // rewards in denoms without a swap route are left alone
+    deps.querier.with_delegation(
+        "validator0001",
+        1000,
+        &[Coin::new(100, "uusd"), Coin::new(50, "ukrw")],
+    );
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("keeper0000", &[]),
+        ExecuteMsg::Harvest {},
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages,
+        vec![
+            SubMsg::new(CosmosMsg::Distribution(
+                DistributionMsg::WithdrawDelegatorReward {
+                    validator: "validator0001".to_string(),
+                }
+            )),
+            SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
+                to_address: "keeper0000".to_string(),
+                amount: vec![Coin::new(10, "uusd")],
+            })),
+            SubMsg::new(CosmosMsg::Staking(StakingMsg::Delegate {
+                validator: "validator0000".to_string(),
+                amount: Coin::new(90, "uusd"),
+            })),
+        ]
+    );
+
+    let state = query_state(deps.as_ref()).unwrap();
+    assert_eq!(state.exchange_rate, Decimal::percent(109));
+    assert_eq!(state.total_delegated, Uint128::from(1090u128));
+    assert_eq!(state.total_harvested, Uint128::from(100u128));
+}
+
+#[test]
+fn harvest_compounds_rewards_withdrawn_by_delegation_changes() {
+    let mut msg = native_staking_msg();
+    msg.harvest_bounty = Some(Decimal::zero());
+    let mut deps = native_staking_market(msg);
+    deps.querier
+        .with_delegation("validator0000", 500, &[Coin::new(20, "uusd")]);
+    deps.querier
+        .with_delegation("validator0001", 500, &[Coin::new(30, "uusd")]);
+
+    // the chain pays out the rewards of the validator delegated to
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0000", &[Coin::new(1000, "uusd")]),
+        ExecuteMsg::DepositStable {
+            recipient: None,
+            referrer: None,
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages[0],
+        SubMsg::new(CosmosMsg::Staking(StakingMsg::Delegate {
+            validator: "validator0001".to_string(),
+            amount: Coin::new(1000, "uusd"),
+        }))
+    );
+    assert_eq!(
+        query_state(deps.as_ref()).unwrap().withdrawn_rewards,
+        Uint128::from(30u128)
+    );
+
+    // within the same block only the other validator is withdrawn from
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("keeper0000", &[]),
+        ExecuteMsg::Harvest {},
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages,
+        vec![
+            SubMsg::new(CosmosMsg::Distribution(
+                DistributionMsg::WithdrawDelegatorReward {
+                    validator: "validator0000".to_string(),
+                }
+            )),
+            SubMsg::new(CosmosMsg::Staking(StakingMsg::Delegate {
+                validator: "validator0000".to_string(),
+                amount: Coin::new(50, "uusd"),
+            })),
+        ]
+    );
+
+    let state = query_state(deps.as_ref()).unwrap();
+    assert_eq!(state.withdrawn_rewards, Uint128::zero());
+    assert_eq!(state.total_harvested, Uint128::from(50u128));
+    assert_eq!(state.total_delegated, Uint128::from(1050u128));
+}
@@ -1,53 +1,40 @@
-use schemars::JsonSchema;
-use serde::{Deserialize, Serialize};
-
 use cosmwasm_bignumber::{Decimal256, Uint256};
-use cosmwasm_std::testing::{MockApi, MockQuerier, MockStorage, MOCK_CONTRACT_ADDR};
+use cosmwasm_std::testing::{
+    mock_dependencies as mock_base_dependencies, mock_env, mock_info, MockApi, MockQuerier,
+    MockStorage, MOCK_CONTRACT_ADDR,
+};
 use cosmwasm_std::{
-    from_binary, from_slice, to_binary, Addr, Api, CanonicalAddr, Coin, ContractResult, Decimal,
-    OwnedDeps, Querier, QuerierResult, QueryRequest, SystemError, SystemResult, Uint128, WasmQuery,
+    from_binary, from_slice, to_binary, Addr, Binary, Coin, ContractResult, Decimal, Empty,
+    FullDelegation, OwnedDeps, Querier, QuerierResult, QueryRequest, Reply, StdError,
+    SubMsgExecutionResponse, SystemError, SystemResult, Uint128, Validator, WasmQuery,
+};
+use cw20::{BalanceResponse, Cw20QueryMsg};
+use moneymarket::distribution_model::InstantiateMsg as DistributionInstantiateMsg;
+use moneymarket::market::{
+    ConfigResponse as MarketConfigResponse, EpochStateResponse, QueryMsg as MarketQueryMsg,
 };
-use cosmwasm_storage::to_length_prefixed;
+use moneymarket::oracle::{PriceResponse, QueryMsg as OracleQueryMsg};
+use moneymarket_distribution_model::contract::{
+    instantiate as distribution_instantiate, query as distribution_query,
+};
+use protobuf::Message;
 use std::collections::HashMap;
+use terraswap::asset::{AssetInfo, PairInfo};
+use terraswap::pair::{QueryMsg as PairQueryMsg, SimulationResponse};
 
-use cw20::TokenInfoResponse;
-use moneymarket::distribution_model::AncEmissionRateResponse;
-use moneymarket::interest_model::BorrowRateResponse;
-use moneymarket::overseer::{BorrowLimitResponse, ConfigResponse};
-use terra_cosmwasm::{TaxCapResponse, TaxRateResponse, TerraQuery, TerraQueryWrapper, TerraRoute};
-
-#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
-#[serde(rename_all = "snake_case")]
-pub enum QueryMsg {
-    /// Query borrow rate to interest model contract
-    BorrowRate {
-        market_balance: Uint128,
-        total_liabilities: Decimal256,
-        total_reserves: Decimal256,
-    },
-    /// Query borrow limit to overseer contract
-    BorrowLimit {
-        borrower: String,
-        block_time: Option<u64>,
-    },
-    /// Query ANC emission rate to distribution model contract
-    AncEmissionRate {
-        deposit_rate: Decimal256,
-        target_deposit_rate: Decimal256,
-        threshold_deposit_rate: Decimal256,
-        current_emission_rate: Decimal256,
-    },
-    /// Query overseer config to get target deposit rate
-    Config {},
-    /// Query cw20 Token Info
-    TokenInfo {},
-}
+use crate::contract::{instantiate, reply, INITIAL_DEPOSIT_AMOUNT};
+use crate::querier::{MemberResponse, MembershipQueryMsg};
+use crate::response::MsgInstantiateContractResponse;
+use crate::state::InstantiateMsg;
+
+pub const OWNER: &str = "owner0000";
+pub const ATERRA: &str = "aterra0000";
+
+pub type MockDeps = OwnedDeps<MockStorage, MockApi, WasmMockQuerier>;
 
 /// mock_dependencies is a drop-in replacement for cosmwasm_std::testing::mock_dependencies
 /// this uses our CustomQuerier.
-pub fn mock_dependencies(
-    contract_balance: &[Coin],
-) -> OwnedDeps<MockStorage, MockApi, WasmMockQuerier> {
+pub fn mock_dependencies(contract_balance: &[Coin]) -> MockDeps {
     let custom_querier: WasmMockQuerier =
         WasmMockQuerier::new(MockQuerier::new(&[(MOCK_CONTRACT_ADDR, contract_balance)]));
 
@@ -58,119 +45,122 @@ pub fn mock_dependencies(
     }
 }
 
-pub struct WasmMockQuerier {
-    base: MockQuerier<TerraQueryWrapper>,
-    token_querier: TokenQuerier,
-    tax_querier: TaxQuerier,
-    borrow_rate_querier: BorrowRateQuerier,
-    borrow_limit_querier: BorrowLimitQuerier,
-}
-
-#[derive(Clone, Default)]
-pub struct TokenQuerier {
-    // this lets us iterate over all pairs that match the first string
-    balances: HashMap<String, HashMap<String, Uint128>>,
-}
-
-impl TokenQuerier {
-    pub fn new(balances: &[(&String, &[(&String, &Uint128)])]) -> Self {
-        TokenQuerier {
-            balances: balances_to_map(balances),
-        }
+/// Market paying 1% interest per day without any optional feature
+pub fn mock_instantiate_msg() -> InstantiateMsg {
+    InstantiateMsg {
+        owner_addr: OWNER.to_string(),
+        stable_denom: "uusd".to_string(),
+        aterra_code_id: 123u64,
+        interest: "0.01".to_string(),
+        tvl_cap: None,
+        max_balance: None,
+        min_deposit: None,
+        permissioned: None,
+        membership_contract: None,
+        unbonding_period: None,
+        native_staking: None,
+        chain_unbonding_period: None,
+        harvest_bounty: None,
+        max_rebalance: None,
+        liquidity_buffer: None,
+        epoch_period: None,
+        epoch_bounty: None,
+        reward_token: None,
+        emission_rate: None,
+        distribution_model: None,
+        target_deposit_rate: None,
+        threshold_deposit_rate: None,
+        governance_token: None,
+        max_lock_duration: None,
+        max_boost: None,
+        boost_ratio: None,
+        referral_share: None,
+        prize_mode: None,
+        draw_period: None,
+        reveal_period: None,
+        prize_winners: None,
     }
 }
 
-pub(crate) fn balances_to_map(
-    balances: &[(&String, &[(&String, &Uint128)])],
-) -> HashMap<String, HashMap<String, Uint128>> {
-    let mut balances_map: HashMap<String, HashMap<String, Uint128>> = HashMap::new();
-    for (contract_addr, balances) in balances.iter() {
-        let mut contract_balances_map: HashMap<String, Uint128> = HashMap::new();
-        for (addr, balance) in balances.iter() {
-            contract_balances_map.insert(addr.to_string(), **balance);
-        }
-
-        balances_map.insert(contract_addr.to_string(), contract_balances_map);
+/// Reply of the token instantiated by `id` at `token_addr`
+pub fn instantiate_reply(id: u64, token_addr: &str) -> Reply {
+    let mut res = MsgInstantiateContractResponse::new();
+    res.set_contract_address(token_addr.to_string());
+    Reply {
+        id,
+        result: ContractResult::Ok(SubMsgExecutionResponse {
+            events: vec![],
+            data: Some(res.write_to_bytes().unwrap().into()),
+        }),
     }
-    balances_map
 }
 
-#[derive(Clone, Default)]
-pub struct TaxQuerier {
-    rate: Decimal,
-    // this lets us iterate over all pairs that match the first string
-    caps: HashMap<String, Uint128>,
+/// Instantiates the market with `msg` and registers its receipt token
+pub fn instantiate_market(deps: &mut MockDeps, msg: InstantiateMsg) {
+    let info = mock_info(
+        OWNER,
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(INITIAL_DEPOSIT_AMOUNT),
+        }],
+    );
+    instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+    reply(deps.as_mut(), mock_env(), instantiate_reply(1, ATERRA)).unwrap();
 }
 
-impl TaxQuerier {
-    pub fn new(rate: Decimal, caps: &[(&String, &Uint128)]) -> Self {
-        TaxQuerier {
-            rate,
-            caps: caps_to_map(caps),
-        }
-    }
-}
-
-pub(crate) fn caps_to_map(caps: &[(&String, &Uint128)]) -> HashMap<String, Uint128> {
-    let mut owner_map: HashMap<String, Uint128> = HashMap::new();
-    for (denom, cap) in caps.iter() {
-        owner_map.insert(denom.to_string(), **cap);
-    }
-    owner_map
+pub struct WasmMockQuerier {
+    base: MockQuerier,
+    staking_querier: StakingQuerier,
+    pair_querier: PairQuerier,
+    market_querier: MarketQuerier,
+    token_querier: TokenQuerier,
+    membership_querier: MembershipQuerier,
+    oracle_querier: OracleQuerier,
+    // distribution model contract answering from its own storage
+    distribution_model: Option<(String, OwnedDeps<MockStorage, MockApi, MockQuerier>)>,
 }
 
 #[derive(Clone, Default)]
-pub struct BorrowRateQuerier {
-    // this lets us iterate over all pairs that match the first string
-    borrower_rate: HashMap<String, Decimal256>,
+pub struct StakingQuerier {
+    denom: String,
+    validators: Vec<Validator>,
+    // delegations of the market
+    delegations: Vec<FullDelegation>,
 }
 
-impl BorrowRateQuerier {
-    pub fn new(borrower_rate: &[(&String, &Decimal256)]) -> Self {
-        BorrowRateQuerier {
-            borrower_rate: borrower_rate_to_map(borrower_rate),
-        }
-    }
+#[derive(Clone, Default)]
+pub struct PairQuerier {
+    // pair info and the price of the offered asset, by pair address
+    pairs: HashMap<String, (PairInfo, Decimal)>,
 }
 
-pub(crate) fn borrower_rate_to_map(
-    borrower_rate: &[(&String, &Decimal256)],
-) -> HashMap<String, Decimal256> {
-    let mut borrower_rate_map: HashMap<String, Decimal256> = HashMap::new();
-    for (market_contract, borrower_rate) in borrower_rate.iter() {
-        borrower_rate_map.insert((*market_contract).clone(), **borrower_rate);
-    }
-    borrower_rate_map
+#[derive(Clone, Default)]
+pub struct MarketQuerier {
+    // config and aterra exchange rate of anchor markets, by market address
+    markets: HashMap<String, (MarketConfigResponse, Decimal256)>,
 }
 
 #[derive(Clone, Default)]
-pub struct BorrowLimitQuerier {
-    // this lets us iterate over all pairs that match the first string
-    borrow_limit: HashMap<String, Uint256>,
+pub struct TokenQuerier {
+    // cw20 balances by token address, then holder
+    balances: HashMap<String, HashMap<String, Uint128>>,
 }
 
-impl BorrowLimitQuerier {
-    pub fn new(borrow_limit: &[(&String, &Uint256)]) -> Self {
-        BorrowLimitQuerier {
-            borrow_limit: borrow_limit_to_map(borrow_limit),
-        }
-    }
+#[derive(Clone, Default)]
+pub struct MembershipQuerier {
+    // member weights by group address, then member
+    groups: HashMap<String, HashMap<String, u64>>,
 }
 
-pub(crate) fn borrow_limit_to_map(
-    borrow_limit: &[(&String, &Uint256)],
-) -> HashMap<String, Uint256> {
-    let mut borrow_limit_map: HashMap<String, Uint256> = HashMap::new();
-    for (market_contract, borrow_limit) in borrow_limit.iter() {
-        borrow_limit_map.insert((*market_contract).clone(), **borrow_limit);
-    }
-    borrow_limit_map
+#[derive(Clone, Default)]
+pub struct OracleQuerier {
+    // prices by oracle address, then base and quote denom
+    prices: HashMap<String, HashMap<(String, String), Decimal256>>,
 }
 
 impl Querier for WasmMockQuerier {
     fn raw_query(&self, bin_request: &[u8]) -> QuerierResult {
-        // MockQuerier doesn't support Custom, so we ignore it completely here
-        let request: QueryRequest<TerraQueryWrapper> = match from_slice(bin_request) {
+        let request: QueryRequest<Empty> = match from_slice(bin_request) {
             Ok(v) => v,
             Err(e) => {
                 return SystemResult::Err(SystemError::InvalidRequest {
@@ -184,175 +174,135 @@ impl Querier for WasmMockQuerier {
 }
 
 impl WasmMockQuerier {
-    pub fn handle_query(&self, request: &QueryRequest<TerraQueryWrapper>) -> QuerierResult {
+    pub fn handle_query(&self, request: &QueryRequest<Empty>) -> QuerierResult {
         match &request {
-            QueryRequest::Custom(TerraQueryWrapper { route, query_data }) => {
-                if &TerraRoute::Treasury == route {
-                    match query_data {
-                        TerraQuery::TaxRate {} => {
-                            let res = TaxRateResponse {
-                                rate: self.tax_querier.rate,
-                            };
-                            SystemResult::Ok(ContractResult::from(to_binary(&res)))
-                        }
-                        TerraQuery::TaxCap { denom } => {
-                            let cap = self
-                                .tax_querier
-                                .caps
-                                .get(denom)
-                                .copied()
-                                .unwrap_or_default();
-                            let res = TaxCapResponse { cap };
-                            SystemResult::Ok(ContractResult::from(to_binary(&res)))
-                        }
-                        _ => panic!("DO NOT ENTER HERE"),
-                    }
-                } else {
-                    panic!("DO NOT ENTER HERE")
-                }
-            }
             QueryRequest::Wasm(WasmQuery::Smart { contract_addr, msg }) => {
-                match from_binary(msg).unwrap() {
-                    QueryMsg::BorrowRate {
-                        market_balance: _,
-                        total_liabilities: _,
-                        total_reserves: _,
-                    } => {
-                        match self.borrow_rate_querier.borrower_rate.get(contract_addr) {
-                            Some(v) => SystemResult::Ok(ContractResult::from(to_binary(
-                                &BorrowRateResponse { rate: *v },
+                if let Some((addr, deps)) = &self.distribution_model {
+                    if addr == contract_addr {
+                        return match from_binary(msg) {
+                            Ok(msg) => SystemResult::Ok(ContractResult::from(distribution_query(
+                                deps.as_ref(),
+                                mock_env(),
+                                msg,
                             ))),
-                            None => SystemResult::Err(SystemError::InvalidRequest {
-                                error: "No borrow rate exists".to_string(),
-                                request: msg.as_slice().into(),
-                            }),
-                        }
-                    }
-                    QueryMsg::BorrowLimit {
-                        borrower,
-                        block_time: _,
-                    } => match self.borrow_limit_querier.borrow_limit.get(&borrower) {
-                        Some(v) => SystemResult::Ok(ContractResult::from(to_binary(
-                            &BorrowLimitResponse {
-                                borrower,
-                                borrow_limit: *v,
-                            },
-                        ))),
-                        None => SystemResult::Err(SystemError::InvalidRequest {
-                            error: "No borrow limit exists".to_string(),
-                            request: msg.as_slice().into(),
-                        }),
-                    },
-                    QueryMsg::AncEmissionRate {
-                        deposit_rate: _,
-                        target_deposit_rate: _,
-                        threshold_deposit_rate: _,
-                        current_emission_rate: _,
-                    } => SystemResult::Ok(ContractResult::from(to_binary(
-                        &AncEmissionRateResponse {
-                            emission_rate: Decimal256::from_uint256(5u64),
-                        },
-                    ))),
-                    QueryMsg::Config {} => {
-                        SystemResult::Ok(ContractResult::from(to_binary(&ConfigResponse {
-                            owner_addr: "".to_string(),
-                            oracle_contract: "".to_string(),
-                            market_contract: "".to_string(),
-                            liquidation_contract: "".to_string(),
-                            collector_contract: "".to_string(),
-                            threshold_deposit_rate: Decimal256::one(),
-                            target_deposit_rate: Decimal256::from_ratio(1, 100),
-                            buffer_distribution_factor: Decimal256::one(),
-                            anc_purchase_factor: Decimal256::one(),
-                            stable_denom: "uusd".to_string(),
-                            epoch_period: 100u64,
-                            price_timeframe: 100u64,
-                            dyn_rate_epoch: 8600u64,
-                            dyn_rate_maxchange: Decimal256::permille(5),
-                            dyn_rate_yr_increase_expectation: Decimal256::permille(1),
-                            dyn_rate_min: Decimal256::from_ratio(
-                                1000000000000u64,
-                                1000000000000000000u64,
-                            ),
-                            dyn_rate_max: Decimal256::from_ratio(
-                                1200000000000u64,
-                                1000000000000000000u64,
-                            ),
-                        })))
+                            Err(e) => invalid_request("distribution model", e, msg),
+                        };
                     }
-                    QueryMsg::TokenInfo {} => {
-                        let balances: HashMap<String, Uint128> =
-                            match self.token_querier.balances.get(contract_addr) {
-                                Some(balances) => balances.clone(),
-                                None => HashMap::new(),
-                            };
-
-                        let mut total_supply = Uint128::zero();
+                }
 
-                        for balance in balances {
-                            total_supply += balance.1;
+                if let Some((pair_info, price)) = self.pair_querier.pairs.get(contract_addr) {
+                    return match from_binary(msg) {
+                        Ok(PairQueryMsg::Pair {}) => {
+                            SystemResult::Ok(ContractResult::from(to_binary(pair_info)))
                         }
-
-                        SystemResult::Ok(ContractResult::from(to_binary(&TokenInfoResponse {
-                            name: "mAPPL".to_string(),
-                            symbol: "mAPPL".to_string(),
-                            decimals: 6,
-                            total_supply,
-                        })))
-                    }
+                        Ok(PairQueryMsg::Simulation { offer_asset }) => {
+                            SystemResult::Ok(ContractResult::from(to_binary(&SimulationResponse {
+                                return_amount: offer_asset.amount * *price,
+                                spread_amount: Uint128::zero(),
+                                commission_amount: Uint128::zero(),
+                            })))
+                        }
+                        Ok(_) => SystemResult::Err(SystemError::UnsupportedRequest {
+                            kind: "pair query".to_string(),
+                        }),
+                        Err(e) => invalid_request("pair", e, msg),
+                    };
                 }
-            }
-            QueryRequest::Wasm(WasmQuery::Raw { contract_addr, key }) => {
-                let key: &[u8] = key.as_slice();
-
-                let prefix_balance = to_length_prefixed(b"balance").to_vec();
 
-                let balances: HashMap<String, Uint128> =
-                    match self.token_querier.balances.get(contract_addr) {
-                        Some(balances) => balances.clone(),
-                        None => HashMap::new(),
+                if let Some((config, exchange_rate)) =
+                    self.market_querier.markets.get(contract_addr)
+                {
+                    return match from_binary(msg) {
+                        Ok(MarketQueryMsg::Config {}) => {
+                            SystemResult::Ok(ContractResult::from(to_binary(config)))
+                        }
+                        Ok(MarketQueryMsg::EpochState { .. }) => {
+                            SystemResult::Ok(ContractResult::from(to_binary(&EpochStateResponse {
+                                exchange_rate: *exchange_rate,
+                                aterra_supply: Uint256::zero(),
+                            })))
+                        }
+                        Ok(_) => SystemResult::Err(SystemError::UnsupportedRequest {
+                            kind: "market query".to_string(),
+                        }),
+                        Err(e) => invalid_request("market", e, msg),
                     };
+                }
 
-                if key[..prefix_balance.len()].to_vec() == prefix_balance {
-                    let key_address: &[u8] = &key[prefix_balance.len()..];
-                    let address_raw: CanonicalAddr = CanonicalAddr::from(key_address);
-                    let api: MockApi = MockApi::default();
-                    let address: Addr = match api.addr_humanize(&address_raw) {
-                        Ok(v) => v,
-                        Err(e) => {
-                            return SystemResult::Err(SystemError::InvalidRequest {
-                                error: format!("Parsing query request: {}", e),
-                                request: key.into(),
-                            })
+                if let Some(balances) = self.token_querier.balances.get(contract_addr) {
+                    return match from_binary(msg) {
+                        Ok(Cw20QueryMsg::Balance { address }) => {
+                            SystemResult::Ok(ContractResult::from(to_binary(&BalanceResponse {
+                                balance: balances.get(&address).copied().unwrap_or_default(),
+                            })))
                         }
+                        Ok(_) => SystemResult::Err(SystemError::UnsupportedRequest {
+                            kind: "token query".to_string(),
+                        }),
+                        Err(e) => invalid_request("token", e, msg),
                     };
-                    let balance = match balances.get(address.as_str()) {
-                        Some(v) => v,
-                        None => {
-                            return SystemResult::Err(SystemError::InvalidRequest {
-                                error: "Balance not found".to_string(),
-                                request: key.into(),
-                            })
+                }
+
+                if let Some(members) = self.membership_querier.groups.get(contract_addr) {
+                    return match from_binary(msg) {
+                        Ok(MembershipQueryMsg::Member { addr, .. }) => {
+                            SystemResult::Ok(ContractResult::from(to_binary(&MemberResponse {
+                                weight: members.get(&addr).copied(),
+                            })))
                         }
+                        Err(e) => invalid_request("membership", e, msg),
                     };
-                    SystemResult::Ok(ContractResult::from(to_binary(&balance)))
-                } else {
-                    panic!("DO NOT ENTER HERE")
                 }
+
+                if let Some(prices) = self.oracle_querier.prices.get(contract_addr) {
+                    return match from_binary(msg) {
+                        Ok(OracleQueryMsg::Price { base, quote }) => match prices
+                            .get(&(base, quote))
+                        {
+                            Some(rate) => {
+                                let time = mock_env().block.time.seconds();
+                                SystemResult::Ok(ContractResult::from(to_binary(&PriceResponse {
+                                    rate: *rate,
+                                    last_updated_base: time,
+                                    last_updated_quote: time,
+                                })))
+                            }
+                            None => {
+                                SystemResult::Ok(ContractResult::Err("No price data".to_string()))
+                            }
+                        },
+                        Ok(_) => SystemResult::Err(SystemError::UnsupportedRequest {
+                            kind: "oracle query".to_string(),
+                        }),
+                        Err(e) => invalid_request("oracle", e, msg),
+                    };
+                }
+
+                self.base.handle_query(request)
             }
             _ => self.base.handle_query(request),
         }
     }
 }
 
+fn invalid_request(kind: &str, e: StdError, msg: &Binary) -> QuerierResult {
+    SystemResult::Err(SystemError::InvalidRequest {
+        error: format!("Parsing {} query: {}", kind, e),
+        request: msg.clone(),
+    })
+}
+
 impl WasmMockQuerier {
-    pub fn new(base: MockQuerier<TerraQueryWrapper>) -> Self {
+    pub fn new(base: MockQuerier) -> Self {
         WasmMockQuerier {
             base,
+            staking_querier: StakingQuerier::default(),
+            pair_querier: PairQuerier::default(),
+            market_querier: MarketQuerier::default(),
             token_querier: TokenQuerier::default(),
-            tax_querier: TaxQuerier::default(),
-            borrow_rate_querier: BorrowRateQuerier::default(),
-            borrow_limit_querier: BorrowLimitQuerier::default(),
+            membership_querier: MembershipQuerier::default(),
+            oracle_querier: OracleQuerier::default(),
+            distribution_model: None,
         }
     }
 
@@ -365,21 +315,127 @@ impl WasmMockQuerier {
         self.base.update_balance(addr, balance)
     }
 
-    // configure the mint whitelist mock querier
-    pub fn with_token_balances(&mut self, balances: &[(&String, &[(&String, &Uint128)])]) {
-        self.token_querier = TokenQuerier::new(balances);
+    // configure the staking module with its bonded denom and validators
+    pub fn with_validators(&mut self, denom: &str, validators: &[&str]) {
+        self.staking_querier.denom = denom.to_string();
+        self.staking_querier.validators = validators
+            .iter()
+            .map(|address| Validator {
+                address: address.to_string(),
+                commission: Decimal::percent(5),
+                max_commission: Decimal::percent(20),
+                max_change_rate: Decimal::percent(1),
+            })
+            .collect();
+        self.update_staking();
+    }
+
+    // set the market's delegation to `validator` and its pending rewards
+    pub fn with_delegation(&mut self, validator: &str, amount: u128, rewards: &[Coin]) {
+        let denom = self.staking_querier.denom.clone();
+        let delegations = &mut self.staking_querier.delegations;
+        delegations.retain(|d| d.validator != validator);
+        delegations.push(FullDelegation {
+            delegator: Addr::unchecked(MOCK_CONTRACT_ADDR),
+            validator: validator.to_string(),
+            amount: Coin::new(amount, &denom),
+            can_redelegate: Coin::new(amount, &denom),
+            accumulated_rewards: rewards.to_vec(),
+        });
+        self.update_staking();
     }
 
-    // configure the tax mock querier
-    pub fn with_tax(&mut self, rate: Decimal, caps: &[(&String, &Uint128)]) {
-        self.tax_querier = TaxQuerier::new(rate, caps);
+    // register a terraswap pair of native `denoms` swapping at `price`
+    pub fn with_pair(&mut self, pair_addr: &str, denoms: [&str; 2], price: Decimal) {
+        let pair_info = PairInfo {
+            asset_infos: [
+                AssetInfo::NativeToken {
+                    denom: denoms[0].to_string(),
+                },
+                AssetInfo::NativeToken {
+                    denom: denoms[1].to_string(),
+                },
+            ],
+            contract_addr: pair_addr.to_string(),
+            liquidity_token: "liquidity0000".to_string(),
+        };
+        self.pair_querier
+            .pairs
+            .insert(pair_addr.to_string(), (pair_info, price));
+    }
+
+    // register an anchor market of `stable_denom` with its aterra token
+    pub fn with_anchor_market(
+        &mut self,
+        market_addr: &str,
+        aterra_addr: &str,
+        stable_denom: &str,
+        exchange_rate: Decimal256,
+    ) {
+        let config = MarketConfigResponse {
+            owner_addr: "owner0001".to_string(),
+            aterra_contract: aterra_addr.to_string(),
+            interest_model: "interest0000".to_string(),
+            distribution_model: "distribution0000".to_string(),
+            overseer_contract: "overseer0000".to_string(),
+            collector_contract: "collector0000".to_string(),
+            distributor_contract: "distributor0000".to_string(),
+            stable_denom: stable_denom.to_string(),
+            max_borrow_factor: Decimal256::one(),
+        };
+        self.market_querier
+            .markets
+            .insert(market_addr.to_string(), (config, exchange_rate));
+        self.token_querier
+            .balances
+            .entry(aterra_addr.to_string())
+            .or_default();
+    }
+
+    // set the cw20 `token` balance of `addr`
+    pub fn with_token_balance(&mut self, token: &str, addr: &str, balance: u128) {
+        self.token_querier
+            .balances
+            .entry(token.to_string())
+            .or_default()
+            .insert(addr.to_string(), Uint128::from(balance));
+    }
+
+    // register a cw4 group at `group_addr` with `members` of weight 1
+    pub fn with_members(&mut self, group_addr: &str, members: &[&str]) {
+        self.membership_querier.groups.insert(
+            group_addr.to_string(),
+            members.iter().map(|m| (m.to_string(), 1)).collect(),
+        );
+    }
+
+    // set the price of `base` in `quote` reported by the oracle at `oracle_addr`
+    pub fn with_oracle_price(
+        &mut self,
+        oracle_addr: &str,
+        base: &str,
+        quote: &str,
+        rate: Decimal256,
+    ) {
+        self.oracle_querier
+            .prices
+            .entry(oracle_addr.to_string())
+            .or_default()
+            .insert((base.to_string(), quote.to_string()), rate);
     }
 
-    pub fn with_borrow_rate(&mut self, borrow_rate: &[(&String, &Decimal256)]) {
-        self.borrow_rate_querier = BorrowRateQuerier::new(borrow_rate);
+    // instantiate the distribution model contract at `addr`
+    pub fn with_distribution_model(&mut self, addr: &str, msg: DistributionInstantiateMsg) {
+        let mut deps = mock_base_dependencies(&[]);
+        distribution_instantiate(deps.as_mut(), mock_env(), mock_info(OWNER, &[]), msg).unwrap();
+        self.distribution_model = Some((addr.to_string(), deps));
     }
 
-    pub fn with_borrow_limit(&mut self, borrow_limit: &[(&String, &Uint256)]) {
-        self.borrow_limit_querier = BorrowLimitQuerier::new(borrow_limit);
+    fn update_staking(&mut self) {
+        self.base.update_staking(
+            &self.staking_querier.denom,
+            &self.staking_querier.validators,
+            &self.staking_querier.delegations,
+        );
     }
 }
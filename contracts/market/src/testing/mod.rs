@@ -1,2 +1,20 @@
+mod allowlist;
+mod boost;
+mod epoch;
+mod harvest;
 mod mock_querier;
+mod prize;
+mod queue;
+mod redirect;
+mod referral;
+mod rewards;
+mod settle;
+mod slashing;
+mod split;
+mod staking;
+mod strategy;
+mod streams;
+mod swap;
 mod tests;
+mod tranche;
+mod unbonding;
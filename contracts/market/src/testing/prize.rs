@@ -0,0 +1,262 @@
+use crate::contract::{execute, query_state};
+use crate::error::ContractError;
+use crate::prize::{query_draw, query_operators, query_prize};
+use crate::state::{ExecuteMsg, InstantiateMsg};
+use crate::testing::mock_querier::{
+    instantiate_market, mock_dependencies, mock_instantiate_msg, MockDeps, OWNER,
+};
+
+use cosmwasm_std::testing::{mock_env, mock_info, MOCK_CONTRACT_ADDR};
+use cosmwasm_std::{attr, BankMsg, Binary, Coin, CosmosMsg, Env, SubMsg, Uint128};
+use sha2::{Digest, Sha256};
+
+const OPERATOR: &str = "operator0000";
+const DRAW_PERIOD: u64 = 300;
+const REVEAL_PERIOD: u64 = 60;
+
+fn prize_msg() -> InstantiateMsg {
+    let mut msg = mock_instantiate_msg();
+    msg.prize_mode = Some(true);
+    msg.draw_period = Some(DRAW_PERIOD);
+    msg.reveal_period = Some(REVEAL_PERIOD);
+    msg.prize_winners = Some(1);
+    msg
+}
+
+/// Prize market with a registered operator and a single depositor
+fn prize_market() -> MockDeps {
+    let mut deps = mock_dependencies(&[]);
+    instantiate_market(&mut deps, prize_msg());
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(OWNER, &[]),
+        ExecuteMsg::RegisterOperator {
+            operator: OPERATOR.to_string(),
+        },
+    )
+    .unwrap();
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0000", &[Coin::new(1000, "uusd")]),
+        ExecuteMsg::DepositStable {
+            recipient: None,
+            referrer: None,
+        },
+    )
+    .unwrap();
+    deps
+}
+
+fn env_at(seconds: u64) -> Env {
+    let mut env = mock_env();
+    env.block.time = env.block.time.plus_seconds(seconds);
+    env
+}
+
+fn commit_msg(draw_id: u64, secret: &[u8]) -> ExecuteMsg {
+    ExecuteMsg::CommitRandomness {
+        draw_id,
+        commitment: Binary::from(Sha256::digest(secret).to_vec()),
+    }
+}
+
+#[test]
+fn prize_goes_to_the_drawn_depositor() {
+    let mut deps = prize_market();
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0000", &[]),
+        commit_msg(0, b"secret"),
+    );
+    match res {
+        Err(ContractError::Unauthorized {}) => {}
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(OPERATOR, &[]),
+        commit_msg(0, b"secret"),
+    )
+    .unwrap();
+
+    // secrets are revealed once the draw ended
+    let reveal_msg = |secret: &[u8]| ExecuteMsg::RevealRandomness {
+        draw_id: 0,
+        secret: Binary::from(secret),
+    };
+    let res = execute(
+        deps.as_mut(),
+        env_at(DRAW_PERIOD - 1),
+        mock_info(OPERATOR, &[]),
+        reveal_msg(b"secret"),
+    );
+    match res {
+        Err(ContractError::RevealNotOpen(draw_id)) => assert_eq!(draw_id, 0),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+    let res = execute(
+        deps.as_mut(),
+        env_at(DRAW_PERIOD),
+        mock_info(OPERATOR, &[]),
+        reveal_msg(b"other"),
+    );
+    match res {
+        Err(ContractError::InvalidReveal {}) => {}
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+    execute(
+        deps.as_mut(),
+        env_at(DRAW_PERIOD),
+        mock_info(OPERATOR, &[]),
+        reveal_msg(b"secret"),
+    )
+    .unwrap();
+
+    let draw_msg = ExecuteMsg::DrawPrizes { draw_id: 0 };
+    let res = execute(
+        deps.as_mut(),
+        env_at(DRAW_PERIOD),
+        mock_info("keeper0000", &[]),
+        draw_msg.clone(),
+    );
+    match res {
+        Err(ContractError::DrawNotReady(draw_id)) => assert_eq!(draw_id, 0),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    // the draw pools a draw period of interest on the deposit
+    let env = env_at(DRAW_PERIOD + REVEAL_PERIOD);
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("keeper0000", &[]),
+        draw_msg.clone(),
+    )
+    .unwrap();
+    assert!(res.attributes.contains(&attr("reveals", "1")));
+    assert!(res.attributes.contains(&attr("total_twab", "300000")));
+    assert!(res.attributes.contains(&attr("prize_per_winner", "100")));
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("keeper0000", &[]),
+        draw_msg,
+    );
+    match res {
+        Err(ContractError::DrawAwarded(draw_id)) => assert_eq!(draw_id, 0),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let award_msg = ExecuteMsg::AwardPrizes {
+        draw_id: 0,
+        limit: None,
+    };
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("keeper0000", &[]),
+        award_msg.clone(),
+    )
+    .unwrap();
+    assert!(res.attributes.contains(&attr("awarded", "100")));
+    assert!(res.attributes.contains(&attr("finished", "true")));
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("keeper0000", &[]),
+        award_msg,
+    );
+    match res {
+        Err(ContractError::DrawAwarded(draw_id)) => assert_eq!(draw_id, 0),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let draw = query_draw(deps.as_ref(), env.clone(), Some(0)).unwrap();
+    assert_eq!(draw.winners, vec!["addr0000".to_string()]);
+    assert_eq!(
+        query_prize(deps.as_ref(), env.clone(), "addr0000".to_string())
+            .unwrap()
+            .claimable,
+        Uint128::from(100u128)
+    );
+
+    deps.querier
+        .update_balance(MOCK_CONTRACT_ADDR, vec![Coin::new(1100, "uusd")]);
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("addr0000", &[]),
+        ExecuteMsg::ClaimPrize {},
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
+            to_address: "addr0000".to_string(),
+            amount: vec![Coin::new(100, "uusd")],
+        }))]
+    );
+    let res = execute(
+        deps.as_mut(),
+        env,
+        mock_info("addr0000", &[]),
+        ExecuteMsg::ClaimPrize {},
+    );
+    match res {
+        Err(ContractError::NothingToClaim {}) => {}
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+}
+
+#[test]
+fn withheld_secrets_roll_the_prize_over() {
+    let mut deps = prize_market();
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(OPERATOR, &[]),
+        commit_msg(0, b"secret"),
+    )
+    .unwrap();
+
+    // the operator never reveals and loses its registration
+    let env = env_at(DRAW_PERIOD + REVEAL_PERIOD);
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("keeper0000", &[]),
+        ExecuteMsg::DrawPrizes { draw_id: 0 },
+    )
+    .unwrap();
+    assert!(res.attributes.contains(&attr("defaulted", "1")));
+    assert!(res.attributes.contains(&attr("prize_per_winner", "0")));
+    assert!(query_draw(deps.as_ref(), env, Some(0)).unwrap().awarded);
+    assert_eq!(
+        query_operators(deps.as_ref()).unwrap().operators,
+        Vec::<String>::new()
+    );
+    assert_eq!(
+        query_state(deps.as_ref()).unwrap().prize_rollover,
+        Uint128::from(100u128)
+    );
+
+    // a draw without any commitment rolls over as well
+    let res = execute(
+        deps.as_mut(),
+        env_at(2 * DRAW_PERIOD + REVEAL_PERIOD),
+        mock_info("keeper0000", &[]),
+        ExecuteMsg::DrawPrizes { draw_id: 1 },
+    )
+    .unwrap();
+    assert!(res.attributes.contains(&attr("reveals", "0")));
+    assert!(res.attributes.contains(&attr("prize", "200")));
+    assert_eq!(
+        query_state(deps.as_ref()).unwrap().prize_rollover,
+        Uint128::from(200u128)
+    );
+}
@@ -0,0 +1,187 @@
+use crate::contract::{execute, query_state};
+use crate::error::ContractError;
+use crate::queue::query_withdrawal_queue;
+use crate::state::{Cw20HookMsg, ExecuteMsg, QueuedWithdrawalResponse};
+use crate::testing::mock_querier::{
+    instantiate_market, mock_dependencies, mock_instantiate_msg, ATERRA,
+};
+
+use cosmwasm_std::testing::{mock_env, mock_info, MOCK_CONTRACT_ADDR};
+use cosmwasm_std::{attr, to_binary, BankMsg, Coin, CosmosMsg, SubMsg, Uint128};
+use cw20::Cw20ReceiveMsg;
+
+#[test]
+fn withdrawals_queue_when_liquidity_is_short() {
+    let mut deps = mock_dependencies(&[]);
+    instantiate_market(&mut deps, mock_instantiate_msg());
+
+    deps.querier
+        .update_balance(MOCK_CONTRACT_ADDR, vec![Coin::new(1000, "uusd")]);
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0000", &[Coin::new(1000, "uusd")]),
+        ExecuteMsg::DepositStable {
+            recipient: None,
+            referrer: None,
+        },
+    )
+    .unwrap();
+
+    // the deposit left the contract
+    deps.querier.update_balance(MOCK_CONTRACT_ADDR, vec![]);
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(ATERRA, &[]),
+        ExecuteMsg::Receive(Cw20ReceiveMsg {
+            sender: "addr0000".to_string(),
+            amount: Uint128::from(1000u128),
+            msg: to_binary(&Cw20HookMsg::RedeemNStable {
+                to: None,
+                msg: None,
+            })
+            .unwrap(),
+        }),
+    )
+    .unwrap();
+    assert_eq!(res.messages.len(), 1);
+    assert!(res.attributes.contains(&attr("payout", "queued")));
+
+    let queue = query_withdrawal_queue(deps.as_ref(), "addr0000".to_string()).unwrap();
+    assert_eq!(queue.claimable, Uint128::zero());
+    assert_eq!(
+        queue.queued,
+        vec![QueuedWithdrawalResponse {
+            id: 0,
+            amount: Uint128::from(1000u128),
+            amount_ahead: Uint128::zero(),
+            requested_at: mock_env().block.time.seconds(),
+        }]
+    );
+
+    // a redemption with a callback can not wait in the queue
+    deps.querier
+        .update_balance(MOCK_CONTRACT_ADDR, vec![Coin::new(1300, "uusd")]);
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0001", &[Coin::new(1000, "uusd")]),
+        ExecuteMsg::DepositStable {
+            recipient: None,
+            referrer: None,
+        },
+    )
+    .unwrap();
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(ATERRA, &[]),
+        ExecuteMsg::Receive(Cw20ReceiveMsg {
+            sender: "addr0001".to_string(),
+            amount: Uint128::from(1000u128),
+            msg: to_binary(&Cw20HookMsg::RedeemNStable {
+                to: Some("contract0000".to_string()),
+                msg: Some(to_binary(&"callback").unwrap()),
+            })
+            .unwrap(),
+        }),
+    );
+    match res {
+        Err(ContractError::InsufficientLiquidity(denom)) => assert_eq!(denom, "uusd"),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    // the second deposit filled the queue, the rest waits for the reserve
+    let queue = query_withdrawal_queue(deps.as_ref(), "addr0000".to_string()).unwrap();
+    assert_eq!(queue.claimable, Uint128::from(1000u128));
+    assert_eq!(queue.queued, vec![]);
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0000", &[]),
+        ExecuteMsg::ClaimQueued {},
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
+            to_address: "addr0000".to_string(),
+            amount: vec![Coin::new(1000, "uusd")],
+        }))]
+    );
+    assert_eq!(
+        query_state(deps.as_ref()).unwrap().total_claimable,
+        Uint128::zero()
+    );
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0000", &[]),
+        ExecuteMsg::ClaimQueued {},
+    );
+    match res {
+        Err(ContractError::NothingToClaim {}) => {}
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+}
+
+#[test]
+fn fund_reserve_fills_queue_first() {
+    let mut deps = mock_dependencies(&[]);
+    instantiate_market(&mut deps, mock_instantiate_msg());
+
+    deps.querier
+        .update_balance(MOCK_CONTRACT_ADDR, vec![Coin::new(1000, "uusd")]);
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0000", &[Coin::new(1000, "uusd")]),
+        ExecuteMsg::DepositStable {
+            recipient: None,
+            referrer: None,
+        },
+    )
+    .unwrap();
+    deps.querier.update_balance(MOCK_CONTRACT_ADDR, vec![]);
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(ATERRA, &[]),
+        ExecuteMsg::Receive(Cw20ReceiveMsg {
+            sender: "addr0000".to_string(),
+            amount: Uint128::from(600u128),
+            msg: to_binary(&Cw20HookMsg::RedeemNStable {
+                to: None,
+                msg: None,
+            })
+            .unwrap(),
+        }),
+    )
+    .unwrap();
+
+    deps.querier
+        .update_balance(MOCK_CONTRACT_ADDR, vec![Coin::new(1000, "uusd")]);
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0001", &[Coin::new(1000, "uusd")]),
+        ExecuteMsg::FundReserve {},
+    )
+    .unwrap();
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "fund_reserve"),
+            attr("amount", "1000"),
+            attr("queue_filled", "600"),
+        ]
+    );
+
+    let state = query_state(deps.as_ref()).unwrap();
+    assert_eq!(state.reserve, Uint128::from(400u128));
+    assert_eq!(state.total_queued, Uint128::zero());
+    assert_eq!(state.total_claimable, Uint128::from(600u128));
+}
@@ -0,0 +1,128 @@
+use crate::contract::{execute, query_state, DURATION};
+use crate::error::ContractError;
+use crate::redirect::{query_incoming_redirects, query_interest_redirect};
+use crate::state::{ExecuteMsg, IncomingRedirectResponse, InterestRedirectResponse};
+use crate::testing::mock_querier::{instantiate_market, mock_dependencies, mock_instantiate_msg};
+
+use cosmwasm_std::testing::{mock_env, mock_info, MOCK_CONTRACT_ADDR};
+use cosmwasm_std::{attr, BankMsg, Coin, CosmosMsg, Decimal, SubMsg, Uint128};
+
+const BENEFICIARY: &str = "charity0000";
+
+fn redirect_msg(beneficiary: Option<&str>, share: Option<Decimal>) -> ExecuteMsg {
+    ExecuteMsg::SetInterestRedirect {
+        beneficiary: beneficiary.map(|b| b.to_string()),
+        share,
+    }
+}
+
+#[test]
+fn redirect_needs_another_beneficiary_and_a_share() {
+    let mut deps = mock_dependencies(&[]);
+    instantiate_market(&mut deps, mock_instantiate_msg());
+
+    for msg in [
+        redirect_msg(Some(BENEFICIARY), Some(Decimal::zero())),
+        redirect_msg(Some(BENEFICIARY), Some(Decimal::percent(101))),
+        redirect_msg(Some("addr0000"), None),
+    ] {
+        let res = execute(deps.as_mut(), mock_env(), mock_info("addr0000", &[]), msg);
+        match res {
+            Err(ContractError::InvalidRedirect {}) => {}
+            _ => panic!("DO NOT ENTER HERE"),
+        }
+    }
+}
+
+#[test]
+fn redirected_interest_is_claimed_by_the_beneficiary() {
+    let mut deps = mock_dependencies(&[]);
+    instantiate_market(&mut deps, mock_instantiate_msg());
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0000", &[Coin::new(1000, "uusd")]),
+        ExecuteMsg::DepositStable {
+            recipient: None,
+            referrer: None,
+        },
+    )
+    .unwrap();
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0000", &[]),
+        redirect_msg(Some(BENEFICIARY), Some(Decimal::percent(50))),
+    )
+    .unwrap();
+
+    // changing the redirect settles the interest accrued under the old one
+    let mut env = mock_env();
+    env.block.time = env.block.time.plus_seconds(DURATION);
+    execute(
+        deps.as_mut(),
+        env,
+        mock_info("addr0000", &[]),
+        redirect_msg(None, None),
+    )
+    .unwrap();
+    assert_eq!(
+        query_interest_redirect(deps.as_ref(), "addr0000".to_string()).unwrap(),
+        InterestRedirectResponse {
+            beneficiary: None,
+            share: Decimal::zero(),
+            total_redirected: Uint128::from(5u128),
+            total_received: Uint128::zero(),
+            claimable: Uint128::zero(),
+        }
+    );
+    assert_eq!(
+        query_interest_redirect(deps.as_ref(), BENEFICIARY.to_string())
+            .unwrap()
+            .claimable,
+        Uint128::from(5u128)
+    );
+    assert_eq!(
+        query_incoming_redirects(deps.as_ref(), BENEFICIARY.to_string(), None, None)
+            .unwrap()
+            .redirects,
+        vec![IncomingRedirectResponse {
+            donor: "addr0000".to_string(),
+            amount: Uint128::from(5u128),
+            active: false,
+        }]
+    );
+
+    deps.querier
+        .update_balance(MOCK_CONTRACT_ADDR, vec![Coin::new(1000, "uusd")]);
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(BENEFICIARY, &[]),
+        ExecuteMsg::ClaimRedirectedInterest {},
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
+            to_address: BENEFICIARY.to_string(),
+            amount: vec![Coin::new(5, "uusd")],
+        }))]
+    );
+    assert!(res.attributes.contains(&attr("queued", "false")));
+    assert_eq!(
+        query_state(deps.as_ref()).unwrap().total_redirect_claimable,
+        Uint128::zero()
+    );
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(BENEFICIARY, &[]),
+        ExecuteMsg::ClaimRedirectedInterest {},
+    );
+    match res {
+        Err(ContractError::NothingToClaim {}) => {}
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+}
@@ -0,0 +1,148 @@
+use crate::contract::{execute, query_state, DURATION};
+use crate::error::ContractError;
+use crate::referral::query_referral;
+use crate::state::{ExecuteMsg, ReferralResponse};
+use crate::testing::mock_querier::{
+    instantiate_market, mock_dependencies, mock_instantiate_msg, MockDeps,
+};
+
+use cosmwasm_std::testing::{mock_env, mock_info, MOCK_CONTRACT_ADDR};
+use cosmwasm_std::{attr, BankMsg, Coin, CosmosMsg, Decimal, Env, SubMsg, Uint128};
+
+const REFERRER: &str = "referrer0000";
+
+fn referral_market() -> MockDeps {
+    let mut deps = mock_dependencies(&[]);
+    let mut msg = mock_instantiate_msg();
+    msg.referral_share = Some(Decimal::percent(50));
+    instantiate_market(&mut deps, msg);
+    deps
+}
+
+fn deposit(deps: &mut MockDeps, env: Env, sender: &str, recipient: Option<&str>, referrer: &str) {
+    execute(
+        deps.as_mut(),
+        env,
+        mock_info(sender, &[Coin::new(1000, "uusd")]),
+        ExecuteMsg::DepositStable {
+            recipient: recipient.map(|r| r.to_string()),
+            referrer: Some(referrer.to_string()),
+        },
+    )
+    .unwrap();
+}
+
+#[test]
+fn only_depositors_record_their_referrer() {
+    let mut deps = referral_market();
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0000", &[Coin::new(1000, "uusd")]),
+        ExecuteMsg::DepositStable {
+            recipient: None,
+            referrer: Some("addr0000".to_string()),
+        },
+    );
+    match res {
+        Err(ContractError::SelfReferral {}) => {}
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    // a funder can not pick the referrer of the recipient
+    deposit(
+        &mut deps,
+        mock_env(),
+        "addr0000",
+        Some("addr0001"),
+        REFERRER,
+    );
+    assert_eq!(
+        query_referral(deps.as_ref(), REFERRER.to_string())
+            .unwrap()
+            .referrals,
+        0
+    );
+
+    deposit(&mut deps, mock_env(), "addr0001", None, REFERRER);
+    assert_eq!(
+        query_referral(deps.as_ref(), REFERRER.to_string())
+            .unwrap()
+            .referrals,
+        1
+    );
+
+    // the first referrer sticks
+    deposit(&mut deps, mock_env(), "addr0001", None, "referrer0001");
+    assert_eq!(
+        query_referral(deps.as_ref(), "referrer0001".to_string())
+            .unwrap()
+            .referrals,
+        0
+    );
+}
+
+#[test]
+fn referrers_claim_a_share_of_interest() {
+    let mut deps = referral_market();
+    deposit(&mut deps, mock_env(), "addr0000", None, REFERRER);
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0001", &[Coin::new(100, "uusd")]),
+        ExecuteMsg::FundReserve {},
+    )
+    .unwrap();
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(REFERRER, &[]),
+        ExecuteMsg::ClaimReferralRewards {},
+    );
+    match res {
+        Err(ContractError::NothingToClaim {}) => {}
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    // the next interaction accrues a day of interest
+    let mut env = mock_env();
+    env.block.time = env.block.time.plus_seconds(DURATION);
+    deposit(&mut deps, env, "addr0000", None, REFERRER);
+    assert_eq!(
+        query_referral(deps.as_ref(), REFERRER.to_string()).unwrap(),
+        ReferralResponse {
+            referrer: REFERRER.to_string(),
+            referrals: 1,
+            referred_interest: Uint128::from(10u128),
+            total_earned: Uint128::from(5u128),
+            pending: Uint128::from(5u128),
+        }
+    );
+    let state = query_state(deps.as_ref()).unwrap();
+    assert_eq!(state.reserve, Uint128::from(95u128));
+    assert_eq!(state.total_referral_pending, Uint128::from(5u128));
+
+    deps.querier
+        .update_balance(MOCK_CONTRACT_ADDR, vec![Coin::new(2100, "uusd")]);
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(REFERRER, &[]),
+        ExecuteMsg::ClaimReferralRewards {},
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
+            to_address: REFERRER.to_string(),
+            amount: vec![Coin::new(5, "uusd")],
+        }))]
+    );
+    assert!(res.attributes.contains(&attr("queued", "false")));
+    assert_eq!(
+        query_state(deps.as_ref()).unwrap().total_referral_pending,
+        Uint128::zero()
+    );
+}
@@ -0,0 +1,142 @@
+use crate::contract::execute;
+use crate::error::ContractError;
+use crate::rewards::query_pending_rewards;
+use crate::state::ExecuteMsg;
+use crate::testing::mock_querier::{
+    instantiate_market, mock_dependencies, mock_instantiate_msg, MockDeps,
+};
+
+use cosmwasm_std::testing::{mock_env, mock_info};
+use cosmwasm_std::{to_binary, Coin, CosmosMsg, Decimal, Env, SubMsg, Uint128, WasmMsg};
+use cw20::Cw20ExecuteMsg;
+
+const REWARD_TOKEN: &str = "reward0000";
+
+/// Market emitting 10 reward tokens per second
+fn rewards_market() -> MockDeps {
+    let mut deps = mock_dependencies(&[]);
+    let mut msg = mock_instantiate_msg();
+    msg.reward_token = Some(REWARD_TOKEN.to_string());
+    msg.emission_rate = Some(Decimal::percent(1000));
+    instantiate_market(&mut deps, msg);
+    deps
+}
+
+fn deposit(deps: &mut MockDeps, sender: &str, amount: u128) {
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(sender, &[Coin::new(amount, "uusd")]),
+        ExecuteMsg::DepositStable {
+            recipient: None,
+            referrer: None,
+        },
+    )
+    .unwrap();
+}
+
+fn seconds_later(seconds: u64) -> Env {
+    let mut env = mock_env();
+    env.block.time = env.block.time.plus_seconds(seconds);
+    env
+}
+
+fn pending(deps: &MockDeps, env: Env, address: &str) -> Uint128 {
+    query_pending_rewards(deps.as_ref(), env, address.to_string())
+        .unwrap()
+        .pending_rewards
+}
+
+#[test]
+fn claim_rewards_needs_a_reward_token() {
+    let mut deps = mock_dependencies(&[]);
+    instantiate_market(&mut deps, mock_instantiate_msg());
+    deposit(&mut deps, "addr0000", 1000);
+
+    let res = execute(
+        deps.as_mut(),
+        seconds_later(100),
+        mock_info("addr0000", &[]),
+        ExecuteMsg::ClaimRewards { to: None },
+    );
+    match res {
+        Err(ContractError::RewardsDisabled {}) => {}
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+}
+
+#[test]
+fn rewards_accrue_pro_rata_and_reset_on_claim() {
+    let mut deps = rewards_market();
+    deposit(&mut deps, "addr0000", 1000);
+    deposit(&mut deps, "addr0001", 3000);
+    assert_eq!(pending(&deps, mock_env(), "addr0000"), Uint128::zero());
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0000", &[]),
+        ExecuteMsg::ClaimRewards { to: None },
+    );
+    match res {
+        Err(ContractError::NothingToClaim {}) => {}
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    // 1000 tokens emitted over 100 seconds, shared by balance
+    assert_eq!(
+        pending(&deps, seconds_later(100), "addr0000"),
+        Uint128::from(250u128)
+    );
+    assert_eq!(
+        pending(&deps, seconds_later(100), "addr0001"),
+        Uint128::from(750u128)
+    );
+
+    let res = execute(
+        deps.as_mut(),
+        seconds_later(100),
+        mock_info("addr0000", &[]),
+        ExecuteMsg::ClaimRewards {
+            to: Some("addr0002".to_string()),
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: REWARD_TOKEN.to_string(),
+            funds: vec![],
+            msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                recipient: "addr0002".to_string(),
+                amount: Uint128::from(250u128),
+            })
+            .unwrap(),
+        }))]
+    );
+
+    // only the claimer's rewards are reset
+    assert_eq!(
+        pending(&deps, seconds_later(100), "addr0000"),
+        Uint128::zero()
+    );
+    assert_eq!(
+        pending(&deps, seconds_later(100), "addr0001"),
+        Uint128::from(750u128)
+    );
+    let res = execute(
+        deps.as_mut(),
+        seconds_later(100),
+        mock_info("addr0000", &[]),
+        ExecuteMsg::ClaimRewards { to: None },
+    );
+    match res {
+        Err(ContractError::NothingToClaim {}) => {}
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    assert_eq!(
+        pending(&deps, seconds_later(200), "addr0000"),
+        Uint128::from(250u128)
+    );
+}
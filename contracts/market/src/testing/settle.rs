@@ -0,0 +1,221 @@
+use crate::contract::{execute, query_ident, query_state, DURATION};
+use crate::state::ExecuteMsg;
+use crate::testing::mock_querier::{
+    instantiate_market, mock_dependencies, mock_instantiate_msg, MockDeps, ATERRA,
+};
+
+use cosmwasm_std::testing::{mock_env, mock_info, MOCK_CONTRACT_ADDR};
+use cosmwasm_std::{
+    attr, from_binary, BankMsg, Coin, CosmosMsg, Env, Response, SubMsg, Uint128, WasmMsg,
+};
+use cw20::Cw20ExecuteMsg;
+
+const DEPOSITORS: [&str; 3] = ["addr0000", "addr0001", "addr0002"];
+
+fn settle_market() -> MockDeps {
+    let mut deps = mock_dependencies(&[]);
+    instantiate_market(&mut deps, mock_instantiate_msg());
+    for depositor in DEPOSITORS {
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(depositor, &[Coin::new(1000, "uusd")]),
+            ExecuteMsg::DepositStable {
+                recipient: None,
+                referrer: None,
+            },
+        )
+        .unwrap();
+    }
+    deps
+}
+
+fn days_later(days: u64) -> Env {
+    let mut env = mock_env();
+    env.block.time = env.block.time.plus_seconds(days * DURATION);
+    env
+}
+
+/// Receipt tokens minted by `res`, by recipient
+fn minted(res: &Response) -> Vec<(String, Uint128)> {
+    res.messages
+        .iter()
+        .filter_map(|msg| match &msg.msg {
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr, msg, ..
+            }) if contract_addr == ATERRA => match from_binary(msg).unwrap() {
+                Cw20ExecuteMsg::Mint { recipient, amount } => Some((recipient, amount)),
+                _ => None,
+            },
+            _ => None,
+        })
+        .collect()
+}
+
+#[test]
+fn settle_mints_the_accrued_interest() {
+    let mut deps = settle_market();
+    let settle_msg = |addresses: &[&str]| ExecuteMsg::Settle {
+        addresses: addresses.iter().map(|a| a.to_string()).collect(),
+    };
+
+    // nothing accrues within a day, nor for unknown depositors
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("keeper0000", &[]),
+        settle_msg(&["addr0000", "addr0003"]),
+    )
+    .unwrap();
+    assert!(res.messages.is_empty());
+    assert!(res.attributes.contains(&attr("settled", "0")));
+
+    let res = execute(
+        deps.as_mut(),
+        days_later(1),
+        mock_info("keeper0000", &[]),
+        settle_msg(&["addr0000", "addr0001", "addr0003"]),
+    )
+    .unwrap();
+    assert_eq!(
+        minted(&res),
+        vec![
+            ("addr0000".to_string(), Uint128::from(10u128)),
+            ("addr0001".to_string(), Uint128::from(10u128)),
+        ]
+    );
+    assert!(res.attributes.contains(&attr("settled", "2")));
+    assert!(res.attributes.contains(&attr("interest", "20")));
+
+    // settled depositors accrue again from the settlement
+    let res = execute(
+        deps.as_mut(),
+        days_later(1),
+        mock_info("keeper0000", &[]),
+        settle_msg(&["addr0000", "addr0001", "addr0002"]),
+    )
+    .unwrap();
+    assert_eq!(
+        minted(&res),
+        vec![("addr0002".to_string(), Uint128::from(10u128))]
+    );
+}
+
+#[test]
+fn settle_range_pages_through_the_depositors() {
+    let mut deps = settle_market();
+    let mut settle_range = |start_after: Option<String>, limit: Option<u32>| {
+        execute(
+            deps.as_mut(),
+            days_later(1),
+            mock_info("keeper0000", &[]),
+            ExecuteMsg::SettleRange { start_after, limit },
+        )
+        .unwrap()
+    };
+
+    let res = settle_range(None, Some(2));
+    let mut settled = minted(&res);
+    assert_eq!(settled.len(), 2);
+    assert!(res.attributes.contains(&attr("settled", "2")));
+    let last = res
+        .attributes
+        .iter()
+        .find(|a| a.key == "last")
+        .map(|a| a.value.clone())
+        .unwrap();
+    assert_eq!(last, settled[1].0);
+
+    // the cursor itself is not settled again
+    let res = settle_range(Some(last.clone()), Some(2));
+    settled.extend(minted(&res));
+    assert!(res.attributes.contains(&attr("settled", "1")));
+    let last = res
+        .attributes
+        .iter()
+        .find(|a| a.key == "last")
+        .map(|a| a.value.clone())
+        .unwrap();
+
+    settled.sort();
+    assert_eq!(
+        settled,
+        DEPOSITORS
+            .iter()
+            .map(|d| (d.to_string(), Uint128::from(10u128)))
+            .collect::<Vec<_>>()
+    );
+
+    // past the last depositor the page is empty
+    let res = settle_range(Some(last), None);
+    assert!(res.messages.is_empty());
+    assert!(res.attributes.contains(&attr("settled", "0")));
+    assert!(!res.attributes.iter().any(|a| a.key == "last"));
+}
+
+#[test]
+fn interest_payouts_replace_compounding() {
+    let mut deps = settle_market();
+    deps.querier
+        .update_balance(MOCK_CONTRACT_ADDR, vec![Coin::new(3000, "uusd")]);
+    let set_payout = |deps: &mut MockDeps, env: Env, to: Option<&str>| {
+        execute(
+            deps.as_mut(),
+            env,
+            mock_info("addr0000", &[]),
+            ExecuteMsg::SetInterestPayout {
+                to: to.map(|to| to.to_string()),
+            },
+        )
+        .unwrap()
+    };
+    let res = set_payout(&mut deps, mock_env(), Some("addr0009"));
+    assert!(res.attributes.contains(&attr("payout_to", "addr0009")));
+
+    let settle_msg = ExecuteMsg::Settle {
+        addresses: vec!["addr0000".to_string()],
+    };
+    let res = execute(
+        deps.as_mut(),
+        days_later(1),
+        mock_info("keeper0000", &[]),
+        settle_msg.clone(),
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
+            to_address: "addr0009".to_string(),
+            amount: vec![Coin::new(10, "uusd")],
+        }))]
+    );
+    assert!(res.attributes.contains(&attr("interest", "0")));
+    assert!(res.attributes.contains(&attr("interest_paid", "10")));
+
+    let time = days_later(1).block.time.seconds();
+    let deposit = query_ident(deps.as_ref(), "addr0000".to_string(), time).unwrap();
+    assert_eq!(deposit.last_balance, Uint128::from(1000u128));
+    assert_eq!(deposit.unpaid_interest, Uint128::zero());
+    assert_eq!(
+        query_state(deps.as_ref()).unwrap().total_unpaid_interest,
+        Uint128::zero()
+    );
+
+    // without a payout address the interest compounds again
+    set_payout(&mut deps, days_later(1), None);
+    let res = execute(
+        deps.as_mut(),
+        days_later(2),
+        mock_info("keeper0000", &[]),
+        settle_msg,
+    )
+    .unwrap();
+    assert_eq!(
+        minted(&res),
+        vec![("addr0000".to_string(), Uint128::from(10u128))]
+    );
+    assert!(!res
+        .messages
+        .iter()
+        .any(|msg| matches!(msg.msg, CosmosMsg::Bank(_))));
+}
@@ -0,0 +1,116 @@
+use crate::contract::{execute, query_state};
+use crate::error::ContractError;
+use crate::state::ExecuteMsg;
+use crate::testing::staking::{native_staking_market, native_staking_msg};
+
+use cosmwasm_std::testing::{mock_env, mock_info};
+use cosmwasm_std::{Coin, CosmosMsg, Decimal, Event, StakingMsg, SubMsg, Uint128};
+
+#[test]
+fn slashed_stake_is_socialized() {
+    let mut deps = native_staking_market(native_staking_msg());
+
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0000", &[Coin::new(1000, "uusd")]),
+        ExecuteMsg::DepositStable {
+            recipient: None,
+            referrer: None,
+        },
+    )
+    .unwrap();
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0001", &[Coin::new(100, "uusd")]),
+        ExecuteMsg::FundReserve {},
+    )
+    .unwrap();
+
+    // nothing happens while the delegations match
+    deps.querier.with_delegation("validator0001", 1000, &[]);
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("keeper0000", &[]),
+        ExecuteMsg::Reconcile {},
+    )
+    .unwrap();
+    assert!(res.messages.is_empty() && res.events.is_empty());
+
+    // the reserve restores what it can, depositors take the rest
+    deps.querier.with_delegation("validator0001", 700, &[]);
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("keeper0000", &[]),
+        ExecuteMsg::Reconcile {},
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::new(CosmosMsg::Staking(StakingMsg::Delegate {
+            validator: "validator0000".to_string(),
+            amount: Coin::new(100, "uusd"),
+        }))]
+    );
+    assert_eq!(
+        res.events,
+        vec![Event::new("slash")
+            .add_attribute("shortfall", "300")
+            .add_attribute("absorbed", "0")
+            .add_attribute("covered", "100")
+            .add_attribute("socialized", "200")
+            .add_attribute("exchange_rate", "0.8")]
+    );
+
+    let state = query_state(deps.as_ref()).unwrap();
+    assert_eq!(state.exchange_rate, Decimal::percent(80));
+    assert_eq!(state.reserve, Uint128::zero());
+    assert_eq!(state.total_delegated, Uint128::from(800u128));
+    assert_eq!(state.total_slashed, Uint128::from(300u128));
+}
+
+#[test]
+fn insolvent_market_rejects_deposits() {
+    let mut deps = native_staking_market(native_staking_msg());
+
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0000", &[Coin::new(1000, "uusd")]),
+        ExecuteMsg::DepositStable {
+            recipient: None,
+            referrer: None,
+        },
+    )
+    .unwrap();
+
+    deps.querier.with_delegation("validator0001", 0, &[]);
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("keeper0000", &[]),
+        ExecuteMsg::Reconcile {},
+    )
+    .unwrap();
+    assert_eq!(
+        query_state(deps.as_ref()).unwrap().exchange_rate,
+        Decimal::zero()
+    );
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0001", &[Coin::new(1000, "uusd")]),
+        ExecuteMsg::DepositStable {
+            recipient: None,
+            referrer: None,
+        },
+    );
+    match res {
+        Err(ContractError::Insolvent {}) => {}
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+}
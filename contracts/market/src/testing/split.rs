@@ -0,0 +1,312 @@
+use crate::contract::{execute, reply, DURATION};
+use crate::error::ContractError;
+use crate::split::query_maturity;
+use crate::state::{Cw20HookMsg, ExecuteMsg};
+use crate::testing::mock_querier::{
+    instantiate_market, instantiate_reply, mock_dependencies, mock_instantiate_msg, MockDeps,
+    ATERRA, OWNER,
+};
+
+use cosmwasm_std::testing::{mock_env, mock_info, MOCK_CONTRACT_ADDR};
+use cosmwasm_std::{
+    from_binary, to_binary, Coin, CosmosMsg, Env, Response, SubMsg, Uint128, WasmMsg,
+};
+use cw20::{Cw20ExecuteMsg, Cw20ReceiveMsg, MinterResponse};
+use terraswap::token::InstantiateMsg as TokenInstantiateMsg;
+
+const PRINCIPAL: &str = "principal0000";
+const YIELD: &str = "yield0000";
+
+fn maturity() -> u64 {
+    mock_env().block.time.seconds() + 10 * DURATION
+}
+
+/// Market with a listed maturity and a deposit of 1000
+fn split_market() -> MockDeps {
+    let mut deps = mock_dependencies(&[]);
+    instantiate_market(&mut deps, mock_instantiate_msg());
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(OWNER, &[]),
+        ExecuteMsg::AddMaturity {
+            maturity: maturity(),
+            token_code_id: 456,
+        },
+    )
+    .unwrap();
+    reply(deps.as_mut(), mock_env(), instantiate_reply(2, PRINCIPAL)).unwrap();
+    reply(deps.as_mut(), mock_env(), instantiate_reply(3, YIELD)).unwrap();
+
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0000", &[Coin::new(1000, "uusd")]),
+        ExecuteMsg::DepositStable {
+            recipient: None,
+            referrer: None,
+        },
+    )
+    .unwrap();
+    deps
+}
+
+fn receive(
+    deps: &mut MockDeps,
+    env: Env,
+    token: &str,
+    amount: u128,
+    msg: Cw20HookMsg,
+) -> Result<Response, ContractError> {
+    execute(
+        deps.as_mut(),
+        env,
+        mock_info(token, &[]),
+        ExecuteMsg::Receive(Cw20ReceiveMsg {
+            sender: "addr0000".to_string(),
+            amount: Uint128::from(amount),
+            msg: to_binary(&msg).unwrap(),
+        }),
+    )
+}
+
+fn token_msg(token: &str, msg: Cw20ExecuteMsg) -> SubMsg {
+    SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+        contract_addr: token.to_string(),
+        funds: vec![],
+        msg: to_binary(&msg).unwrap(),
+    }))
+}
+
+#[test]
+fn add_maturity_instantiates_its_tokens() {
+    let mut deps = mock_dependencies(&[]);
+    instantiate_market(&mut deps, mock_instantiate_msg());
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0000", &[]),
+        ExecuteMsg::AddMaturity {
+            maturity: maturity(),
+            token_code_id: 456,
+        },
+    );
+    match res {
+        Err(ContractError::Unauthorized {}) => {}
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(OWNER, &[]),
+        ExecuteMsg::AddMaturity {
+            maturity: mock_env().block.time.seconds(),
+            token_code_id: 456,
+        },
+    );
+    match res {
+        Err(ContractError::InvalidMaturity {}) => {}
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    // symbols carry the maturity day in letters
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(OWNER, &[]),
+        ExecuteMsg::AddMaturity {
+            maturity: maturity(),
+            token_code_id: 456,
+        },
+    )
+    .unwrap();
+    let token_msg = |name: String, symbol: &str, id: u64| {
+        SubMsg::reply_on_success(
+            CosmosMsg::Wasm(WasmMsg::Instantiate {
+                admin: None,
+                code_id: 456,
+                funds: vec![],
+                label: "".to_string(),
+                msg: to_binary(&TokenInstantiateMsg {
+                    name,
+                    symbol: symbol.to_string(),
+                    decimals: 6u8,
+                    initial_balances: vec![],
+                    mint: Some(MinterResponse {
+                        minter: MOCK_CONTRACT_ADDR.to_string(),
+                        cap: None,
+                    }),
+                })
+                .unwrap(),
+            }),
+            id,
+        )
+    };
+    assert_eq!(
+        res.messages,
+        vec![
+            token_msg(
+                format!("xyz USD principal {}", maturity()),
+                "pxyzUS-BAXS",
+                2
+            ),
+            token_msg(format!("xyz USD yield {}", maturity()), "yxyzUS-BAXS", 3),
+        ]
+    );
+
+    reply(deps.as_mut(), mock_env(), instantiate_reply(2, PRINCIPAL)).unwrap();
+    reply(deps.as_mut(), mock_env(), instantiate_reply(3, YIELD)).unwrap();
+    let res = query_maturity(deps.as_ref(), maturity()).unwrap();
+    assert_eq!(res.principal_token, PRINCIPAL);
+    assert_eq!(res.yield_token, YIELD);
+}
+
+#[test]
+fn maturity_symbols_fit_longer_denoms() {
+    let mut deps = mock_dependencies(&[]);
+    let mut msg = mock_instantiate_msg();
+    msg.stable_denom = "uluna".to_string();
+    instantiate_market(&mut deps, msg);
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(OWNER, &[]),
+        ExecuteMsg::AddMaturity {
+            maturity: maturity(),
+            token_code_id: 456,
+        },
+    )
+    .unwrap();
+    let symbols: Vec<String> = res
+        .messages
+        .iter()
+        .map(|msg| match &msg.msg {
+            CosmosMsg::Wasm(WasmMsg::Instantiate { msg, .. }) => {
+                from_binary::<TokenInstantiateMsg>(msg).unwrap().symbol
+            }
+            _ => panic!("DO NOT ENTER HERE"),
+        })
+        .collect();
+    assert_eq!(symbols, vec!["pxyzLUN-BAXS", "yxyzLUN-BAXS"]);
+    assert!(symbols.iter().all(|symbol| symbol.len() <= 12));
+}
+
+#[test]
+fn split_tokens_recombine_through_escrow() {
+    let mut deps = split_market();
+
+    let res = receive(
+        &mut deps,
+        mock_env(),
+        "token0000",
+        400,
+        Cw20HookMsg::Split {
+            maturity: maturity(),
+        },
+    );
+    match res {
+        Err(ContractError::Unauthorized {}) => {}
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+    let res = receive(
+        &mut deps,
+        mock_env(),
+        ATERRA,
+        400,
+        Cw20HookMsg::Split {
+            maturity: maturity(),
+        },
+    )
+    .unwrap();
+    let mint = |token: &str| {
+        token_msg(
+            token,
+            Cw20ExecuteMsg::Mint {
+                recipient: "addr0000".to_string(),
+                amount: Uint128::from(400u128),
+            },
+        )
+    };
+    assert_eq!(res.messages, vec![mint(PRINCIPAL), mint(YIELD)]);
+
+    // a lone principal token waits in escrow for a yield token
+    let recombine_msg = || Cw20HookMsg::Recombine {
+        maturity: maturity(),
+    };
+    let res = receive(&mut deps, mock_env(), PRINCIPAL, 300, recombine_msg()).unwrap();
+    assert!(res.messages.is_empty());
+    let res = receive(&mut deps, mock_env(), YIELD, 200, recombine_msg()).unwrap();
+    let burn = |token: &str| {
+        token_msg(
+            token,
+            Cw20ExecuteMsg::Burn {
+                amount: Uint128::from(200u128),
+            },
+        )
+    };
+    assert_eq!(
+        res.messages,
+        vec![
+            burn(PRINCIPAL),
+            burn(YIELD),
+            token_msg(
+                ATERRA,
+                Cw20ExecuteMsg::Transfer {
+                    recipient: "addr0000".to_string(),
+                    amount: Uint128::from(200u128),
+                },
+            ),
+        ]
+    );
+    let res = query_maturity(deps.as_ref(), maturity()).unwrap();
+    assert_eq!(res.balance, Uint128::from(200u128));
+    assert_eq!(res.principal, Uint128::from(200u128));
+
+    let res = receive(&mut deps, mock_env(), "token0000", 100, recombine_msg());
+    match res {
+        Err(ContractError::Unauthorized {}) => {}
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    // the unmatched principal tokens can be taken back
+    let cancel_msg = ExecuteMsg::CancelRecombine {
+        maturity: maturity(),
+    };
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0000", &[]),
+        cancel_msg.clone(),
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages,
+        vec![token_msg(
+            PRINCIPAL,
+            Cw20ExecuteMsg::Transfer {
+                recipient: "addr0000".to_string(),
+                amount: Uint128::from(100u128),
+            },
+        )]
+    );
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0000", &[]),
+        cancel_msg,
+    );
+    match res {
+        Err(ContractError::NothingToClaim {}) => {}
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let mut env = mock_env();
+    env.block.time = env.block.time.plus_seconds(10 * DURATION);
+    let res = receive(&mut deps, env, PRINCIPAL, 100, recombine_msg());
+    match res {
+        Err(ContractError::Matured(time)) => assert_eq!(time, maturity()),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+}
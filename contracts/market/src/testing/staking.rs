@@ -0,0 +1,465 @@
+use crate::contract::{execute, instantiate, query_state};
+use crate::error::ContractError;
+use crate::staking::query_validators;
+use crate::state::{Cw20HookMsg, ExecuteMsg, InstantiateMsg, UnbondingEntryResponse};
+use crate::testing::mock_querier::{
+    instantiate_market, mock_dependencies, mock_instantiate_msg, MockDeps, ATERRA, OWNER,
+};
+use crate::unbonding::query_unbonding;
+
+use cosmwasm_std::testing::{mock_env, mock_info, MOCK_CONTRACT_ADDR};
+use cosmwasm_std::{
+    attr, to_binary, BankMsg, Coin, CosmosMsg, Response, StakingMsg, SubMsg, Uint128,
+};
+use cw20::Cw20ReceiveMsg;
+
+const UNBONDING_PERIOD: u64 = 21 * 86400;
+
+/// Native staking market with two registered validators
+pub fn native_staking_market(msg: InstantiateMsg) -> MockDeps {
+    let mut deps = mock_dependencies(&[]);
+    deps.querier
+        .with_validators("uusd", &["validator0000", "validator0001"]);
+    instantiate_market(&mut deps, msg);
+
+    for validator in ["validator0000", "validator0001"] {
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(OWNER, &[]),
+            ExecuteMsg::RegisterValidator {
+                validator: validator.to_string(),
+                weight: None,
+            },
+        )
+        .unwrap();
+    }
+    deps
+}
+
+fn deposit(deps: &mut MockDeps, sender: &str, amount: u128) {
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(sender, &[Coin::new(amount, "uusd")]),
+        ExecuteMsg::DepositStable {
+            recipient: None,
+            referrer: None,
+        },
+    )
+    .unwrap();
+}
+
+fn owner_execute(deps: &mut MockDeps, msg: ExecuteMsg) -> Response {
+    execute(deps.as_mut(), mock_env(), mock_info(OWNER, &[]), msg).unwrap()
+}
+
+/// Accounted delegation of every validator
+fn delegations(deps: &MockDeps) -> Vec<(String, u128)> {
+    query_validators(deps.as_ref(), mock_env())
+        .unwrap()
+        .validators
+        .into_iter()
+        .map(|v| (v.address, v.delegated.u128()))
+        .collect()
+}
+
+fn redelegate_msg(src: &str, dst: &str, amount: u128) -> SubMsg {
+    SubMsg::new(CosmosMsg::Staking(StakingMsg::Redelegate {
+        src_validator: src.to_string(),
+        dst_validator: dst.to_string(),
+        amount: Coin::new(amount, "uusd"),
+    }))
+}
+
+pub fn native_staking_msg() -> InstantiateMsg {
+    let mut msg = mock_instantiate_msg();
+    msg.native_staking = Some(true);
+    msg.unbonding_period = Some(UNBONDING_PERIOD);
+    msg
+}
+
+#[test]
+fn native_staking_validates_the_staking_module() {
+    let mut deps = mock_dependencies(&[]);
+    deps.querier.with_validators("uluna", &["validator0000"]);
+    let res = instantiate(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(OWNER, &[Coin::new(1000000, "uusd")]),
+        native_staking_msg(),
+    );
+    match res {
+        Err(ContractError::InvalidNativeStaking(denom)) => assert_eq!(denom, "uluna"),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    // redemptions must not be paid before the chain releases the stake
+    let mut deps = mock_dependencies(&[]);
+    deps.querier.with_validators("uusd", &["validator0000"]);
+    let mut msg = native_staking_msg();
+    msg.unbonding_period = Some(86400);
+    let res = instantiate(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(OWNER, &[Coin::new(1000000, "uusd")]),
+        msg,
+    );
+    match res {
+        Err(ContractError::UnbondingPeriodTooShort(period)) => {
+            assert_eq!(period, UNBONDING_PERIOD)
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    // nor before the batch of the next epoch is released
+    let mut msg = native_staking_msg();
+    msg.epoch_period = Some(86400);
+    let res = instantiate(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(OWNER, &[Coin::new(1000000, "uusd")]),
+        msg,
+    );
+    match res {
+        Err(ContractError::UnbondingPeriodTooShort(period)) => {
+            assert_eq!(period, UNBONDING_PERIOD + 86400)
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+}
+
+#[test]
+fn register_unknown_validator() {
+    let mut deps = native_staking_market(native_staking_msg());
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(OWNER, &[]),
+        ExecuteMsg::RegisterValidator {
+            validator: "validator0002".to_string(),
+            weight: None,
+        },
+    );
+    match res {
+        Err(ContractError::ValidatorNotFound(validator)) => {
+            assert_eq!(validator, "validator0002")
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+}
+
+#[test]
+fn deposits_delegate_and_redemptions_unbond() {
+    let mut deps = native_staking_market(native_staking_msg());
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0000", &[Coin::new(1000, "uusd")]),
+        ExecuteMsg::DepositStable {
+            recipient: None,
+            referrer: None,
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages[0],
+        SubMsg::new(CosmosMsg::Staking(StakingMsg::Delegate {
+            validator: "validator0001".to_string(),
+            amount: Coin::new(1000, "uusd"),
+        }))
+    );
+
+    // the next deposit goes to the validator furthest below its target
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0001", &[Coin::new(600, "uusd")]),
+        ExecuteMsg::DepositStable {
+            recipient: None,
+            referrer: None,
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages[0],
+        SubMsg::new(CosmosMsg::Staking(StakingMsg::Delegate {
+            validator: "validator0000".to_string(),
+            amount: Coin::new(600, "uusd"),
+        }))
+    );
+
+    // redemptions only record the stake to undelegate
+    let redeem_msg = || {
+        ExecuteMsg::Receive(Cw20ReceiveMsg {
+            sender: "addr0000".to_string(),
+            amount: Uint128::from(200u128),
+            msg: to_binary(&Cw20HookMsg::RedeemNStable {
+                to: None,
+                msg: None,
+            })
+            .unwrap(),
+        })
+    };
+    for _ in 0..2 {
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(ATERRA, &[]),
+            redeem_msg(),
+        )
+        .unwrap();
+        assert!(res
+            .messages
+            .iter()
+            .all(|msg| !matches!(msg.msg, CosmosMsg::Staking(_))));
+    }
+    assert_eq!(
+        query_state(deps.as_ref()).unwrap().pending_undelegation,
+        Uint128::from(400u128)
+    );
+
+    // the epoch undelegates them in a single batch
+    deps.querier.with_delegation("validator0000", 600, &[]);
+    deps.querier.with_delegation("validator0001", 1000, &[]);
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("keeper0000", &[]),
+        ExecuteMsg::ExecuteEpochOperations {},
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::new(CosmosMsg::Staking(StakingMsg::Undelegate {
+            validator: "validator0001".to_string(),
+            amount: Coin::new(400, "uusd"),
+        }))]
+    );
+    let state = query_state(deps.as_ref()).unwrap();
+    assert_eq!(state.pending_undelegation, Uint128::zero());
+    assert_eq!(state.total_delegated, Uint128::from(1200u128));
+
+    let env = mock_env();
+    let release_time = env.block.time.seconds() + UNBONDING_PERIOD;
+    let unbonding = query_unbonding(deps.as_ref(), env, "addr0000".to_string()).unwrap();
+    let entry = UnbondingEntryResponse {
+        recipient: "addr0000".to_string(),
+        amount: Uint128::from(200u128),
+        release_time,
+    };
+    assert_eq!(unbonding.unbondings, vec![entry.clone(), entry]);
+    assert_eq!(unbonding.claimable, Uint128::zero());
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0000", &[]),
+        ExecuteMsg::ClaimUnbonded {},
+    );
+    match res {
+        Err(ContractError::NothingToClaim {}) => {}
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    // the undelegated stake arrives once the chain releases it
+    deps.querier
+        .update_balance(MOCK_CONTRACT_ADDR, vec![Coin::new(400, "uusd")]);
+    let mut env = mock_env();
+    env.block.time = env.block.time.plus_seconds(UNBONDING_PERIOD);
+    let res = execute(
+        deps.as_mut(),
+        env,
+        mock_info("addr0000", &[]),
+        ExecuteMsg::ClaimUnbonded {},
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
+            to_address: "addr0000".to_string(),
+            amount: vec![Coin::new(400, "uusd")],
+        }))]
+    );
+}
+
+#[test]
+fn rebalance_moves_at_most_max_rebalance() {
+    let mut msg = native_staking_msg();
+    msg.max_rebalance = Some(Uint128::from(300u128));
+    let mut deps = native_staking_market(msg);
+    deposit(&mut deps, "addr0000", 1000);
+    assert_eq!(
+        delegations(&deps),
+        vec![
+            ("validator0000".to_string(), 0),
+            ("validator0001".to_string(), 1000)
+        ]
+    );
+
+    let res = owner_execute(&mut deps, ExecuteMsg::Rebalance {});
+    assert_eq!(
+        res.messages,
+        vec![redelegate_msg("validator0001", "validator0000", 300)]
+    );
+    assert!(res.attributes.contains(&attr("redelegated", "300")));
+
+    let res = owner_execute(&mut deps, ExecuteMsg::Rebalance {});
+    assert_eq!(
+        res.messages,
+        vec![redelegate_msg("validator0001", "validator0000", 200)]
+    );
+    assert_eq!(
+        delegations(&deps),
+        vec![
+            ("validator0000".to_string(), 500),
+            ("validator0001".to_string(), 500)
+        ]
+    );
+
+    // balanced validators are left alone
+    let res = owner_execute(&mut deps, ExecuteMsg::Rebalance {});
+    assert!(res.messages.is_empty());
+    assert!(res.attributes.contains(&attr("redelegated", "0")));
+}
+
+#[test]
+fn validator_weights_set_the_targets() {
+    let mut deps = native_staking_market(native_staking_msg());
+    deposit(&mut deps, "addr0000", 1000);
+    owner_execute(&mut deps, ExecuteMsg::Rebalance {});
+
+    let update_msg = |validator: &str| ExecuteMsg::UpdateValidatorWeight {
+        validator: validator.to_string(),
+        weight: 3,
+    };
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0000", &[]),
+        update_msg("validator0000"),
+    );
+    match res {
+        Err(ContractError::Unauthorized {}) => {}
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(OWNER, &[]),
+        update_msg("validator0002"),
+    );
+    match res {
+        Err(ContractError::ValidatorNotFound(validator)) => {
+            assert_eq!(validator, "validator0002")
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    // the weight only moves the target, stake follows on rebalance
+    owner_execute(&mut deps, update_msg("validator0000"));
+    let validators = query_validators(deps.as_ref(), mock_env())
+        .unwrap()
+        .validators;
+    assert_eq!(validators[0].weight, 3);
+    assert_eq!(validators[0].target, Uint128::from(750u128));
+    assert_eq!(validators[0].delegated, Uint128::from(500u128));
+    assert_eq!(validators[1].target, Uint128::from(250u128));
+
+    let res = owner_execute(&mut deps, ExecuteMsg::Rebalance {});
+    assert_eq!(
+        res.messages,
+        vec![redelegate_msg("validator0001", "validator0000", 250)]
+    );
+
+    // new deposits follow the weights as well
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0001", &[Coin::new(400, "uusd")]),
+        ExecuteMsg::DepositStable {
+            recipient: None,
+            referrer: None,
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages[0],
+        SubMsg::new(CosmosMsg::Staking(StakingMsg::Delegate {
+            validator: "validator0000".to_string(),
+            amount: Coin::new(400, "uusd"),
+        }))
+    );
+}
+
+#[test]
+fn removed_validators_stake_is_redelegated() {
+    let mut deps = native_staking_market(native_staking_msg());
+    deps.querier
+        .with_validators("uusd", &["validator0000", "validator0001", "validator0002"]);
+    owner_execute(
+        &mut deps,
+        ExecuteMsg::RegisterValidator {
+            validator: "validator0002".to_string(),
+            weight: None,
+        },
+    );
+    deposit(&mut deps, "addr0000", 900);
+    assert_eq!(delegations(&deps)[2], ("validator0002".to_string(), 900));
+
+    let remove_msg = |validator: &str| ExecuteMsg::RemoveValidator {
+        validator: validator.to_string(),
+    };
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0000", &[]),
+        remove_msg("validator0002"),
+    );
+    match res {
+        Err(ContractError::Unauthorized {}) => {}
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    // the stake is split over the remaining validators' deficits
+    let res = owner_execute(&mut deps, remove_msg("validator0002"));
+    assert_eq!(
+        res.messages,
+        vec![
+            redelegate_msg("validator0002", "validator0000", 450),
+            redelegate_msg("validator0002", "validator0001", 450),
+        ]
+    );
+    assert!(res.attributes.contains(&attr("redelegated", "900")));
+    assert_eq!(
+        delegations(&deps),
+        vec![
+            ("validator0000".to_string(), 450),
+            ("validator0001".to_string(), 450)
+        ]
+    );
+    assert_eq!(
+        query_state(deps.as_ref()).unwrap().total_delegated,
+        Uint128::from(900u128)
+    );
+
+    // stake can't be moved to validators without weight
+    owner_execute(
+        &mut deps,
+        ExecuteMsg::UpdateValidatorWeight {
+            validator: "validator0000".to_string(),
+            weight: 0,
+        },
+    );
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(OWNER, &[]),
+        remove_msg("validator0001"),
+    );
+    match res {
+        Err(ContractError::NoValidators {}) => {}
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+}
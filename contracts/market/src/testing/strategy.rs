@@ -0,0 +1,328 @@
+use crate::contract::{execute, query_state};
+use crate::error::ContractError;
+use crate::queue::query_withdrawal_queue;
+use crate::state::{Cw20HookMsg, ExecuteMsg, StrategyKind, StrategyResponse};
+use crate::strategy::query_strategies;
+use crate::testing::mock_querier::{
+    instantiate_market, mock_dependencies, mock_instantiate_msg, MockDeps, ATERRA, OWNER,
+};
+use crate::testing::staking::{native_staking_market, native_staking_msg};
+
+use cosmwasm_bignumber::Decimal256;
+use cosmwasm_std::testing::{mock_env, mock_info, MOCK_CONTRACT_ADDR};
+use cosmwasm_std::{attr, to_binary, BankMsg, Coin, CosmosMsg, Decimal, SubMsg, Uint128, WasmMsg};
+use cw20::{Cw20ExecuteMsg, Cw20ReceiveMsg};
+use moneymarket::market::{Cw20HookMsg as MarketHookMsg, ExecuteMsg as MarketExecuteMsg};
+
+const MARKET: &str = "market0000";
+const MARKET_ATERRA: &str = "aterra0001";
+
+fn add_anchor_msg(market: &str) -> ExecuteMsg {
+    ExecuteMsg::AddStrategy {
+        contract_addr: market.to_string(),
+        kind: StrategyKind::Anchor,
+        weight: 1,
+    }
+}
+
+/// Market keeping a 20% liquidity buffer with an anchor strategy
+fn strategy_market() -> MockDeps {
+    let mut deps = mock_dependencies(&[]);
+    let mut msg = mock_instantiate_msg();
+    msg.liquidity_buffer = Some(Decimal::percent(20));
+    instantiate_market(&mut deps, msg);
+    deps.querier
+        .with_anchor_market(MARKET, MARKET_ATERRA, "uusd", Decimal256::percent(125));
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(OWNER, &[]),
+        add_anchor_msg(MARKET),
+    )
+    .unwrap();
+    deps
+}
+
+#[test]
+fn add_strategy_checks_the_market() {
+    let mut deps = strategy_market();
+    deps.querier
+        .with_anchor_market("market0001", "aterra0002", "ukrw", Decimal256::one());
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0000", &[]),
+        add_anchor_msg("market0001"),
+    );
+    match res {
+        Err(ContractError::Unauthorized {}) => {}
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(OWNER, &[]),
+        add_anchor_msg("market0001"),
+    );
+    match res {
+        Err(ContractError::InvalidStrategy(market)) => assert_eq!(market, "market0001"),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(OWNER, &[]),
+        add_anchor_msg(MARKET),
+    );
+    match res {
+        Err(ContractError::StrategyExists(market)) => assert_eq!(market, MARKET),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    // delegated funds can not be put into strategies
+    let mut deps = native_staking_market(native_staking_msg());
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(OWNER, &[]),
+        add_anchor_msg(MARKET),
+    );
+    match res {
+        Err(ContractError::StrategyWithNativeStaking {}) => {}
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+}
+
+#[test]
+fn allocate_keeps_the_buffer() {
+    let mut deps = strategy_market();
+    deps.querier
+        .update_balance(MOCK_CONTRACT_ADDR, vec![Coin::new(1000, "uusd")]);
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("keeper0000", &[]),
+        ExecuteMsg::Allocate {},
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: MARKET.to_string(),
+            funds: vec![Coin::new(800, "uusd")],
+            msg: to_binary(&MarketExecuteMsg::DepositStable {}).unwrap(),
+        }))]
+    );
+    assert!(res.attributes.contains(&attr("allocated", "800")));
+
+    // the deposit went through, nothing is left to allocate
+    deps.querier
+        .update_balance(MOCK_CONTRACT_ADDR, vec![Coin::new(200, "uusd")]);
+    deps.querier
+        .with_token_balance(MARKET_ATERRA, MOCK_CONTRACT_ADDR, 640);
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("keeper0000", &[]),
+        ExecuteMsg::Allocate {},
+    )
+    .unwrap();
+    assert!(res.messages.is_empty());
+    assert_eq!(
+        query_strategies(deps.as_ref(), mock_env())
+            .unwrap()
+            .strategies,
+        vec![StrategyResponse {
+            contract_addr: MARKET.to_string(),
+            kind: StrategyKind::Anchor,
+            weight: 1,
+            target: Uint128::from(800u128),
+            principal: Uint128::from(800u128),
+            value: Uint128::from(800u128),
+        }]
+    );
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(OWNER, &[]),
+        ExecuteMsg::RemoveStrategy {
+            contract_addr: MARKET.to_string(),
+        },
+    );
+    match res {
+        Err(ContractError::StrategyPositionOpen(market)) => assert_eq!(market, MARKET),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+}
+
+#[test]
+fn skim_yield_moves_yield_to_the_reserve() {
+    let mut deps = strategy_market();
+    deps.querier
+        .update_balance(MOCK_CONTRACT_ADDR, vec![Coin::new(1000, "uusd")]);
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("keeper0000", &[]),
+        ExecuteMsg::Allocate {},
+    )
+    .unwrap();
+    deps.querier
+        .update_balance(MOCK_CONTRACT_ADDR, vec![Coin::new(200, "uusd")]);
+    deps.querier
+        .with_token_balance(MARKET_ATERRA, MOCK_CONTRACT_ADDR, 640);
+
+    // the position earned 160 without any interest owed to depositors
+    deps.querier
+        .with_anchor_market(MARKET, MARKET_ATERRA, "uusd", Decimal256::percent(150));
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("keeper0000", &[]),
+        ExecuteMsg::SkimYield {},
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: MARKET_ATERRA.to_string(),
+            funds: vec![],
+            msg: to_binary(&Cw20ExecuteMsg::Send {
+                contract: MARKET.to_string(),
+                amount: Uint128::from(107u128),
+                msg: to_binary(&MarketHookMsg::RedeemStable {}).unwrap(),
+            })
+            .unwrap(),
+        }))]
+    );
+    assert!(res.attributes.contains(&attr("earned", "160")));
+    assert!(res.attributes.contains(&attr("spread", "160")));
+    assert_eq!(
+        query_state(deps.as_ref()).unwrap().reserve,
+        Uint128::from(160u128)
+    );
+
+    // a loss is covered by the reserve before touching depositors
+    deps.querier
+        .with_token_balance(MARKET_ATERRA, MOCK_CONTRACT_ADDR, 533);
+    deps.querier
+        .with_anchor_market(MARKET, MARKET_ATERRA, "uusd", Decimal256::percent(125));
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("keeper0000", &[]),
+        ExecuteMsg::SkimYield {},
+    )
+    .unwrap();
+    assert!(res.messages.is_empty());
+    assert!(res.attributes.contains(&attr("lost", "134")));
+    assert!(res.attributes.contains(&attr("covered", "134")));
+    assert!(res.attributes.contains(&attr("socialized", "0")));
+
+    let state = query_state(deps.as_ref()).unwrap();
+    assert_eq!(state.reserve, Uint128::from(26u128));
+    assert_eq!(state.exchange_rate, Decimal::one());
+}
+
+#[test]
+fn strategies_serve_redemptions_and_the_queue() {
+    let mut deps = strategy_market();
+    deps.querier
+        .update_balance(MOCK_CONTRACT_ADDR, vec![Coin::new(1000, "uusd")]);
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0000", &[Coin::new(1000, "uusd")]),
+        ExecuteMsg::DepositStable {
+            recipient: None,
+            referrer: None,
+        },
+    )
+    .unwrap();
+
+    // the strategy lost part of the allocation
+    deps.querier
+        .update_balance(MOCK_CONTRACT_ADDR, vec![Coin::new(200, "uusd")]);
+    deps.querier
+        .with_token_balance(MARKET_ATERRA, MOCK_CONTRACT_ADDR, 400);
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(ATERRA, &[]),
+        ExecuteMsg::Receive(Cw20ReceiveMsg {
+            sender: "addr0000".to_string(),
+            amount: Uint128::from(1000u128),
+            msg: to_binary(&Cw20HookMsg::RedeemNStable {
+                to: None,
+                msg: None,
+            })
+            .unwrap(),
+        }),
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages[1..],
+        [
+            SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: MARKET_ATERRA.to_string(),
+                funds: vec![],
+                msg: to_binary(&Cw20ExecuteMsg::Send {
+                    contract: MARKET.to_string(),
+                    amount: Uint128::from(400u128),
+                    msg: to_binary(&MarketHookMsg::RedeemStable {}).unwrap(),
+                })
+                .unwrap(),
+            })),
+            SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
+                to_address: "addr0000".to_string(),
+                amount: vec![Coin::new(700, "uusd")],
+            })),
+        ]
+    );
+    assert!(res.attributes.contains(&attr("payout", "partially_queued")));
+
+    let queue = query_withdrawal_queue(deps.as_ref(), "addr0000".to_string()).unwrap();
+    assert_eq!(queue.queued[0].amount, Uint128::from(300u128));
+    assert_eq!(
+        query_state(deps.as_ref()).unwrap().total_queued,
+        Uint128::from(300u128)
+    );
+
+    // once the strategy holds enough again the queue is filled from it
+    deps.querier.update_balance(MOCK_CONTRACT_ADDR, vec![]);
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0000", &[]),
+        ExecuteMsg::ClaimQueued {},
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages,
+        vec![
+            SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: MARKET_ATERRA.to_string(),
+                funds: vec![],
+                msg: to_binary(&Cw20ExecuteMsg::Send {
+                    contract: MARKET.to_string(),
+                    amount: Uint128::from(241u128),
+                    msg: to_binary(&MarketHookMsg::RedeemStable {}).unwrap(),
+                })
+                .unwrap(),
+            })),
+            SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
+                to_address: "addr0000".to_string(),
+                amount: vec![Coin::new(300, "uusd")],
+            })),
+        ]
+    );
+
+    let state = query_state(deps.as_ref()).unwrap();
+    assert_eq!(state.total_queued, Uint128::zero());
+    assert_eq!(state.total_claimable, Uint128::zero());
+}
@@ -0,0 +1,256 @@
+use crate::contract::execute;
+use crate::error::ContractError;
+use crate::state::{Cw20HookMsg, ExecuteMsg, PendingStreamRewardResponse, StreamResponse};
+use crate::streams::{query_pending_stream_rewards, query_streams};
+use crate::testing::mock_querier::{
+    instantiate_market, mock_dependencies, mock_instantiate_msg, MockDeps, OWNER,
+};
+
+use cosmwasm_std::testing::{mock_env, mock_info};
+use cosmwasm_std::{to_binary, BankMsg, Coin, CosmosMsg, SubMsg, Uint128};
+use cw20::Cw20ReceiveMsg;
+use terraswap::asset::AssetInfo;
+
+const SPONSOR: &str = "sponsor0000";
+const DURATION: u64 = 1000;
+
+fn stream_market() -> MockDeps {
+    let mut deps = mock_dependencies(&[]);
+    instantiate_market(&mut deps, mock_instantiate_msg());
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(OWNER, &[]),
+        ExecuteMsg::AddSponsors {
+            addresses: vec![SPONSOR.to_string()],
+        },
+    )
+    .unwrap();
+    deps
+}
+
+fn create_stream_msg(start_time: u64, end_time: u64) -> ExecuteMsg {
+    ExecuteMsg::CreateStream {
+        start_time,
+        end_time,
+    }
+}
+
+#[test]
+fn only_sponsors_create_streams() {
+    let mut deps = stream_market();
+    let now = mock_env().block.time.seconds();
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0000", &[Coin::new(1000, "ukrw")]),
+        create_stream_msg(now, now + DURATION),
+    );
+    match res {
+        Err(ContractError::Unauthorized {}) => {}
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    // streams can neither start in the past nor hold the slot for years
+    for (start_time, end_time) in [
+        (now - 1, now + DURATION),
+        (now, now),
+        (now, now + 2 * 365 * 86400 + 1),
+    ] {
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(SPONSOR, &[Coin::new(1000, "ukrw")]),
+            create_stream_msg(start_time, end_time),
+        );
+        match res {
+            Err(ContractError::InvalidStream(asset)) => assert_eq!(asset, "ukrw"),
+            _ => panic!("DO NOT ENTER HERE"),
+        }
+    }
+
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(SPONSOR, &[Coin::new(1000, "ukrw")]),
+        create_stream_msg(now, now + DURATION),
+    )
+    .unwrap();
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("token0000", &[]),
+        ExecuteMsg::Receive(Cw20ReceiveMsg {
+            sender: SPONSOR.to_string(),
+            amount: Uint128::from(500u128),
+            msg: to_binary(&Cw20HookMsg::CreateStream {
+                start_time: now + DURATION,
+                end_time: now + 2 * DURATION,
+            })
+            .unwrap(),
+        }),
+    )
+    .unwrap();
+    assert_eq!(
+        query_streams(deps.as_ref()).unwrap().streams,
+        vec![
+            StreamResponse {
+                id: 0,
+                sponsor: SPONSOR.to_string(),
+                asset: AssetInfo::NativeToken {
+                    denom: "ukrw".to_string(),
+                },
+                budget: Uint128::from(1000u128),
+                start_time: now,
+                end_time: now + DURATION,
+                claimed: Uint128::zero(),
+            },
+            StreamResponse {
+                id: 1,
+                sponsor: SPONSOR.to_string(),
+                asset: AssetInfo::Token {
+                    contract_addr: "token0000".to_string(),
+                },
+                budget: Uint128::from(500u128),
+                start_time: now + DURATION,
+                end_time: now + 2 * DURATION,
+                claimed: Uint128::zero(),
+            },
+        ]
+    );
+
+    // removed sponsors can not open new streams
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(OWNER, &[]),
+        ExecuteMsg::RemoveSponsors {
+            addresses: vec![SPONSOR.to_string()],
+        },
+    )
+    .unwrap();
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(SPONSOR, &[Coin::new(1000, "ukrw")]),
+        create_stream_msg(now, now + DURATION),
+    );
+    match res {
+        Err(ContractError::Unauthorized {}) => {}
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+}
+
+#[test]
+fn stream_rewards_are_claimed_then_refunded() {
+    let mut deps = stream_market();
+    let now = mock_env().block.time.seconds();
+
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0000", &[Coin::new(1000, "uusd")]),
+        ExecuteMsg::DepositStable {
+            recipient: None,
+            referrer: None,
+        },
+    )
+    .unwrap();
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(SPONSOR, &[Coin::new(1000, "ukrw")]),
+        create_stream_msg(now, now + DURATION),
+    )
+    .unwrap();
+
+    // half of the budget has been emitted to the only depositor
+    let mut env = mock_env();
+    env.block.time = env.block.time.plus_seconds(DURATION / 2);
+    let pending =
+        query_pending_stream_rewards(deps.as_ref(), env.clone(), "addr0000".to_string()).unwrap();
+    assert_eq!(
+        pending.rewards,
+        vec![PendingStreamRewardResponse {
+            id: 0,
+            asset: AssetInfo::NativeToken {
+                denom: "ukrw".to_string(),
+            },
+            pending: Uint128::from(500u128),
+        }]
+    );
+
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("addr0000", &[]),
+        ExecuteMsg::ClaimStreamRewards {
+            ids: None,
+            to: None,
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
+            to_address: "addr0000".to_string(),
+            amount: vec![Coin::new(500, "ukrw")],
+        }))]
+    );
+    let res = execute(
+        deps.as_mut(),
+        env,
+        mock_info("addr0000", &[]),
+        ExecuteMsg::ClaimStreamRewards {
+            ids: None,
+            to: None,
+        },
+    );
+    match res {
+        Err(ContractError::NothingToClaim {}) => {}
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    // the stream stays claimable for a while after it ends
+    let mut env = mock_env();
+    env.block.time = env.block.time.plus_seconds(DURATION);
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("addr0001", &[]),
+        ExecuteMsg::CloseStream { id: 0 },
+    );
+    match res {
+        Err(ContractError::StreamActive(id)) => assert_eq!(id, 0),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    env.block.time = env.block.time.plus_seconds(30 * 86400);
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("addr0001", &[]),
+        ExecuteMsg::CloseStream { id: 0 },
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
+            to_address: SPONSOR.to_string(),
+            amount: vec![Coin::new(500, "ukrw")],
+        }))]
+    );
+    assert_eq!(query_streams(deps.as_ref()).unwrap().streams, vec![]);
+
+    let res = execute(
+        deps.as_mut(),
+        env,
+        mock_info("addr0001", &[]),
+        ExecuteMsg::CloseStream { id: 0 },
+    );
+    match res {
+        Err(ContractError::StreamNotFound(id)) => assert_eq!(id, 0),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+}
@@ -0,0 +1,255 @@
+use crate::contract::{execute, query_state};
+use crate::error::ContractError;
+use crate::state::{ExecuteMsg, SwapRouteResponse};
+use crate::swap::query_swap_routes;
+use crate::testing::mock_querier::{
+    instantiate_market, mock_dependencies, mock_instantiate_msg, OWNER,
+};
+use crate::testing::staking::{native_staking_market, native_staking_msg};
+
+use cosmwasm_bignumber::Decimal256;
+use cosmwasm_std::testing::{mock_env, mock_info, MOCK_CONTRACT_ADDR};
+use cosmwasm_std::{
+    to_binary, Coin, CosmosMsg, Decimal, DistributionMsg, StakingMsg, SubMsg, Uint128, WasmMsg,
+};
+use terraswap::asset::{Asset, AssetInfo};
+use terraswap::pair::ExecuteMsg as PairExecuteMsg;
+
+const ORACLE: &str = "oracle0000";
+
+fn swap_route_msg(denom: &str) -> ExecuteMsg {
+    ExecuteMsg::SetSwapRoute {
+        denom: denom.to_string(),
+        pair_addr: "pair0000".to_string(),
+        max_spread: Decimal::percent(10),
+        oracle_addr: ORACLE.to_string(),
+    }
+}
+
+fn swap_msg(amount: u128) -> SubMsg {
+    SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+        contract_addr: "pair0000".to_string(),
+        funds: vec![Coin::new(amount, "ukrw")],
+        msg: to_binary(&PairExecuteMsg::Swap {
+            offer_asset: Asset {
+                info: AssetInfo::NativeToken {
+                    denom: "ukrw".to_string(),
+                },
+                amount: Uint128::from(amount),
+            },
+            belief_price: Some(Decimal::from_ratio(10u128, 1u128)),
+            max_spread: Some(Decimal::percent(10)),
+            to: None,
+        })
+        .unwrap(),
+    }))
+}
+
+#[test]
+fn swap_routes_need_a_matching_pair() {
+    let mut deps = mock_dependencies(&[]);
+    instantiate_market(&mut deps, mock_instantiate_msg());
+    deps.querier
+        .with_pair("pair0000", ["ukrw", "uusd"], Decimal::percent(10));
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0000", &[]),
+        swap_route_msg("ukrw"),
+    );
+    match res {
+        Err(ContractError::Unauthorized {}) => {}
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    for denom in ["uluna", "uusd"] {
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(OWNER, &[]),
+            swap_route_msg(denom),
+        );
+        match res {
+            Err(ContractError::InvalidSwapRoute(invalid)) => assert_eq!(invalid, denom),
+            _ => panic!("DO NOT ENTER HERE"),
+        }
+    }
+
+    // the oracle must price the denom
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(OWNER, &[]),
+        swap_route_msg("ukrw"),
+    );
+    match res {
+        Err(ContractError::InvalidSwapRoute(invalid)) => assert_eq!(invalid, "ukrw"),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    deps.querier
+        .with_oracle_price(ORACLE, "ukrw", "uusd", Decimal256::percent(10));
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(OWNER, &[]),
+        swap_route_msg("ukrw"),
+    )
+    .unwrap();
+    assert_eq!(
+        query_swap_routes(deps.as_ref()).unwrap().routes,
+        vec![SwapRouteResponse {
+            denom: "ukrw".to_string(),
+            pair_addr: "pair0000".to_string(),
+            max_spread: Decimal::percent(10),
+            oracle_addr: ORACLE.to_string(),
+        }]
+    );
+
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(OWNER, &[]),
+        ExecuteMsg::RemoveSwapRoute {
+            denom: "ukrw".to_string(),
+        },
+    )
+    .unwrap();
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(OWNER, &[]),
+        ExecuteMsg::RemoveSwapRoute {
+            denom: "ukrw".to_string(),
+        },
+    );
+    match res {
+        Err(ContractError::SwapRouteNotFound(denom)) => assert_eq!(denom, "ukrw"),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+}
+
+#[test]
+fn harvest_swaps_routed_rewards() {
+    let mut deps = native_staking_market(native_staking_msg());
+    deps.querier
+        .with_pair("pair0000", ["ukrw", "uusd"], Decimal::percent(10));
+    deps.querier
+        .with_oracle_price(ORACLE, "ukrw", "uusd", Decimal256::percent(10));
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(OWNER, &[]),
+        swap_route_msg("ukrw"),
+    )
+    .unwrap();
+
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0000", &[Coin::new(1000, "uusd")]),
+        ExecuteMsg::DepositStable {
+            recipient: None,
+            referrer: None,
+        },
+    )
+    .unwrap();
+
+    // the swapped rewards count at the return after the max spread
+    deps.querier.with_delegation(
+        "validator0001",
+        1000,
+        &[Coin::new(100, "uusd"), Coin::new(1000, "ukrw")],
+    );
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("keeper0000", &[]),
+        ExecuteMsg::Harvest {},
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages,
+        vec![
+            SubMsg::new(CosmosMsg::Distribution(
+                DistributionMsg::WithdrawDelegatorReward {
+                    validator: "validator0001".to_string(),
+                }
+            )),
+            swap_msg(1000),
+            SubMsg::new(CosmosMsg::Staking(StakingMsg::Delegate {
+                validator: "validator0000".to_string(),
+                amount: Coin::new(190, "uusd"),
+            })),
+        ]
+    );
+
+    let state = query_state(deps.as_ref()).unwrap();
+    assert_eq!(state.exchange_rate, Decimal::percent(119));
+    assert_eq!(state.total_harvested, Uint128::from(190u128));
+}
+
+#[test]
+fn sweep_funds_the_reserve() {
+    let mut deps = mock_dependencies(&[]);
+    instantiate_market(&mut deps, mock_instantiate_msg());
+    // the pool's price can be moved within the block, the oracle's can not
+    deps.querier
+        .with_pair("pair0000", ["uusd", "ukrw"], Decimal::percent(20));
+    deps.querier
+        .with_oracle_price(ORACLE, "ukrw", "uusd", Decimal256::percent(10));
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("keeper0000", &[]),
+        ExecuteMsg::Sweep {
+            denom: "ukrw".to_string(),
+        },
+    );
+    match res {
+        Err(ContractError::SwapRouteNotFound(denom)) => assert_eq!(denom, "ukrw"),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(OWNER, &[]),
+        swap_route_msg("ukrw"),
+    )
+    .unwrap();
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("keeper0000", &[]),
+        ExecuteMsg::Sweep {
+            denom: "ukrw".to_string(),
+        },
+    );
+    match res {
+        Err(ContractError::NothingToSweep(denom)) => assert_eq!(denom, "ukrw"),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let reserve = query_state(deps.as_ref()).unwrap().reserve;
+    deps.querier.update_balance(
+        MOCK_CONTRACT_ADDR,
+        vec![Coin::new(1000, "uusd"), Coin::new(500, "ukrw")],
+    );
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("keeper0000", &[]),
+        ExecuteMsg::Sweep {
+            denom: "ukrw".to_string(),
+        },
+    )
+    .unwrap();
+    assert_eq!(res.messages, vec![swap_msg(500)]);
+    assert_eq!(
+        query_state(deps.as_ref()).unwrap().reserve,
+        reserve + Uint128::from(45u128)
+    );
+}
@@ -1,31 +1,147 @@
+use crate::contract::{execute, query_ident};
+use crate::error::ContractError;
 use crate::helpers::calculate_accrued_interest;
-use crate::state::DepositInfo;
-
-use cosmwasm_std::{
-    attr, from_binary, to_binary, Addr, BankMsg, Coin, ContractResult, CosmosMsg, Decimal, Reply,
-    StdError, StdResult, SubMsg, SubMsgExecutionResponse, Uint128, WasmMsg,
+use crate::state::{DepositInfo, ExecuteMsg};
+use crate::testing::mock_querier::{
+    instantiate_market, mock_dependencies, mock_instantiate_msg, MockDeps, ATERRA, OWNER,
 };
-use serde_json::*;
+
+use cosmwasm_std::testing::{mock_env, mock_info};
+use cosmwasm_std::{attr, to_binary, Coin, CosmosMsg, Decimal, Response, SubMsg, Uint128, WasmMsg};
+use cw20::Cw20ExecuteMsg;
 use std::str::FromStr;
 
+fn deposit(
+    deps: &mut MockDeps,
+    sender: &str,
+    funds: &[Coin],
+    recipient: Option<&str>,
+) -> Result<Response, ContractError> {
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(sender, funds),
+        ExecuteMsg::DepositStable {
+            recipient: recipient.map(|r| r.to_string()),
+            referrer: None,
+        },
+    )
+}
+
 #[test]
 fn redeem_stable() {
     let last_balance: u64 = 8000000;
     let rate: String = "0.000382982750338989".to_string();
     let decimals = Decimal::from_str(&rate).unwrap();
-    println!("{:?}", decimals);
-    let accrued_interest = calculate_accrued_interest(
-        &DepositInfo {
-            interested_balance: Uint128::zero(),
-            last_interaction: 1,
-            last_balance: Uint128::from(last_balance),
-            initial_interaction: 1,
+    let deposit = DepositInfo {
+        last_interaction: 1,
+        last_balance: Uint128::from(last_balance),
+        initial_interaction: 1,
+        ..DepositInfo::default()
+    };
+
+    // no interest before a full day passed
+    let accrued_interest = calculate_accrued_interest(&deposit, decimals, 0).unwrap();
+    assert_eq!(accrued_interest, Uint128::zero());
+
+    let accrued_interest = calculate_accrued_interest(&deposit, decimals, 1).unwrap();
+    assert_eq!(accrued_interest, Uint128::from(3063u128));
+}
+
+#[test]
+fn deposit_limits() {
+    let mut deps = mock_dependencies(&[]);
+    let mut msg = mock_instantiate_msg();
+    msg.min_deposit = Some(Uint128::from(100u128));
+    msg.max_balance = Some(Uint128::from(1000u128));
+    msg.tvl_cap = Some(Uint128::from(2000u128));
+    instantiate_market(&mut deps, msg);
+
+    let res = deposit(&mut deps, "addr0000", &[Coin::new(1000, "ukrw")], None);
+    match res {
+        Err(ContractError::ZeroDeposit(denom)) => assert_eq!(denom, "uusd"),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+    let res = deposit(&mut deps, "addr0000", &[Coin::new(99, "uusd")], None);
+    match res {
+        Err(ContractError::DepositTooSmall(min_deposit, denom)) => {
+            assert_eq!(min_deposit, 100);
+            assert_eq!(denom, "uusd");
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    // the balance cap counts what the depositor already holds
+    deposit(&mut deps, "addr0000", &[Coin::new(900, "uusd")], None).unwrap();
+    let res = deposit(&mut deps, "addr0000", &[Coin::new(200, "uusd")], None);
+    match res {
+        Err(ContractError::MaxBalanceExceeded(max_balance)) => assert_eq!(max_balance, 1000),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    // the tvl cap counts every depositor
+    deposit(&mut deps, "addr0001", &[Coin::new(1000, "uusd")], None).unwrap();
+    let res = deposit(&mut deps, "addr0002", &[Coin::new(200, "uusd")], None);
+    match res {
+        Err(ContractError::TvlCapExceeded(tvl_cap)) => assert_eq!(tvl_cap, 2000),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+    deposit(&mut deps, "addr0002", &[Coin::new(100, "uusd")], None).unwrap();
+}
+
+#[test]
+fn deposits_credit_the_recipient() {
+    let mut deps = mock_dependencies(&[]);
+    let mut msg = mock_instantiate_msg();
+    msg.permissioned = Some(true);
+    instantiate_market(&mut deps, msg);
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(OWNER, &[]),
+        ExecuteMsg::AddToAllowlist {
+            addresses: vec!["addr0001".to_string()],
         },
-        decimals,
-        0,
+    )
+    .unwrap();
+
+    // the recipient must be allowlisted, the funder need not be
+    let res = deposit(
+        &mut deps,
+        "addr0001",
+        &[Coin::new(1000, "uusd")],
+        Some("addr0002"),
     );
+    match res {
+        Err(ContractError::NotAllowlisted(ident)) => assert_eq!(ident, "addr0002"),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+    let res = deposit(
+        &mut deps,
+        "addr0000",
+        &[Coin::new(1000, "uusd")],
+        Some("addr0001"),
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: ATERRA.to_string(),
+            funds: vec![],
+            msg: to_binary(&Cw20ExecuteMsg::Mint {
+                recipient: "addr0001".to_string(),
+                amount: Uint128::from(1000u128),
+            })
+            .unwrap(),
+        }))]
+    );
+    assert!(res.attributes.contains(&attr("funder", "addr0000")));
+    assert!(res.attributes.contains(&attr("beneficiary", "addr0001")));
 
-    // println!("{}", json!(accrued_interest).to_string());
-    println!("{:?}", accrued_interest);
+    let time = mock_env().block.time.seconds();
+    let recipient = query_ident(deps.as_ref(), "addr0001".to_string(), time).unwrap();
+    assert_eq!(recipient.last_balance, Uint128::from(1000u128));
+    assert_eq!(recipient.sum_deposits, Uint128::from(1000u128));
+    let funder = query_ident(deps.as_ref(), "addr0000".to_string(), time).unwrap();
+    assert_eq!(funder.last_balance, Uint128::zero());
 }
-
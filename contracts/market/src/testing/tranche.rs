@@ -0,0 +1,346 @@
+use crate::contract::{execute, reply, DURATION};
+use crate::error::ContractError;
+use crate::state::{Cw20HookMsg, ExecuteMsg, StrategyKind, TranchesResponse};
+use crate::testing::mock_querier::{
+    instantiate_market, instantiate_reply, mock_dependencies, mock_instantiate_msg, MockDeps, OWNER,
+};
+use crate::tranche::query_tranches;
+
+use cosmwasm_bignumber::Decimal256;
+use cosmwasm_std::testing::{mock_env, mock_info, MOCK_CONTRACT_ADDR};
+use cosmwasm_std::{attr, to_binary, BankMsg, Coin, CosmosMsg, Decimal, SubMsg, Uint128, WasmMsg};
+use cw20::{Cw20ExecuteMsg, Cw20ReceiveMsg, MinterResponse};
+use moneymarket::market::{Cw20HookMsg as MarketHookMsg, ExecuteMsg as MarketExecuteMsg};
+use std::str::FromStr;
+use terraswap::token::InstantiateMsg as TokenInstantiateMsg;
+
+const JUNIOR: &str = "junior0000";
+const MARKET: &str = "market0000";
+const MARKET_ATERRA: &str = "aterra0001";
+
+fn enable_msg() -> ExecuteMsg {
+    ExecuteMsg::EnableJuniorTranche { token_code_id: 456 }
+}
+
+/// Market allocating everything to an anchor strategy, with a junior tranche
+fn tranche_market() -> MockDeps {
+    let mut deps = mock_dependencies(&[]);
+    instantiate_market(&mut deps, mock_instantiate_msg());
+    deps.querier
+        .with_anchor_market(MARKET, MARKET_ATERRA, "uusd", Decimal256::percent(125));
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(OWNER, &[]),
+        ExecuteMsg::AddStrategy {
+            contract_addr: MARKET.to_string(),
+            kind: StrategyKind::Anchor,
+            weight: 1,
+        },
+    )
+    .unwrap();
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(OWNER, &[]),
+        enable_msg(),
+    )
+    .unwrap();
+    reply(deps.as_mut(), mock_env(), instantiate_reply(4, JUNIOR)).unwrap();
+    deps
+}
+
+fn deposit_junior(deps: &mut MockDeps, sender: &str, amount: u128) -> Vec<SubMsg> {
+    deps.querier
+        .update_balance(MOCK_CONTRACT_ADDR, vec![Coin::new(amount, "uusd")]);
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(sender, &[Coin::new(amount, "uusd")]),
+        ExecuteMsg::DepositJunior {},
+    )
+    .unwrap()
+    .messages
+}
+
+fn strategy_deposit_msg(amount: u128) -> SubMsg {
+    SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+        contract_addr: MARKET.to_string(),
+        funds: vec![Coin::new(amount, "uusd")],
+        msg: to_binary(&MarketExecuteMsg::DepositStable {}).unwrap(),
+    }))
+}
+
+fn junior_msg(msg: Cw20ExecuteMsg) -> SubMsg {
+    SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+        contract_addr: JUNIOR.to_string(),
+        funds: vec![],
+        msg: to_binary(&msg).unwrap(),
+    }))
+}
+
+#[test]
+fn enable_junior_tranche_instantiates_its_token() {
+    let mut deps = mock_dependencies(&[]);
+    instantiate_market(&mut deps, mock_instantiate_msg());
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0000", &[Coin::new(1000, "uusd")]),
+        ExecuteMsg::DepositJunior {},
+    );
+    match res {
+        Err(ContractError::JuniorTrancheDisabled {}) => {}
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0000", &[]),
+        enable_msg(),
+    );
+    match res {
+        Err(ContractError::Unauthorized {}) => {}
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(OWNER, &[]),
+        enable_msg(),
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::reply_on_success(
+            CosmosMsg::Wasm(WasmMsg::Instantiate {
+                admin: None,
+                code_id: 456,
+                funds: vec![],
+                label: "".to_string(),
+                msg: to_binary(&TokenInstantiateMsg {
+                    name: "xyz USD junior".to_string(),
+                    symbol: "jxyzUST".to_string(),
+                    decimals: 6u8,
+                    initial_balances: vec![],
+                    mint: Some(MinterResponse {
+                        minter: MOCK_CONTRACT_ADDR.to_string(),
+                        cap: None,
+                    }),
+                })
+                .unwrap(),
+            }),
+            4,
+        )]
+    );
+
+    reply(deps.as_mut(), mock_env(), instantiate_reply(4, JUNIOR)).unwrap();
+    assert_eq!(
+        query_tranches(deps.as_ref()).unwrap().junior_token,
+        Some(JUNIOR.to_string())
+    );
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(OWNER, &[]),
+        enable_msg(),
+    );
+    match res {
+        Err(ContractError::JuniorTrancheEnabled {}) => {}
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0000", &[]),
+        ExecuteMsg::DepositJunior {},
+    );
+    match res {
+        Err(ContractError::ZeroDeposit(denom)) => assert_eq!(denom, "uusd"),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+}
+
+#[test]
+fn junior_tranche_takes_the_residual_yield_and_the_losses() {
+    let mut deps = tranche_market();
+
+    let msgs = deposit_junior(&mut deps, "addr0000", 1000);
+    assert_eq!(
+        msgs,
+        vec![
+            strategy_deposit_msg(1000),
+            junior_msg(Cw20ExecuteMsg::Mint {
+                recipient: "addr0000".to_string(),
+                amount: Uint128::from(1000u128),
+            }),
+        ]
+    );
+    deps.querier
+        .with_token_balance(MARKET_ATERRA, MOCK_CONTRACT_ADDR, 800);
+
+    // the yield is booked before minting, so the next depositor pays for it
+    deps.querier
+        .with_anchor_market(MARKET, MARKET_ATERRA, "uusd", Decimal256::percent(150));
+    let msgs = deposit_junior(&mut deps, "addr0001", 600);
+    assert_eq!(
+        msgs,
+        vec![
+            strategy_deposit_msg(600),
+            junior_msg(Cw20ExecuteMsg::Mint {
+                recipient: "addr0001".to_string(),
+                amount: Uint128::from(500u128),
+            }),
+        ]
+    );
+    assert_eq!(
+        query_tranches(deps.as_ref()).unwrap(),
+        TranchesResponse {
+            senior_supply: Uint128::zero(),
+            senior_exchange_rate: Decimal::one(),
+            junior_token: Some(JUNIOR.to_string()),
+            junior_supply: Uint128::from(1500u128),
+            junior_exchange_rate: Decimal::from_str("1.2").unwrap(),
+            junior_value: Uint128::from(1800u128),
+        }
+    );
+
+    // the loss is booked before burning, so the redeemer bears their share
+    deps.querier
+        .update_balance(MOCK_CONTRACT_ADDR, vec![Coin::new(0, "uusd")]);
+    deps.querier
+        .with_token_balance(MARKET_ATERRA, MOCK_CONTRACT_ADDR, 1200);
+    deps.querier
+        .with_anchor_market(MARKET, MARKET_ATERRA, "uusd", Decimal256::one());
+    let mut redeem = |token: &str| {
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(token, &[]),
+            ExecuteMsg::Receive(Cw20ReceiveMsg {
+                sender: "addr0001".to_string(),
+                amount: Uint128::from(500u128),
+                msg: to_binary(&Cw20HookMsg::RedeemJunior { to: None }).unwrap(),
+            }),
+        )
+    };
+    match redeem("token0000") {
+        Err(ContractError::Unauthorized {}) => {}
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+    let res = redeem(JUNIOR).unwrap();
+    assert_eq!(
+        res.messages,
+        vec![
+            junior_msg(Cw20ExecuteMsg::Burn {
+                amount: Uint128::from(500u128),
+            }),
+            SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: MARKET_ATERRA.to_string(),
+                funds: vec![],
+                msg: to_binary(&Cw20ExecuteMsg::Send {
+                    contract: MARKET.to_string(),
+                    amount: Uint128::from(401u128),
+                    msg: to_binary(&MarketHookMsg::RedeemStable {}).unwrap(),
+                })
+                .unwrap(),
+            })),
+            SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
+                to_address: "addr0001".to_string(),
+                amount: vec![Coin::new(400, "uusd")],
+            })),
+        ]
+    );
+    assert!(res.attributes.contains(&attr("redeem_amount", "400")));
+    assert!(res.attributes.contains(&attr("payout", "paid")));
+
+    let res = query_tranches(deps.as_ref()).unwrap();
+    assert_eq!(res.senior_exchange_rate, Decimal::one());
+    assert_eq!(res.junior_supply, Uint128::from(1000u128));
+    assert_eq!(res.junior_exchange_rate, Decimal::from_str("0.8").unwrap());
+}
+
+#[test]
+fn senior_interest_is_owed_before_it_is_settled() {
+    let mut deps = tranche_market();
+    deposit_junior(&mut deps, "addr0000", 1000);
+    deps.querier
+        .update_balance(MOCK_CONTRACT_ADDR, vec![Coin::new(1000, "uusd")]);
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0001", &[Coin::new(1000, "uusd")]),
+        ExecuteMsg::DepositStable {
+            recipient: None,
+            referrer: None,
+        },
+    )
+    .unwrap();
+    deps.querier
+        .update_balance(MOCK_CONTRACT_ADDR, vec![Coin::new(0, "uusd")]);
+    deps.querier
+        .with_token_balance(MARKET_ATERRA, MOCK_CONTRACT_ADDR, 1600);
+
+    // three periods of interest on the senior deposit come out of the 100
+    // earned although the depositor has not settled it yet
+    deps.querier.with_anchor_market(
+        MARKET,
+        MARKET_ATERRA,
+        "uusd",
+        Decimal256::from_ratio(21u64, 16u64),
+    );
+    let mut env = mock_env();
+    env.block.time = env.block.time.plus_seconds(3 * DURATION);
+    let res = execute(
+        deps.as_mut(),
+        env,
+        mock_info("keeper0000", &[]),
+        ExecuteMsg::SkimYield {},
+    )
+    .unwrap();
+    assert!(res.attributes.contains(&attr("earned", "100")));
+    assert!(res.attributes.contains(&attr("interest", "30")));
+    assert_eq!(
+        query_tranches(deps.as_ref()).unwrap().junior_exchange_rate,
+        Decimal::from_str("1.07").unwrap()
+    );
+}
+
+#[test]
+fn deposits_restart_a_wiped_junior_tranche() {
+    let mut deps = tranche_market();
+    deposit_junior(&mut deps, "addr0000", 1000);
+    deps.querier
+        .update_balance(MOCK_CONTRACT_ADDR, vec![Coin::new(0, "uusd")]);
+    deps.querier
+        .with_token_balance(MARKET_ATERRA, MOCK_CONTRACT_ADDR, 0);
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("keeper0000", &[]),
+        ExecuteMsg::SkimYield {},
+    )
+    .unwrap();
+    let res = query_tranches(deps.as_ref()).unwrap();
+    assert_eq!(res.junior_exchange_rate, Decimal::zero());
+    assert_eq!(res.junior_value, Uint128::zero());
+
+    // the wiped tokens are written off and the tranche restarts at par
+    let msgs = deposit_junior(&mut deps, "addr0001", 500);
+    assert_eq!(
+        msgs,
+        vec![
+            strategy_deposit_msg(500),
+            junior_msg(Cw20ExecuteMsg::Mint {
+                recipient: "addr0001".to_string(),
+                amount: Uint128::from(500u128),
+            }),
+        ]
+    );
+    let res = query_tranches(deps.as_ref()).unwrap();
+    assert_eq!(res.junior_supply, Uint128::from(500u128));
+    assert_eq!(res.junior_exchange_rate, Decimal::one());
+    assert_eq!(res.junior_value, Uint128::from(500u128));
+}
@@ -0,0 +1,203 @@
+use crate::contract::{execute, query_state};
+use crate::error::ContractError;
+use crate::state::{Cw20HookMsg, ExecuteMsg};
+use crate::testing::mock_querier::{
+    instantiate_market, mock_dependencies, mock_instantiate_msg, MockDeps, ATERRA,
+};
+use crate::unbonding::query_unbonding;
+
+use cosmwasm_std::testing::{mock_env, mock_info, MOCK_CONTRACT_ADDR};
+use cosmwasm_std::{attr, to_binary, BankMsg, Coin, CosmosMsg, SubMsg, Uint128};
+use cw20::Cw20ReceiveMsg;
+
+fn redeem_msg(amount: u128, msg: Option<&str>) -> ExecuteMsg {
+    ExecuteMsg::Receive(Cw20ReceiveMsg {
+        sender: "addr0000".to_string(),
+        amount: Uint128::from(amount),
+        msg: to_binary(&Cw20HookMsg::RedeemNStable {
+            to: msg.map(|_| "contract0000".to_string()),
+            msg: msg.map(|msg| to_binary(msg).unwrap()),
+        })
+        .unwrap(),
+    })
+}
+
+fn unbonding_market() -> MockDeps {
+    let mut deps = mock_dependencies(&[]);
+    let mut msg = mock_instantiate_msg();
+    msg.unbonding_period = Some(100);
+    instantiate_market(&mut deps, msg);
+
+    deps.querier
+        .update_balance(MOCK_CONTRACT_ADDR, vec![Coin::new(1000, "uusd")]);
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0000", &[Coin::new(1000, "uusd")]),
+        ExecuteMsg::DepositStable {
+            recipient: None,
+            referrer: None,
+        },
+    )
+    .unwrap();
+    deps
+}
+
+#[test]
+fn unbonding_rejects_callbacks() {
+    let mut deps = unbonding_market();
+
+    // callbacks can not run once the funds are released
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(ATERRA, &[]),
+        redeem_msg(100, Some("callback")),
+    );
+    match res {
+        Err(ContractError::UnbondingCallback {}) => {}
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+}
+
+#[test]
+fn redemptions_unbond_before_claim() {
+    let mut deps = unbonding_market();
+
+    for _ in 0..10 {
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(ATERRA, &[]),
+            redeem_msg(90, None),
+        )
+        .unwrap();
+        assert_eq!(res.messages.len(), 1);
+        assert!(res.attributes.contains(&attr("payout", "unbonding")));
+    }
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(ATERRA, &[]),
+        redeem_msg(90, None),
+    );
+    match res {
+        Err(ContractError::TooManyUnbondings {}) => {}
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    // the unbonding funds are not available to anybody else
+    let state = query_state(deps.as_ref()).unwrap();
+    assert_eq!(state.total_unbonding, Uint128::from(900u128));
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0000", &[]),
+        ExecuteMsg::ClaimUnbonded {},
+    );
+    match res {
+        Err(ContractError::NothingToClaim {}) => {}
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let mut env = mock_env();
+    env.block.time = env.block.time.plus_seconds(100);
+    let unbonding = query_unbonding(deps.as_ref(), env.clone(), "addr0000".to_string()).unwrap();
+    assert_eq!(unbonding.claimable, Uint128::from(900u128));
+
+    let res = execute(
+        deps.as_mut(),
+        env,
+        mock_info("addr0000", &[]),
+        ExecuteMsg::ClaimUnbonded {},
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
+            to_address: "addr0000".to_string(),
+            amount: vec![Coin::new(900, "uusd")],
+        }))]
+    );
+    assert!(res.attributes.contains(&attr("queued", "false")));
+    assert_eq!(
+        query_state(deps.as_ref()).unwrap().total_unbonding,
+        Uint128::zero()
+    );
+}
+
+#[test]
+fn redemptions_for_others_use_the_redeemers_slots() {
+    let mut deps = unbonding_market();
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0001", &[Coin::new(100, "uusd")]),
+        ExecuteMsg::DepositStable {
+            recipient: None,
+            referrer: None,
+        },
+    )
+    .unwrap();
+
+    // dust redemptions paying the victim fill the griefer's slots
+    let dust_msg = || {
+        ExecuteMsg::Receive(Cw20ReceiveMsg {
+            sender: "addr0001".to_string(),
+            amount: Uint128::from(1u128),
+            msg: to_binary(&Cw20HookMsg::RedeemNStable {
+                to: Some("addr0000".to_string()),
+                msg: None,
+            })
+            .unwrap(),
+        })
+    };
+    for _ in 0..10 {
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(ATERRA, &[]),
+            dust_msg(),
+        )
+        .unwrap();
+    }
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(ATERRA, &[]),
+        dust_msg(),
+    );
+    match res {
+        Err(ContractError::TooManyUnbondings {}) => {}
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    // the victim still redeems
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(ATERRA, &[]),
+        redeem_msg(100, None),
+    )
+    .unwrap();
+    let unbonding = query_unbonding(deps.as_ref(), mock_env(), "addr0000".to_string()).unwrap();
+    assert_eq!(unbonding.unbondings.len(), 1);
+
+    // the griefer's claim pays the recipient
+    let mut env = mock_env();
+    env.block.time = env.block.time.plus_seconds(100);
+    let res = execute(
+        deps.as_mut(),
+        env,
+        mock_info("addr0001", &[]),
+        ExecuteMsg::ClaimUnbonded {},
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
+            to_address: "addr0000".to_string(),
+            amount: vec![Coin::new(10, "uusd")],
+        }))]
+    );
+}
@@ -0,0 +1,282 @@
+use std::cmp::min;
+
+use cosmwasm_std::{
+    attr, to_binary, Addr, CosmosMsg, Decimal, Deps, DepsMut, Env, MessageInfo, Response,
+    StdResult, SubMsg, Uint128, WasmMsg,
+};
+use cw20::{Cw20ExecuteMsg, MinterResponse};
+use terraswap::token::InstantiateMsg as TokenInstantiateMsg;
+
+use crate::allowlist::assert_allowlisted;
+use crate::contract::DURATION;
+use crate::deposit::withdraw_stable;
+use crate::error::ContractError;
+use crate::harvest::harvest_rewards;
+use crate::helpers::{compound_interest, receipt_to_stable, stable_to_receipt};
+use crate::queue::fill_queue;
+use crate::slashing::reconcile;
+use crate::staking::delegate_msgs;
+use crate::state::{
+    read_config, read_state, store_config, store_state, Config, State, TranchesResponse,
+};
+use crate::strategy::{allocate_msgs, skim_yield};
+
+/// Interest owed on every senior receipt token since the last checkpoint,
+/// settled into deposit balances or not, compounded per `DURATION`
+pub fn senior_interest(config: &Config, state: &mut State, time: u64) -> Uint128 {
+    // state stored before the checkpoint existed starts accruing now
+    if state.yield_checkpoint_time == 0 {
+        state.yield_checkpoint_time = time;
+    }
+    let days = (time - state.yield_checkpoint_time) / DURATION;
+    state.yield_checkpoint_time += days * DURATION;
+
+    let liability = receipt_to_stable(state.tvl, state.exchange_rate);
+    compound_interest(liability, config.interest_rate, days)
+}
+
+/// Splits `earned` yield against the senior `interest` accrued over the same
+/// period: the junior tranche takes the residual and absorbs any shortfall.
+/// Returns the residual when there is no junior tranche.
+pub fn waterfall(state: &mut State, earned: Uint128, interest: Uint128) -> Uint128 {
+    let residual = earned.saturating_sub(interest);
+    if state.junior_supply.is_zero() {
+        return residual;
+    }
+
+    if residual.is_zero() {
+        absorb_loss(state, interest - earned);
+    } else {
+        state.junior_exchange_rate =
+            state.junior_exchange_rate + Decimal::from_ratio(residual, state.junior_supply);
+    }
+    Uint128::zero()
+}
+
+/// Takes `loss` out of the junior tranche, returning the part exceeding it
+pub fn absorb_loss(state: &mut State, loss: Uint128) -> Uint128 {
+    let junior_value = receipt_to_stable(state.junior_supply, state.junior_exchange_rate);
+    let absorbed = min(loss, junior_value);
+    if absorbed.is_zero() {
+        return loss;
+    }
+
+    let haircut = Decimal::from_ratio(absorbed, state.junior_supply);
+    state.junior_exchange_rate = if haircut < state.junior_exchange_rate {
+        state.junior_exchange_rate - haircut
+    } else {
+        Decimal::zero()
+    };
+    loss - absorbed
+}
+
+/// Books the yield and losses so far into the exchange rates, so junior
+/// tokens cannot be minted or burned at a rate about to change
+fn checkpoint(mut deps: DepsMut, env: &Env, config: &Config) -> Result<Response, ContractError> {
+    let mut operations: Vec<Response> = vec![];
+    if config.native_staking {
+        operations.push(reconcile(deps.branch(), env.clone())?);
+        match harvest_rewards(deps.branch(), env.clone(), None) {
+            Ok(response) => operations.push(response),
+            Err(ContractError::NothingToHarvest {}) => {}
+            Err(err) => return Err(err),
+        }
+    } else {
+        operations.push(skim_yield(deps, env.clone())?);
+    }
+
+    let mut response = Response::new();
+    for res in operations.into_iter() {
+        response = response
+            .add_submessages(res.messages)
+            .add_events(res.events);
+    }
+    Ok(response)
+}
+
+/// Instantiates the junior tranche token, registered by reply 4
+pub fn enable_junior_tranche(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    token_code_id: u64,
+) -> Result<Response, ContractError> {
+    let config: Config = read_config(deps.storage)?;
+    if deps.api.addr_canonicalize(info.sender.as_str())? != config.owner_addr {
+        return Err(ContractError::Unauthorized {});
+    }
+    if config.junior_token.is_some() {
+        return Err(ContractError::JuniorTrancheEnabled {});
+    }
+
+    Ok(Response::new()
+        .add_submessage(SubMsg::reply_on_success(
+            CosmosMsg::Wasm(WasmMsg::Instantiate {
+                admin: None,
+                code_id: token_code_id,
+                funds: vec![],
+                label: "".to_string(),
+                msg: to_binary(&TokenInstantiateMsg {
+                    name: format!("xyz {} junior", config.stable_denom[1..].to_uppercase()),
+                    symbol: format!(
+                        "jxyz{}T",
+                        config.stable_denom[1..(config.stable_denom.len() - 1)].to_uppercase()
+                    ),
+                    decimals: 6u8,
+                    initial_balances: vec![],
+                    mint: Some(MinterResponse {
+                        minter: env.contract.address.to_string(),
+                        cap: None,
+                    }),
+                })?,
+            }),
+            4,
+        ))
+        .add_attributes(vec![attr("action", "enable_junior_tranche")]))
+}
+
+pub fn register_junior_token(deps: DepsMut, token_addr: Addr) -> Result<Response, ContractError> {
+    let mut config: Config = read_config(deps.storage)?;
+    if config.junior_token.is_some() {
+        return Err(ContractError::JuniorTrancheEnabled {});
+    }
+
+    config.junior_token = Some(deps.api.addr_canonicalize(token_addr.as_str())?);
+    store_config(deps.storage, &config)?;
+
+    Ok(Response::new().add_attributes(vec![attr("junior_token", token_addr)]))
+}
+
+/// Deposits stable coins into the junior tranche, minting junior tokens at
+/// the junior exchange rate after a checkpoint
+pub fn deposit_junior(
+    mut deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    let config: Config = read_config(deps.storage)?;
+    let junior_token = match &config.junior_token {
+        Some(junior_token) => deps.api.addr_humanize(junior_token)?,
+        None => return Err(ContractError::JuniorTrancheDisabled {}),
+    };
+    assert_allowlisted(deps.as_ref(), &config, &info.sender)?;
+
+    let deposit_amount: Uint128 = info
+        .funds
+        .iter()
+        .find(|c| c.denom == config.stable_denom)
+        .map(|c| c.amount)
+        .unwrap_or_else(Uint128::zero);
+    if deposit_amount.is_zero() {
+        return Err(ContractError::ZeroDeposit(config.stable_denom));
+    }
+
+    let checkpoint = checkpoint(deps.branch(), &env, &config)?;
+    let mut state: State = read_state(deps.storage)?;
+    // a tranche wiped out by losses restarts at par, writing off its tokens
+    if receipt_to_stable(state.junior_supply, state.junior_exchange_rate).is_zero() {
+        state.junior_supply = Uint128::zero();
+        state.junior_exchange_rate = Decimal::one();
+    }
+    let mint_amount = stable_to_receipt(deposit_amount, state.junior_exchange_rate);
+    state.junior_supply += mint_amount;
+    store_state(deps.storage, &state)?;
+
+    let (withdraw_msgs, queue_filled) = fill_queue(deps.branch(), &env, &config)?;
+    let yield_msgs: Vec<CosmosMsg> = if config.native_staking {
+        delegate_msgs(
+            deps.branch(),
+            &env,
+            &config,
+            deposit_amount.saturating_sub(queue_filled),
+        )?
+    } else {
+        allocate_msgs(deps.branch(), &env, &config, Uint128::zero())?.0
+    };
+
+    Ok(checkpoint
+        .add_messages(withdraw_msgs)
+        .add_messages(yield_msgs)
+        .add_message(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: junior_token.to_string(),
+            funds: vec![],
+            msg: to_binary(&Cw20ExecuteMsg::Mint {
+                recipient: info.sender.to_string(),
+                amount: mint_amount,
+            })?,
+        }))
+        .add_attributes(vec![
+            attr("action", "deposit_junior"),
+            attr("depositor", info.sender),
+            attr("deposit_amount", deposit_amount),
+            attr("mint_amount", mint_amount),
+        ]))
+}
+
+/// Burns `amount` junior tokens sent by `sender`, paying out their value
+/// after a checkpoint
+pub fn redeem_junior(
+    mut deps: DepsMut,
+    env: Env,
+    sender: Addr,
+    amount: Uint128,
+    recipient: Addr,
+) -> Result<Response, ContractError> {
+    let config: Config = read_config(deps.storage)?;
+    let junior_token = match &config.junior_token {
+        Some(junior_token) => deps.api.addr_humanize(junior_token)?,
+        None => return Err(ContractError::JuniorTrancheDisabled {}),
+    };
+
+    let checkpoint = checkpoint(deps.branch(), &env, &config)?;
+    let mut state: State = read_state(deps.storage)?;
+    let redeem_amount = receipt_to_stable(amount, state.junior_exchange_rate);
+    state.junior_supply -= amount;
+    store_state(deps.storage, &state)?;
+
+    let mut msgs: Vec<CosmosMsg> = vec![CosmosMsg::Wasm(WasmMsg::Execute {
+        contract_addr: junior_token.to_string(),
+        funds: vec![],
+        msg: to_binary(&Cw20ExecuteMsg::Burn { amount })?,
+    })];
+    let payout_status = if redeem_amount.is_zero() {
+        "none"
+    } else {
+        let (payout, payout_status) = withdraw_stable(
+            deps,
+            &env,
+            &config,
+            &sender,
+            &recipient,
+            redeem_amount,
+            None,
+        )?;
+        msgs.extend(payout);
+        payout_status
+    };
+
+    Ok(checkpoint.add_messages(msgs).add_attributes(vec![
+        attr("action", "redeem_junior"),
+        attr("redeemer", sender),
+        attr("recipient", recipient),
+        attr("redeem_amount", redeem_amount),
+        attr("payout", payout_status),
+    ]))
+}
+
+pub fn query_tranches(deps: Deps) -> StdResult<TranchesResponse> {
+    let config: Config = read_config(deps.storage)?;
+    let state: State = read_state(deps.storage)?;
+
+    Ok(TranchesResponse {
+        senior_supply: state.tvl,
+        senior_exchange_rate: state.exchange_rate,
+        junior_token: match config.junior_token {
+            Some(junior_token) => Some(deps.api.addr_humanize(&junior_token)?.to_string()),
+            None => None,
+        },
+        junior_supply: state.junior_supply,
+        junior_exchange_rate: state.junior_exchange_rate,
+        junior_value: receipt_to_stable(state.junior_supply, state.junior_exchange_rate),
+    })
+}
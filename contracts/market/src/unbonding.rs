@@ -0,0 +1,116 @@
+use cosmwasm_std::{
+    attr, Addr, CosmosMsg, Deps, DepsMut, Env, MessageInfo, Response, StdResult, Uint128,
+};
+
+use crate::error::ContractError;
+use crate::queue::pay_or_enqueue;
+use crate::state::{
+    read_config, read_state, read_unbondings, store_state, store_unbondings, Config, State,
+    Unbonding, UnbondingEntryResponse, UnbondingResponse,
+};
+
+/// Maximum number of unbondings a single redeemer may have in flight
+const MAX_UNBONDINGS: usize = 10;
+
+/// Starts an unbonding of `amount` for `recipient`. It is held by the
+/// redeemer, so redemptions paying others can not use up their slots.
+pub fn start_unbonding(
+    deps: DepsMut,
+    env: &Env,
+    config: &Config,
+    redeemer: &Addr,
+    recipient: &Addr,
+    amount: Uint128,
+) -> Result<u64, ContractError> {
+    let ident_raw = deps.api.addr_canonicalize(redeemer.as_str())?;
+    let mut unbondings = read_unbondings(deps.storage, &ident_raw);
+    if unbondings.len() >= MAX_UNBONDINGS {
+        return Err(ContractError::TooManyUnbondings {});
+    }
+
+    let release_time = env.block.time.seconds() + config.unbonding_period;
+    unbondings.push(Unbonding {
+        recipient: deps.api.addr_canonicalize(recipient.as_str())?,
+        amount,
+        release_time,
+    });
+    store_unbondings(deps.storage, &ident_raw, &unbondings)?;
+
+    let mut state: State = read_state(deps.storage)?;
+    state.total_unbonding += amount;
+    store_state(deps.storage, &state)?;
+    Ok(release_time)
+}
+
+pub fn claim_unbonded(
+    mut deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    let config: Config = read_config(deps.storage)?;
+    let time = env.block.time.seconds();
+    let ident_raw = deps.api.addr_canonicalize(info.sender.as_str())?;
+
+    let (matured, unbonding): (Vec<Unbonding>, Vec<Unbonding>) =
+        read_unbondings(deps.storage, &ident_raw)
+            .into_iter()
+            .partition(|unbonding| unbonding.release_time <= time);
+    let amount: Uint128 = matured.iter().map(|unbonding| unbonding.amount).sum();
+    if amount.is_zero() {
+        return Err(ContractError::NothingToClaim {});
+    }
+
+    store_unbondings(deps.storage, &ident_raw, &unbonding)?;
+    let mut state: State = read_state(deps.storage)?;
+    state.total_unbonding -= amount;
+    store_state(deps.storage, &state)?;
+
+    // each recipient is paid its matured unbondings
+    let mut recipients: Vec<(Addr, Uint128)> = vec![];
+    for unbonding in matured.iter() {
+        let recipient = deps.api.addr_humanize(&unbonding.recipient)?;
+        match recipients.iter_mut().find(|(addr, _)| *addr == recipient) {
+            Some((_, total)) => *total += unbonding.amount,
+            None => recipients.push((recipient, unbonding.amount)),
+        }
+    }
+
+    let mut msgs: Vec<CosmosMsg> = vec![];
+    let mut queued = false;
+    for (recipient, total) in recipients.iter() {
+        let (payout, queued_amount) =
+            pay_or_enqueue(deps.branch(), &env, &config, recipient, *total, None)?;
+        msgs.extend(payout);
+        queued |= !queued_amount.is_zero();
+    }
+    Ok(Response::new().add_messages(msgs).add_attributes(vec![
+        attr("action", "claim_unbonded"),
+        attr("claimer", info.sender),
+        attr("amount", amount),
+        attr("queued", queued.to_string()),
+    ]))
+}
+
+pub fn query_unbonding(deps: Deps, env: Env, address: String) -> StdResult<UnbondingResponse> {
+    let time = env.block.time.seconds();
+    let unbondings = read_unbondings(deps.storage, &deps.api.addr_canonicalize(&address)?);
+    let claimable = unbondings
+        .iter()
+        .filter(|unbonding| unbonding.release_time <= time)
+        .map(|unbonding| unbonding.amount)
+        .sum();
+
+    Ok(UnbondingResponse {
+        unbondings: unbondings
+            .into_iter()
+            .map(|unbonding| {
+                Ok(UnbondingEntryResponse {
+                    recipient: deps.api.addr_humanize(&unbonding.recipient)?.to_string(),
+                    amount: unbonding.amount,
+                    release_time: unbonding.release_time,
+                })
+            })
+            .collect::<StdResult<Vec<UnbondingEntryResponse>>>()?,
+        claimable,
+    })
+}
@@ -59,11 +59,13 @@ def upload() -> str:
 
 payloads = [
     {
+        "owner_addr": test1.key.acc_address,
         "stable_denom": "uusd",
         "aterra_code_id": 1572,
         "interest": "0.000382982750338989",
     },
     {
+        "owner_addr": test1.key.acc_address,
         "stable_denom": "uluna",
         "aterra_code_id": 1572,
         "interest": "0.000382982750338989",